@@ -1,8 +1,16 @@
 //! Analysis module for test summaries and SQL statistics
 
-use crate::log_parser::ApiLogEntry;
-use colored::Colorize;
+use crate::cucumber::{CucumberReport, Status};
+use crate::diff::{json_diff, parse_value, render_diff};
+use crate::gherkin::Feature;
+use crate::karate_http::HttpExchange;
+use crate::log_parser::{ApiLogEntry, KarateFailure, LogLevel};
+use crate::redact::Redactor;
+use crate::scenarios::{find_scenario, same_feature, RunModel};
+use crate::stacktrace::StackTrace;
+use crate::theme::Theme;
 use std::collections::HashMap;
+use std::time::Duration;
 
 /// SQL query statistics
 pub struct SqlStats {
@@ -11,6 +19,8 @@ pub struct SqlStats {
     pub total_rows_affected: i64,
     pub error_count: u32,
     pub total_elapsed_ms: f64,
+    /// Queries whose `elapsed` is not a valid duration (left out of the total)
+    pub unparsed_elapsed: u32,
     pub slowest_queries: Vec<SqlQuery>,
 }
 
@@ -30,6 +40,7 @@ impl SqlStats {
             total_rows_affected: 0,
             error_count: 0,
             total_elapsed_ms: 0.0,
+            unparsed_elapsed: 0,
             slowest_queries: Vec::new(),
         }
     }
@@ -54,12 +65,16 @@ impl SqlStats {
             }
 
             // Track errors
-            if entry.err.is_some() || entry.level == "ERROR" {
+            if entry.err.is_some() || entry.log_level() == LogLevel::Error {
                 self.error_count += 1;
             }
 
-            // Parse elapsed time
-            let elapsed = parse_elapsed(&entry.elapsed);
+            // Parse elapsed time; an unparseable one is counted instead of taken as 0
+            let elapsed = entry.elapsed_ms();
+            if entry.elapsed.is_some() && elapsed.is_none() {
+                self.unparsed_elapsed += 1;
+            }
+            let elapsed = elapsed.unwrap_or(0.0);
             self.total_elapsed_ms += elapsed;
 
             // Track slowest queries (keep top 5)
@@ -78,38 +93,51 @@ impl SqlStats {
     }
 
     /// Print SQL statistics summary
-    pub fn print_summary(&self) {
+    pub fn print_summary(&self, theme: &Theme) {
         if self.total_queries == 0 {
             return;
         }
 
+        let palette = &theme.palette;
+
         println!();
-        println!("{}", "📊 SQL Statistics".bright_cyan().bold());
-        println!("{}", "─".repeat(40).bright_black());
+        println!(
+            "{}",
+            palette
+                .heading
+                .paint(&Theme::prefixed(&theme.prefixes.stats, "SQL Statistics"))
+        );
+        println!("{}", palette.muted.paint(&theme.rule(40)));
         println!(
             "  Total Queries: {}",
-            self.total_queries.to_string().bright_white()
+            palette.text.paint(&self.total_queries.to_string())
         );
         println!(
             "  Total Rows Affected: {}",
-            self.total_rows_affected.to_string().bright_white()
+            palette.text.paint(&self.total_rows_affected.to_string())
         );
         println!(
             "  Query Errors: {}",
             if self.error_count > 0 {
-                self.error_count.to_string().red()
+                palette.error.paint(&self.error_count.to_string())
             } else {
-                self.error_count.to_string().green()
+                palette.success.paint(&self.error_count.to_string())
             }
         );
         println!(
             "  Total Time: {:.2}ms",
             self.total_elapsed_ms
         );
+        if self.unparsed_elapsed > 0 {
+            println!(
+                "  Unparsed Durations: {}",
+                palette.warn.paint(&self.unparsed_elapsed.to_string())
+            );
+        }
 
         if !self.queries_by_type.is_empty() {
             println!();
-            println!("  {}", "By Type:".bright_yellow());
+            println!("  {}", palette.section.paint("By Type:"));
             for (query_type, count) in &self.queries_by_type {
                 println!("    {}: {}", query_type, count);
             }
@@ -117,7 +145,7 @@ impl SqlStats {
 
         if !self.slowest_queries.is_empty() {
             println!();
-            println!("  {}", "Slowest Queries:".bright_yellow());
+            println!("  {}", palette.section.paint("Slowest Queries:"));
             for (i, query) in self.slowest_queries.iter().take(5).enumerate() {
                 let truncated = if query.sql.len() > 60 {
                     format!("{}...", &query.sql[..60])
@@ -128,7 +156,7 @@ impl SqlStats {
                     "    {}. {:.2}ms - {}",
                     i + 1,
                     query.elapsed_ms,
-                    truncated.bright_black()
+                    palette.muted.paint(&truncated)
                 );
             }
         }
@@ -149,6 +177,18 @@ pub struct TestSummary {
     pub failed: u32,
     pub skipped: u32,
     pub failed_features: Vec<FailedFeature>,
+    /// Counts and failures come from Karate's cucumber JSON report rather than stdout
+    pub from_report: bool,
+    /// Differences between what was seen live and what the report says
+    pub discrepancies: Vec<String>,
+    /// Summed scenario durations from the report
+    pub scenario_time: Option<Duration>,
+    /// Per-feature and per-scenario results
+    pub model: RunModel,
+    /// Panics and stack traces the API printed
+    pub api_errors: Vec<StackTrace>,
+    /// Whether the final run summary line was seen (per-feature lines add up until then)
+    run_summary_seen: bool,
 }
 
 #[derive(Clone)]
@@ -157,6 +197,16 @@ pub struct FailedFeature {
     pub line_number: Option<u32>,
     pub error_message: String,
     pub url: Option<String>,
+    /// Scenario title, when known
+    pub scenario: Option<String>,
+    /// Feature and scenario tags
+    pub tags: Vec<String>,
+    /// The failing step, e.g. `* match response.id == 1`
+    pub step: Option<String>,
+    /// HTTP calls the scenario made, from Karate's DEBUG logging
+    pub calls: Vec<HttpExchange>,
+    pub expected: Option<String>,
+    pub actual: Option<String>,
 }
 
 impl TestSummary {
@@ -168,17 +218,31 @@ impl TestSummary {
             failed: 0,
             skipped: 0,
             failed_features: Vec::new(),
+            from_report: false,
+            discrepancies: Vec::new(),
+            scenario_time: None,
+            model: RunModel::new(),
+            api_errors: Vec::new(),
+            run_summary_seen: false,
         }
     }
 
     /// Update summary from Karate output line
     pub fn update_from_line(&mut self, line: &str) {
-        // Parse scenario summary line
-        // Example: "scenarios:  2 | passed:  1 | failed:  1 | time: 0.4675"
+        // Parse scenario summary lines
+        // Per feature: "scenarios:  2 | passed:  1 | failed:  1 | time: 0.4675"
+        // End of run:  "scenarios:  5 | passed:  4 | failed:  1"
         if let Some(result) = crate::log_parser::parse_karate_summary(line) {
-            self.total_scenarios = result.total_scenarios;
-            self.passed = result.passed;
-            self.failed = result.failed;
+            if result.time_secs.is_none() {
+                self.run_summary_seen = true;
+                self.total_scenarios = result.total_scenarios;
+                self.passed = result.passed;
+                self.failed = result.failed;
+            } else if !self.run_summary_seen {
+                self.total_scenarios += result.total_scenarios;
+                self.passed += result.passed;
+                self.failed += result.failed;
+            }
         }
 
         // Parse feature count
@@ -193,66 +257,230 @@ impl TestSummary {
         }
     }
 
-    /// Track a failed feature
-    pub fn track_failure(&mut self, feature: &str, error: &str, url: Option<String>) {
-        // Extract line number from feature string (e.g., "file.feature:40")
-        let (file, line_num) = if let Some(pos) = feature.rfind(':') {
-            let line = feature[pos + 1..].parse().ok();
-            (feature[..pos].to_string(), line)
-        } else {
-            (feature.to_string(), None)
+    /// Track a failure assembled from Karate's output, with the calls its scenario made
+    ///
+    /// The scenario is named from the parsed `features` right away, so the live view and
+    /// an interrupted run have it without waiting for `annotate`.
+    pub fn track_failure(&mut self, failure: &KarateFailure, calls: &[HttpExchange], features: &[Feature]) {
+        if failure.line_number > 0 {
+            self.model
+                .failure_at(&failure.feature_file, failure.line_number, failure.step.as_deref(), features);
+        }
+        let mut tracked = FailedFeature {
+            feature_file: failure.feature_file.clone(),
+            line_number: (failure.line_number > 0).then_some(failure.line_number),
+            error_message: failure.assertion.clone(),
+            url: failure.url.clone(),
+            scenario: None,
+            tags: Vec::new(),
+            step: failure.step.clone(),
+            calls: calls.to_vec(),
+            expected: failure.expected.clone(),
+            actual: failure.actual.clone(),
         };
+        name_failure(&mut tracked, features);
+        self.failed_features.push(tracked);
+    }
+
+    /// Take counts from the cucumber report and merge its failures with the live ones;
+    /// report text goes through `redactor` like the live output did
+    pub fn reconcile(&mut self, report: &CucumberReport, redactor: &Redactor) {
+        let live = (self.total_scenarios, self.passed, self.failed);
+        let reported = (
+            report.scenarios().count() as u32,
+            report.count(Status::Passed),
+            report.count(Status::Failed),
+        );
+        if live != reported && live.0 > 0 {
+            self.discrepancies.push(format!(
+                "Live output counted {} scenarios ({} passed, {} failed); the report has {} ({} passed, {} failed)",
+                live.0, live.1, live.2, reported.0, reported.1, reported.2
+            ));
+        }
+
+        self.total_features = report.features.len() as u32;
+        (self.total_scenarios, self.passed, self.failed) = reported;
+        self.skipped = report.count(Status::Skipped);
+        self.scenario_time = Some(report.scenarios().map(|(_, s)| s.duration()).sum());
+        self.model.apply_report(&report.features);
+        self.from_report = true;
+
+        let mut missed = 0;
+        for (feature, scenario) in report.scenarios().filter(|(_, s)| s.status() == Status::Failed) {
+            let step = scenario.failing_step();
+            let message = step
+                .and_then(|s| s.error_message.as_deref())
+                .and_then(|m| m.lines().map(str::trim).find(|l| !l.is_empty()))
+                .map(|m| redactor.redact_line(m))
+                .unwrap_or_default();
+
+            let tags: Vec<String> = feature.tags.iter().chain(&scenario.tags).cloned().collect();
+            let step_text = step.map(|s| redactor.redact_line(&format!("{} {}", s.keyword, s.name)));
+
+            let live = self.failed_features.iter_mut().find(|f| {
+                same_feature(&f.feature_file, &feature.uri)
+                    && f.line_number.is_none_or(|line| scenario.contains_line(line))
+                    && f.scenario.is_none()
+            });
+            match live {
+                Some(failure) => {
+                    failure.scenario = Some(scenario.name.clone());
+                    failure.tags = tags;
+                    failure.step = step_text;
+                    if failure.error_message.is_empty() {
+                        failure.error_message = message;
+                    }
+                }
+                None => {
+                    missed += 1;
+                    self.failed_features.push(FailedFeature {
+                        feature_file: if feature.uri.is_empty() {
+                            feature.name.clone()
+                        } else {
+                            feature.uri.clone()
+                        },
+                        line_number: Some(step.map_or(scenario.line, |s| s.line)),
+                        error_message: message,
+                        url: None,
+                        scenario: Some(scenario.name.clone()),
+                        tags,
+                        step: step_text,
+                        calls: Vec::new(),
+                        expected: None,
+                        actual: None,
+                    });
+                }
+            }
+        }
+        if missed > 0 && live.0 > 0 {
+            self.discrepancies.push(format!(
+                "{} failure{} found only in the report",
+                missed,
+                if missed == 1 { "" } else { "s" }
+            ));
+        }
+    }
 
-        self.failed_features.push(FailedFeature {
-            feature_file: file,
-            line_number: line_num,
-            error_message: error.to_string(),
-            url,
-        });
+    /// Name failures Karate only reported as `file.feature:40`, using the parsed feature files
+    pub fn annotate(&mut self, features: &[Feature]) {
+        for failure in &mut self.failed_features {
+            name_failure(failure, features);
+        }
+
+        self.model.time_single_scenarios(features);
+        for record in &mut self.model.features {
+            for scenario in record.scenarios.iter_mut().filter(|s| s.name.is_empty()) {
+                if let Some((feature, parsed)) = find_scenario(features, &record.path, scenario.line) {
+                    scenario.name = parsed.name.clone();
+                    scenario.tags = feature.tags.iter().chain(&parsed.tags).cloned().collect();
+                }
+            }
+        }
     }
 
     /// Print test summary
-    pub fn print_summary(&self) {
+    pub fn print_summary(&self, theme: &Theme) {
+        let palette = &theme.palette;
+
         println!();
-        println!("{}", "🥋 Test Summary".bright_cyan().bold());
-        println!("{}", "─".repeat(40).bright_black());
+        println!(
+            "{}",
+            palette
+                .heading
+                .paint(&Theme::prefixed(&theme.prefixes.banner, "Test Summary"))
+        );
+        println!("{}", palette.muted.paint(&theme.rule(40)));
 
         println!(
             "  Features: {}",
-            self.total_features.to_string().bright_white()
+            palette.text.paint(&self.total_features.to_string())
         );
+        if self.skipped > 0 {
+            println!("  Skipped: {}", palette.warn.paint(&self.skipped.to_string()));
+        }
         println!(
             "  Scenarios: {} total, {} passed, {} failed",
-            self.total_scenarios.to_string().bright_white(),
-            self.passed.to_string().green(),
+            palette.text.paint(&self.total_scenarios.to_string()),
+            palette.success.paint(&self.passed.to_string()),
             if self.failed > 0 {
-                self.failed.to_string().red().bold()
+                palette.error_strong.paint(&self.failed.to_string())
             } else {
-                self.failed.to_string().green()
+                palette.success.paint(&self.failed.to_string())
             }
         );
 
+        if let Some(time) = self.scenario_time {
+            println!("  Scenario time: {}", palette.text.paint(&format!("{:.1}s", time.as_secs_f64())));
+        }
+        if self.from_report {
+            println!("  {}", palette.muted.paint("Results from the Karate cucumber report"));
+        }
+        for note in &self.discrepancies {
+            println!("  {} {}", palette.warn.paint(&theme.prefixes.warn), palette.muted.paint(note));
+        }
+
         if !self.failed_features.is_empty() {
             println!();
-            println!("  {}", "Failed Tests:".red().bold());
+            println!("  {}", palette.error_strong.paint("Failed Tests:"));
             for failure in &self.failed_features {
                 println!(
                     "    {} {}{}",
-                    "✗".red(),
-                    failure.feature_file.bright_white(),
-                    failure
-                        .line_number
-                        .map(|n| format!(":{}", n))
-                        .unwrap_or_default()
-                        .bright_black()
+                    palette.error.paint(&theme.borders.fail_marker),
+                    palette.text.paint(&failure.feature_file),
+                    palette.muted.paint(
+                        &failure
+                            .line_number
+                            .map(|n| format!(":{}", n))
+                            .unwrap_or_default()
+                    )
                 );
+                if let Some(scenario) = &failure.scenario {
+                    let tags = failure.tags.join(" ");
+                    println!("      {} {}", palette.text.paint(scenario), palette.muted.paint(&tags));
+                }
+                if let Some(step) = &failure.step {
+                    println!("      {}", palette.error.paint(step));
+                }
                 if !failure.error_message.is_empty() {
-                    let truncated = if failure.error_message.len() > 80 {
-                        format!("{}...", &failure.error_message[..80])
-                    } else {
-                        failure.error_message.clone()
+                    let truncated = match failure.error_message.char_indices().nth(80) {
+                        Some((end, _)) => format!("{}...", &failure.error_message[..end]),
+                        None => failure.error_message.clone(),
                     };
-                    println!("      {}", truncated.bright_black());
+                    println!("      {}", palette.muted.paint(&truncated));
+                }
+                if !failure.calls.is_empty() {
+                    println!("      {}", palette.section.paint("Karate calls:"));
+                    // The last calls are the ones that led to the failure
+                    let skip = failure.calls.len().saturating_sub(5);
+                    for call in &failure.calls[skip..] {
+                        println!("        {}", palette.muted.paint(&call.summary()));
+                    }
+                }
+                if let (Some(expected), Some(actual)) = (&failure.expected, &failure.actual) {
+                    let diff = json_diff(&parse_value(expected), &parse_value(actual));
+                    if !diff.is_empty() {
+                        println!("{}", render_diff(&diff, theme, "      ", 10));
+                    }
+                }
+            }
+        }
+
+        if !self.api_errors.is_empty() {
+            println!();
+            println!("  {}", palette.error_strong.paint("API Panics:"));
+            for trace in &self.api_errors {
+                println!(
+                    "    {} {} {}",
+                    palette.error.paint(&theme.borders.fail_marker),
+                    palette.text.paint(&trace.headline()),
+                    palette.accent.paint(trace.request_id.as_deref().unwrap_or_default())
+                );
+                if let Some(origin) = trace.origin() {
+                    println!(
+                        "      at {} {}",
+                        palette.error.paint(&origin.function),
+                        palette.muted.paint(origin.location.as_deref().unwrap_or_default())
+                    );
                 }
             }
         }
@@ -265,15 +493,22 @@ impl Default for TestSummary {
     }
 }
 
-/// Parse elapsed time string like "1.235ms" to milliseconds
-fn parse_elapsed(elapsed: &Option<String>) -> f64 {
-    elapsed
-        .as_ref()
-        .and_then(|e| {
-            let s = e.trim_end_matches("ms").trim_end_matches("s");
-            s.parse::<f64>().ok()
-        })
-        .unwrap_or(0.0)
+/// Name a failure Karate only reported as `file.feature:40`, using the parsed feature files
+fn name_failure(failure: &mut FailedFeature, features: &[Feature]) {
+    let Some(line) = failure.line_number.filter(|_| failure.scenario.is_none()) else {
+        return;
+    };
+    if let Some((feature, scenario)) = find_scenario(features, &failure.feature_file, line) {
+        failure.scenario = Some(scenario.name.clone());
+        failure.tags = feature.tags.iter().chain(&scenario.tags).cloned().collect();
+        if failure.step.is_none() {
+            failure.step = scenario
+                .steps
+                .iter()
+                .find(|s| s.line == line)
+                .map(|s| format!("{} {}", s.keyword, s.text));
+        }
+    }
 }
 
 /// Extract a number after a label in a line
@@ -288,12 +523,74 @@ fn extract_number_after(label: &str, line: &str) -> Option<u32> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::RedactionConfig;
+
+    #[test]
+    fn test_sql_elapsed_units() {
+        let mut stats = SqlStats::new();
+        for elapsed in ["1.2s", "850µs", "1.235ms", "fast"] {
+            let line = format!(r#"{{"msg":"SQL","sql":"SELECT 1","elapsed":"{}"}}"#, elapsed);
+            stats.track_query(&ApiLogEntry::parse(&line).unwrap());
+        }
+        assert!((stats.total_elapsed_ms - 1202.085).abs() < 1e-9);
+        assert_eq!(stats.unparsed_elapsed, 1);
+        assert_eq!(stats.slowest_queries[0].elapsed_ms, 1200.0);
+    }
 
     #[test]
-    fn test_parse_elapsed() {
-        assert_eq!(parse_elapsed(&Some("1.235ms".to_string())), 1.235);
-        assert_eq!(parse_elapsed(&Some("0.5ms".to_string())), 0.5);
-        assert_eq!(parse_elapsed(&None), 0.0);
+    fn test_per_feature_lines_add_up() {
+        let mut summary = TestSummary::new();
+        summary.update_from_line("scenarios:  2 | passed:  1 | failed:  1 | time: 0.4675");
+        summary.update_from_line("scenarios:  3 | passed:  3 | failed:  0 | time: 1.2");
+        assert_eq!((summary.total_scenarios, summary.passed, summary.failed), (5, 4, 1));
+
+        summary.update_from_line("scenarios:  6 | passed:  5 | failed:  1");
+        assert_eq!((summary.total_scenarios, summary.passed, summary.failed), (6, 5, 1));
+    }
+
+    #[test]
+    fn test_reconcile_with_report() {
+        let report = CucumberReport {
+            features: CucumberReport::parse(
+                r#"[{"uri": "classpath:features/users.feature", "elements": [
+                    {"type": "scenario", "line": 5, "name": "create user", "steps": [
+                        {"line": 6, "result": {"status": "failed", "error_message": "\nstatus code was: 500"}}]},
+                    {"type": "scenario", "line": 9, "name": "delete user", "steps": [
+                        {"line": 10, "name": "header Authorization = 'Bearer abc.def.ghi'", "keyword": "And",
+                         "result": {"status": "failed", "error_message": "timeout patient_id=42"}}]}
+                ]}]"#,
+            )
+            .unwrap(),
+        };
+
+        let mut summary = TestSummary::new();
+        summary.update_from_line("scenarios:  2 | passed:  1 | failed:  1");
+        let failure = KarateFailure {
+            feature_file: "features/users.feature".to_string(),
+            line_number: 6,
+            assertion: String::new(),
+            url: None,
+            step: None,
+            expected: None,
+            actual: None,
+            response: None,
+        };
+        summary.track_failure(&failure, &[], &[]);
+        let redactor = Redactor::new(&RedactionConfig {
+            patterns: vec![r"patient_id=(\d+)".to_string()],
+            ..Default::default()
+        })
+        .unwrap();
+        summary.reconcile(&report, &redactor);
+
+        assert_eq!((summary.total_scenarios, summary.passed, summary.failed), (2, 0, 2));
+        assert_eq!(summary.failed_features.len(), 2);
+        assert_eq!(summary.failed_features[0].scenario.as_deref(), Some("create user"));
+        assert_eq!(summary.failed_features[0].error_message, "status code was: 500");
+        assert_eq!(summary.failed_features[1].line_number, Some(10));
+        assert_eq!(summary.failed_features[1].error_message, "timeout patient_id=***");
+        assert!(!summary.failed_features[1].step.as_deref().unwrap().contains("abc.def.ghi"));
+        assert_eq!(summary.discrepancies.len(), 2);
     }
 
     #[test]
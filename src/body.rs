@@ -0,0 +1,243 @@
+//! Pretty-printing of request/response bodies from body dump logs
+
+use crate::theme::{Palette, Theme};
+use serde_json::Value;
+
+/// Limits applied when rendering a body
+#[derive(Debug, Clone)]
+pub struct BodyLimits {
+    /// Maximum array items / object fields shown per level
+    pub max_items: usize,
+    /// Maximum characters shown for a string value
+    pub max_string_len: usize,
+    /// Spaces per indentation level
+    pub indent: usize,
+}
+
+/// Renders JSON bodies with indentation, syntax colouring and truncation
+pub struct BodyRenderer<'a> {
+    palette: &'a Palette,
+    ellipsis: &'a str,
+    limits: &'a BodyLimits,
+}
+
+impl<'a> BodyRenderer<'a> {
+    pub fn new(theme: &'a Theme, limits: &'a BodyLimits) -> Self {
+        Self {
+            palette: &theme.palette,
+            ellipsis: &theme.borders.ellipsis,
+            limits,
+        }
+    }
+
+    /// Render a body value as indented lines, each prefixed with `base_indent`
+    pub fn render(&self, body: &Value, base_indent: &str) -> String {
+        // Some middlewares log the body as a JSON string; unwrap it when possible
+        let parsed;
+        let body = match body {
+            Value::String(s) => match serde_json::from_str::<Value>(s) {
+                Ok(v @ (Value::Object(_) | Value::Array(_))) => {
+                    parsed = v;
+                    &parsed
+                }
+                _ => body,
+            },
+            _ => body,
+        };
+
+        let mut out = String::new();
+        self.render_value(body, 0, &mut out);
+        out.lines()
+            .map(|line| format!("{}{}", base_indent, line))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn render_value(&self, value: &Value, depth: usize, out: &mut String) {
+        let palette = self.palette;
+        match value {
+            Value::Null => out.push_str(&palette.info.paint("null").to_string()),
+            Value::Bool(b) => out.push_str(&palette.info.paint(&b.to_string()).to_string()),
+            Value::Number(n) => out.push_str(&palette.highlight.paint(&n.to_string()).to_string()),
+            Value::String(s) => out.push_str(&self.render_string(s)),
+            Value::Array(items) => {
+                if items.is_empty() {
+                    out.push_str(&palette.muted.paint("[]").to_string());
+                    return;
+                }
+                out.push_str(&palette.muted.paint("[").to_string());
+                let shown = items.len().min(self.limits.max_items);
+                for (i, item) in items.iter().take(shown).enumerate() {
+                    out.push('\n');
+                    out.push_str(&self.pad(depth + 1));
+                    self.render_value(item, depth + 1, out);
+                    if i + 1 < items.len() {
+                        out.push_str(&palette.muted.paint(",").to_string());
+                    }
+                }
+                self.push_more_marker(items.len() - shown, "items", depth, out);
+                out.push('\n');
+                out.push_str(&self.pad(depth));
+                out.push_str(&palette.muted.paint("]").to_string());
+            }
+            Value::Object(map) => {
+                if map.is_empty() {
+                    out.push_str(&palette.muted.paint("{}").to_string());
+                    return;
+                }
+                out.push_str(&palette.muted.paint("{").to_string());
+                let shown = map.len().min(self.limits.max_items);
+                for (i, (key, item)) in map.iter().take(shown).enumerate() {
+                    out.push('\n');
+                    out.push_str(&self.pad(depth + 1));
+                    out.push_str(&palette.accent.paint(&format!("\"{}\"", key)).to_string());
+                    out.push_str(&palette.muted.paint(": ").to_string());
+                    self.render_value(item, depth + 1, out);
+                    if i + 1 < map.len() {
+                        out.push_str(&palette.muted.paint(",").to_string());
+                    }
+                }
+                self.push_more_marker(map.len() - shown, "fields", depth, out);
+                out.push('\n');
+                out.push_str(&self.pad(depth));
+                out.push_str(&palette.muted.paint("}").to_string());
+            }
+        }
+    }
+
+    fn render_string(&self, s: &str) -> String {
+        let palette = self.palette;
+
+        if let Some(summary) = summarize_binary(s) {
+            return palette.muted.paint(&summary).to_string();
+        }
+
+        let char_count = s.chars().count();
+        if char_count > self.limits.max_string_len {
+            let truncated: String = s.chars().take(self.limits.max_string_len).collect();
+            return format!(
+                "{}{}",
+                palette.body.paint(&format!("\"{}", escape(&truncated))),
+                palette.muted.paint(&format!(
+                    "{}\" ({} more chars)",
+                    self.ellipsis,
+                    char_count - self.limits.max_string_len
+                ))
+            );
+        }
+
+        palette.body.paint(&format!("\"{}\"", escape(s))).to_string()
+    }
+
+    fn push_more_marker(&self, remaining: usize, noun: &str, depth: usize, out: &mut String) {
+        if remaining == 0 {
+            return;
+        }
+        out.push('\n');
+        out.push_str(&self.pad(depth + 1));
+        out.push_str(
+            &self
+                .palette
+                .muted
+                .paint(&format!("{} {} more {}", self.ellipsis, remaining, noun))
+                .to_string(),
+        );
+    }
+
+    fn pad(&self, depth: usize) -> String {
+        " ".repeat(depth * self.limits.indent)
+    }
+}
+
+/// Escape a string the way it would appear inside JSON quotes
+fn escape(s: &str) -> String {
+    let quoted = serde_json::to_string(s).unwrap_or_default();
+    quoted[1..quoted.len().saturating_sub(1)].to_string()
+}
+
+/// Summarise base64 or binary payloads by size instead of printing them
+fn summarize_binary(s: &str) -> Option<String> {
+    if s.chars().any(|c| c == '\u{FFFD}' || (c.is_control() && !c.is_whitespace())) {
+        return Some(format!("<binary, {} bytes>", s.len()));
+    }
+
+    // Data URLs: "data:image/png;base64,...."
+    let (label, payload) = match s.strip_prefix("data:").and_then(|rest| rest.split_once(";base64,")) {
+        Some((mime, payload)) => (format!("base64 {}", mime), payload),
+        None => ("base64".to_string(), s),
+    };
+
+    let looks_base64 = payload.len() >= 64
+        && payload.len() % 4 == 0
+        && payload
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b == b'+' || b == b'/' || b == b'=')
+        // Long identifiers/words are alphanumeric too; real base64 mixes cases and digits
+        && payload.bytes().any(|b| b.is_ascii_digit())
+        && payload.bytes().any(|b| b.is_ascii_uppercase())
+        && payload.bytes().any(|b| b.is_ascii_lowercase());
+
+    if !looks_base64 {
+        return None;
+    }
+
+    let padding = payload.bytes().rev().take_while(|&b| b == b'=').count();
+    let decoded = (payload.len() / 4 * 3).saturating_sub(padding);
+    Some(format!("<{}, {} bytes>", label, decoded))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Rendered text without colors, whatever the terminal supports
+    fn render(value: &Value, limits: &BodyLimits) -> String {
+        render_with(&Theme::default(), value, limits)
+    }
+
+    fn render_with(theme: &Theme, value: &Value, limits: &BodyLimits) -> String {
+        let out = BodyRenderer::new(theme, limits).render(value, "");
+        regex::Regex::new(r"\x1b\[[0-9;]*m").unwrap().replace_all(&out, "").into_owned()
+    }
+
+    fn limits() -> BodyLimits {
+        BodyLimits {
+            max_items: 2,
+            max_string_len: 10,
+            indent: 2,
+        }
+    }
+
+    #[test]
+    fn test_truncates_arrays_and_strings() {
+        let value = serde_json::json!({"ids": [1, 2, 3, 4], "name": "abcdefghijklmnop"});
+        let out = render(&value, &limits());
+        assert!(out.contains("… 2 more items"));
+        assert!(out.contains("\"abcdefghij…\" (6 more chars)"));
+        assert!(out.contains("\n  \"ids\": ["));
+
+        let out = render_with(&Theme::ascii(), &value, &limits());
+        assert!(out.contains("... 2 more items"));
+        assert!(out.contains("\"abcdefghij...\" (6 more chars)"));
+    }
+
+    #[test]
+    fn test_unwraps_json_string_body() {
+        let value = Value::String(r#"{"ok":true}"#.to_string());
+        let out = render(&value, &limits());
+        assert_eq!(out, "{\n  \"ok\": true\n}");
+    }
+
+    #[test]
+    fn test_summarizes_base64() {
+        let payload = "iVBORw0KGgoAAAANSUhEUgAAAAEAAAABCAYAAAAfFcSJAAAADUlEQVR42mNk+M9QDwADhgGAWjR9awAAAABJRU5ErkJggg==";
+        assert_eq!(
+            summarize_binary(payload).as_deref(),
+            Some("<base64, 70 bytes>")
+        );
+        assert!(summarize_binary("plain text value").is_none());
+
+        let padded = format!("Ab1/{}", "=".repeat(60));
+        assert_eq!(summarize_binary(&padded).as_deref(), Some("<base64, 0 bytes>"));
+    }
+}
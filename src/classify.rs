@@ -0,0 +1,249 @@
+//! Ordered rules assigning log types, prefixes and colours to API entries and Karate lines
+
+use crate::config::{ClassifyConfig, ClassifyRule};
+use crate::filter_expr::FilterExpr;
+use crate::log_parser::{ApiLogEntry, LogType};
+use crate::theme::Style;
+use regex::Regex;
+
+/// Log types API rules can assign
+pub const API_TYPES: &[&str] = &["request", "sql", "error", "body", "general"];
+
+/// Log types Karate rules can assign
+pub const KARATE_TYPES: &[&str] = &["summary", "failure", "scenario_start", "scenario_end", "batch", "info"];
+
+fn api_type(name: &str) -> Option<LogType> {
+    match name.to_lowercase().as_str() {
+        "request" => Some(LogType::ApiRequest),
+        "sql" => Some(LogType::ApiSql),
+        "error" => Some(LogType::ApiError),
+        "body" => Some(LogType::ApiBodyDump),
+        "general" => Some(LogType::ApiGeneral),
+        _ => None,
+    }
+}
+
+fn karate_type(name: &str) -> Option<LogType> {
+    match name.to_lowercase().as_str() {
+        "summary" => Some(LogType::KarateSummary),
+        "failure" => Some(LogType::KarateFailure),
+        "scenario_start" => Some(LogType::KarateScenarioStart),
+        "scenario_end" => Some(LogType::KarateScenarioEnd),
+        "batch" => Some(LogType::KarateBatch),
+        "info" => Some(LogType::KarateInfo),
+        _ => None,
+    }
+}
+
+fn rule(log_type: &str, when: Option<&str>, patterns: &[&str]) -> ClassifyRule {
+    ClassifyRule {
+        log_type: log_type.to_string(),
+        when: when.map(str::to_string),
+        patterns: patterns.iter().map(|p| p.to_string()).collect(),
+        prefix: None,
+        color: None,
+    }
+}
+
+/// Our slog conventions: `SQL` messages, body dumps and `REQUEST` summaries
+pub fn builtin_api_rules() -> Vec<ClassifyRule> {
+    vec![
+        rule("error", Some(r#"(msg ~ "SQL" || sql) && (level == ERROR || err)"#), &[]),
+        rule("sql", Some(r#"msg ~ "SQL" || sql"#), &[]),
+        rule("body", Some(r#"msg ~ "request / response body dump""#), &[]),
+        rule("request", Some(r#"msg == "REQUEST""#), &[]),
+        rule("error", Some("level == ERROR"), &[]),
+    ]
+}
+
+/// Karate's summary and failure output, and the API's 📦 batch marker
+pub fn builtin_karate_rules() -> Vec<ClassifyRule> {
+    vec![
+        rule("batch", None, &["📦"]),
+        rule("summary", None, &["scenarios:", "passed:", "failed:"]),
+        rule("failure", None, &["status code was:", "expected:"]),
+        rule("failure", None, &[r"^Scenario:|\.feature:", "failed"]),
+        rule("scenario_start", None, &[r"^Scenario:|\.feature:"]),
+        rule("summary", None, &["^(features:|elapsed:)"]),
+    ]
+}
+
+/// Result of classifying an entry or line
+#[derive(Debug, Clone)]
+pub struct Class<'a> {
+    pub log_type: LogType,
+    /// Prefix replacing the theme's one for the type
+    pub prefix: Option<&'a str>,
+    /// Colour replacing the type's one for plainly printed lines
+    pub style: Option<Style>,
+    /// What follows the last pattern match (Karate lines), e.g. a batch line's content
+    pub rest: &'a str,
+}
+
+#[derive(Debug)]
+struct Rule {
+    log_type: LogType,
+    when: Option<FilterExpr>,
+    patterns: Vec<Regex>,
+    prefix: Option<String>,
+    style: Option<Style>,
+}
+
+impl Rule {
+    /// Compile a rule; validation rejects invalid ones at startup, so none are dropped in a run
+    fn compile(rule: &ClassifyRule, log_type: fn(&str) -> Option<LogType>) -> Option<Self> {
+        let when = match &rule.when {
+            Some(expression) => Some(FilterExpr::parse(expression).ok()?),
+            None => None,
+        };
+        let patterns = rule
+            .patterns
+            .iter()
+            .map(|p| Regex::new(p).ok())
+            .collect::<Option<Vec<_>>>()?;
+        let style = match &rule.color {
+            Some(color) => Some(Style::named(color)?),
+            None => None,
+        };
+        Some(Self {
+            log_type: log_type(&rule.log_type)?,
+            when,
+            patterns,
+            prefix: rule.prefix.clone(),
+            style,
+        })
+    }
+
+    fn class<'a>(&'a self, rest: &'a str) -> Class<'a> {
+        Class {
+            log_type: self.log_type.clone(),
+            prefix: self.prefix.as_deref(),
+            style: self.style,
+            rest,
+        }
+    }
+}
+
+/// Compiled API and Karate rule tables
+#[derive(Debug)]
+pub struct Classifier {
+    api: Vec<Rule>,
+    karate: Vec<Rule>,
+}
+
+impl Classifier {
+    /// Configured rules first, then the built-in ones unless disabled
+    pub fn new(config: &ClassifyConfig) -> Self {
+        let mut api = config.api.clone();
+        let mut karate = config.karate.clone();
+        if config.builtin_rules {
+            api.extend(builtin_api_rules());
+            karate.extend(builtin_karate_rules());
+        }
+        Self {
+            // Karate rules match lines, not entries
+            api: api
+                .iter()
+                .filter(|r| r.when.is_some() && r.patterns.is_empty())
+                .filter_map(|r| Rule::compile(r, api_type))
+                .collect(),
+            karate: karate
+                .iter()
+                .filter(|r| r.when.is_none() && !r.patterns.is_empty())
+                .filter_map(|r| Rule::compile(r, karate_type))
+                .collect(),
+        }
+    }
+
+    /// Type of an API entry; `general` when no rule matches
+    pub fn api(&self, entry: &ApiLogEntry) -> Class<'_> {
+        self.api
+            .iter()
+            .find(|rule| rule.when.as_ref().is_some_and(|when| when.matches(entry)))
+            .map(|rule| rule.class(""))
+            .unwrap_or(Class {
+                log_type: LogType::ApiGeneral,
+                prefix: None,
+                style: None,
+                rest: "",
+            })
+    }
+
+    /// Type of a Karate output line (matched trimmed); `info` when no rule matches
+    pub fn karate<'a>(&'a self, line: &'a str) -> Class<'a> {
+        let trimmed = line.trim();
+        for rule in &self.karate {
+            let mut end = 0;
+            let matched = rule.patterns.iter().all(|pattern| match pattern.find(trimmed) {
+                Some(m) => {
+                    end = end.max(m.end());
+                    true
+                }
+                None => false,
+            });
+            if matched {
+                return rule.class(&trimmed[end..]);
+            }
+        }
+        Class {
+            log_type: LogType::KarateInfo,
+            prefix: None,
+            style: None,
+            rest: trimmed,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(json: &str) -> ApiLogEntry {
+        ApiLogEntry::parse(json).unwrap()
+    }
+
+    #[test]
+    fn test_builtin_rules() {
+        let builtin = Classifier::new(&ClassifyConfig::default());
+        let api = |json| builtin.api(&entry(json)).log_type;
+        assert_eq!(api(r#"{"level":"INFO","msg":"SQL","sql":"SELECT 1"}"#), LogType::ApiSql);
+        assert_eq!(api(r#"{"level":"INFO","msg":"query","sql":"SELECT 1","err":"x"}"#), LogType::ApiError);
+        assert_eq!(api(r#"{"level":"DEBUG","msg":"request / response body dump"}"#), LogType::ApiBodyDump);
+        assert_eq!(api(r#"{"level":"INFO","msg":"REQUEST","status":200}"#), LogType::ApiRequest);
+        assert_eq!(api(r#"{"level":"ERROR","msg":"boom"}"#), LogType::ApiError);
+        assert_eq!(api(r#"{"level":"INFO","msg":"hello"}"#), LogType::ApiGeneral);
+
+        let karate = |line| builtin.karate(line).log_type;
+        assert_eq!(karate("scenarios:  2 | passed:  1 | failed:  1 | time: 0.4"), LogType::KarateSummary);
+        assert_eq!(karate("  features:     1 | skipped:    0"), LogType::KarateSummary);
+        assert_eq!(karate("Scenario: [1.1:12] create user failed"), LogType::KarateFailure);
+        assert_eq!(karate("classpath:users.feature:12"), LogType::KarateScenarioStart);
+        assert_eq!(karate("12:00:00 INFO  - step"), LogType::KarateInfo);
+        let batch = builtin.karate("📦 {\"msg\":\"job\"}");
+        assert_eq!((batch.log_type, batch.rest), (LogType::KarateBatch, " {\"msg\":\"job\"}"));
+    }
+
+    #[test]
+    fn test_configured_rules_come_first() {
+        let config: ClassifyConfig = toml::from_str(
+            r#"
+api = [{ type = "general", when = 'msg ~ "SQL" && !sql', prefix = "DB? ", color = "yellow" }]
+karate = [{ type = "batch", patterns = ['^\[job\]'] }]
+"#,
+        )
+        .unwrap();
+        let classifier = Classifier::new(&config);
+        let class = classifier.api(&entry(r#"{"level":"INFO","msg":"SQL cache warmed"}"#));
+        assert_eq!((class.log_type, class.prefix), (LogType::ApiGeneral, Some("DB? ")));
+        assert_eq!(class.style, Style::named("yellow"));
+        let sql = classifier.api(&entry(r#"{"level":"INFO","msg":"SQL","sql":"SELECT 1"}"#));
+        assert_eq!(sql.log_type, LogType::ApiSql);
+        assert_eq!(classifier.karate("[job] nightly").rest, " nightly");
+
+        let only = Classifier::new(&ClassifyConfig {
+            builtin_rules: false,
+            ..config
+        });
+        assert_eq!(only.karate("📦 job").log_type, LogType::KarateInfo);
+    }
+}
@@ -1,7 +1,8 @@
-//! Configuration module for loading TOML/JSON config files
+//! Configuration module for loading TOML/YAML/JSON config files
 
 use serde::{Deserialize, Serialize};
-use std::path::Path;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -12,8 +13,126 @@ pub enum ConfigError {
     TomlError(#[from] toml::de::Error),
     #[error("Failed to parse JSON: {0}")]
     JsonError(#[from] serde_json::Error),
+    #[error("Failed to parse YAML: {0}")]
+    YamlError(#[from] serde_yaml_ng::Error),
     #[error("Unsupported config format: {0}")]
     UnsupportedFormat(String),
+    #[error("Unknown profile \"{name}\" (available: {available})")]
+    UnknownProfile { name: String, available: String },
+    #[error("Invalid environment override {var}: {message}")]
+    EnvOverride { var: String, message: String },
+    #[error("Invalid profile \"{name}\": {source}")]
+    InvalidProfile {
+        name: String,
+        source: serde_json::Error,
+    },
+    #[error("In included file {path}: {source}")]
+    Include {
+        path: String,
+        source: Box<ConfigError>,
+    },
+    #[error("Include cycle: {0} includes itself")]
+    IncludeCycle(String),
+    #[error("Invalid include list: {0}")]
+    InvalidInclude(String),
+    #[error("Invalid merged configuration: {0}")]
+    InvalidMerged(serde_json::Error),
+}
+
+/// Environment variable selecting a profile when `--profile` is not given
+pub const PROFILE_ENV: &str = "KARATE_MONITOR_PROFILE";
+
+/// Prefix of `KARATE_MONITOR_<SECTION>__<KEY>` override variables
+pub const ENV_PREFIX: &str = "KARATE_MONITOR_";
+
+/// File names looked for in the current directory and repository root
+/// Settings missing from the serialized config while unset, with their empty JSON value
+pub const OPTIONAL_KEYS: &[(&str, &str)] = &[
+    ("schema.pattern", "null"),
+    ("schema.fields", "{}"),
+    ("schema.levels", "{}"),
+    ("schema.duration_units", "{}"),
+    ("classify.api", "[]"),
+    ("classify.karate", "[]"),
+];
+
+const CONFIG_FILE_NAMES: &[&str] = &[
+    "karate-monitor.toml",
+    "karate-monitor.yaml",
+    "karate-monitor.yml",
+    "karate-monitor.json",
+];
+
+/// File names looked for in `$XDG_CONFIG_HOME/karate-monitor/`
+const XDG_FILE_NAMES: &[&str] = &["config.toml", "config.yaml", "config.yml", "config.json"];
+
+/// Location baked into the container image, checked last
+pub const CONTAINER_CONFIG_PATH: &str = "/app/karate-monitor.toml";
+
+/// Config file formats, chosen by extension
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ConfigFormat {
+    Toml,
+    Json,
+    Yaml,
+}
+
+impl ConfigFormat {
+    pub fn of(path: &Path) -> Result<Self, ConfigError> {
+        match path.extension().and_then(|e| e.to_str()).unwrap_or("toml") {
+            "toml" => Ok(Self::Toml),
+            "json" => Ok(Self::Json),
+            "yaml" | "yml" => Ok(Self::Yaml),
+            ext => Err(ConfigError::UnsupportedFormat(ext.to_string())),
+        }
+    }
+
+    /// Parse one file into a generic value for merging
+    fn parse(self, content: &str) -> Result<serde_json::Value, ConfigError> {
+        if content.trim().is_empty() {
+            return Ok(serde_json::Value::Object(Default::default()));
+        }
+        // Parse the typed config first so errors keep their line numbers
+        match self {
+            Self::Toml => {
+                toml::from_str::<Config>(content)?;
+                Ok(serde_json::to_value(toml::from_str::<toml::Value>(content)?)?)
+            }
+            Self::Json => {
+                serde_json::from_str::<Config>(content)?;
+                Ok(serde_json::from_str(content)?)
+            }
+            Self::Yaml => {
+                serde_yaml_ng::from_str::<Config>(content)?;
+                Ok(serde_yaml_ng::from_str(content)?)
+            }
+        }
+    }
+}
+
+/// Places searched for a config file when `--config` is not given, in order
+pub fn discovery_candidates(cwd: &Path, xdg_config_home: Option<&Path>) -> Vec<PathBuf> {
+    let mut dirs = vec![cwd.to_path_buf()];
+    if let Some(root) = cwd.ancestors().skip(1).find(|dir| dir.join(".git").exists()) {
+        dirs.push(root.to_path_buf());
+    }
+
+    let mut candidates: Vec<PathBuf> = dirs
+        .iter()
+        .flat_map(|dir| CONFIG_FILE_NAMES.iter().map(move |name| dir.join(name)))
+        .collect();
+    if let Some(xdg) = xdg_config_home {
+        candidates.extend(XDG_FILE_NAMES.iter().map(|name| xdg.join("karate-monitor").join(name)));
+    }
+    candidates.push(PathBuf::from(CONTAINER_CONFIG_PATH));
+    candidates
+}
+
+/// First existing config file among the discovery candidates
+pub fn discover(cwd: &Path, xdg_config_home: Option<&Path>) -> Option<PathBuf> {
+    discovery_candidates(cwd, xdg_config_home)
+        .into_iter()
+        .find(|path| path.is_file())
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -28,6 +147,16 @@ pub struct Config {
     pub display: DisplayConfig,
     #[serde(default)]
     pub analysis: AnalysisConfig,
+    #[serde(default)]
+    pub redaction: RedactionConfig,
+    #[serde(default)]
+    pub progress: ProgressConfig,
+    #[serde(default)]
+    pub schema: SchemaConfig,
+    #[serde(default)]
+    pub classify: ClassifyConfig,
+    #[serde(default)]
+    pub filters: FiltersConfig,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -70,6 +199,28 @@ pub struct LoggingConfig {
     pub include_patterns: Vec<String>,
     #[serde(default)]
     pub exclude_patterns: Vec<String>,
+    /// Filter expression, e.g. `status >= 400 && uri ~ "/karte"`
+    #[serde(default)]
+    pub filter: Option<String>,
+    /// Filtered-out entries shown before / after each entry that passes the filters
+    #[serde(default)]
+    pub context_before: usize,
+    #[serde(default)]
+    pub context_after: usize,
+    /// Only use entries with the same request_id as context
+    #[serde(default)]
+    pub context_same_request: bool,
+    /// Collapse consecutive near-identical API entries into one line with a ×N counter
+    #[serde(default)]
+    pub dedup: bool,
+    /// Entries shown per message and window; 0 disables the limit
+    #[serde(default)]
+    pub rate_limit: usize,
+    #[serde(default = "default_rate_limit_window")]
+    pub rate_limit_window_secs: u64,
+    /// Apply dedup and the rate limit to exported logs as well
+    #[serde(default)]
+    pub dedup_export: bool,
     #[serde(default = "default_true")]
     pub colors: bool,
     #[serde(default)]
@@ -80,18 +231,43 @@ pub struct LoggingConfig {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DisplayConfig {
-    #[serde(default = "default_api_prefix")]
-    pub api_prefix: String,
-    #[serde(default = "default_karate_prefix")]
-    pub karate_prefix: String,
-    #[serde(default = "default_sql_prefix")]
-    pub sql_prefix: String,
-    #[serde(default = "default_error_prefix")]
-    pub error_prefix: String,
-    #[serde(default = "default_success_prefix")]
-    pub success_prefix: String,
+    /// Built-in theme: default, ascii, minimal, high-contrast
+    #[serde(default = "default_theme")]
+    pub theme: String,
+    /// Prefix overrides applied on top of the theme
+    #[serde(default)]
+    pub api_prefix: Option<String>,
+    #[serde(default)]
+    pub karate_prefix: Option<String>,
+    #[serde(default)]
+    pub sql_prefix: Option<String>,
+    #[serde(default)]
+    pub error_prefix: Option<String>,
+    #[serde(default)]
+    pub success_prefix: Option<String>,
     #[serde(default)]
     pub show_timestamps: bool,
+    /// Pretty-print request/response bodies of body dump logs
+    #[serde(default = "default_true")]
+    pub pretty_bodies: bool,
+    /// Maximum array items / object fields shown per level of a body
+    #[serde(default = "default_body_max_items")]
+    pub body_max_items: usize,
+    /// Maximum characters shown for a string inside a body
+    #[serde(default = "default_body_max_string_len")]
+    pub body_max_string_len: usize,
+    /// SQL log rendering: pretty (multi-line), compact (one line) or raw (JSON)
+    #[serde(default = "default_sql_style")]
+    pub sql_style: String,
+    /// Compact SQL statements longer than this are truncated
+    #[serde(default = "default_sql_compact_max_len")]
+    pub sql_compact_max_len: usize,
+    /// Queries at or above this many milliseconds are flagged as slow
+    #[serde(default = "default_slow_query_ms")]
+    pub slow_query_ms: f64,
+    /// Run with the full-screen dashboard instead of streaming output
+    #[serde(default)]
+    pub tui: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -104,6 +280,138 @@ pub struct AnalysisConfig {
     pub show_sql_stats: bool,
     #[serde(default)]
     pub failed_only: bool,
+    /// Scenarios listed in the "slowest scenarios" report; 0 hides it
+    #[serde(default = "default_slowest_scenarios")]
+    pub slowest_scenarios: usize,
+    /// Print a per-feature breakdown after the test summary
+    #[serde(default = "default_true")]
+    pub feature_breakdown: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RedactionConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// Built-in rules for common tokens and headers: "default" or "none"
+    #[serde(default = "default_redaction_preset")]
+    pub preset: String,
+    /// JSON field names masked at any depth (case-insensitive)
+    #[serde(default)]
+    pub fields: Vec<String>,
+    /// JSON paths masked exactly, e.g. "$.request_body.patients[*].name"
+    #[serde(default)]
+    pub paths: Vec<String>,
+    /// Regexes masked in any text; only group 1 is masked when present
+    #[serde(default)]
+    pub patterns: Vec<String>,
+    /// Mask string literals in SQL statements
+    #[serde(default)]
+    pub sql_literals: bool,
+    #[serde(default = "default_redaction_mask")]
+    pub mask: String,
+}
+
+/// How API log lines are encoded and their fields named, for services that do not log like our slog setup
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SchemaConfig {
+    /// Line format: auto (JSON, then `pattern`, then logfmt, per line), json, logfmt or regex
+    #[serde(default = "default_schema_format")]
+    pub format: String,
+    /// Regex with named groups for entry fields, for plain-text logs
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pattern: Option<String>,
+    /// Source field names per entry field, tried in order, e.g. `msg = ["message"]`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub fields: BTreeMap<String, Vec<String>>,
+    /// Level values (case-insensitive) mapped to DEBUG, INFO, WARN or ERROR, e.g. `"30" = "INFO"`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub levels: BTreeMap<String, String>,
+    /// Unit of numeric durations per entry field: ns, us, ms or s
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub duration_units: BTreeMap<String, String>,
+}
+
+/// Rules assigning log types, prefixes and colours to API entries and Karate lines
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClassifyConfig {
+    /// Whether the built-in rules (our slog and Karate conventions) follow the configured ones
+    #[serde(default = "default_true")]
+    pub builtin_rules: bool,
+    /// Rules for API log entries, tried in order before the built-in ones
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub api: Vec<ClassifyRule>,
+    /// Rules for Karate output lines, tried in order before the built-in ones
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub karate: Vec<ClassifyRule>,
+}
+
+/// One classification rule; the first matching rule decides
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ClassifyRule {
+    /// Log type: request, sql, error, body, general (API) or summary, failure,
+    /// scenario_start, scenario_end, batch, info (Karate)
+    #[serde(rename = "type")]
+    pub log_type: String,
+    /// Filter expression the entry must match (API rules)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub when: Option<String>,
+    /// Regexes that must all match the line (Karate rules)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub patterns: Vec<String>,
+    /// Line prefix instead of the theme's prefix for the type
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prefix: Option<String>,
+    /// Colour of plainly printed lines, e.g. `cyan` or `bright_red`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+}
+
+/// Per-source filter rules; unset fields inherit from `[logging]` (except for Karate)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FiltersConfig {
+    /// API logs that are not SQL or body dumps
+    #[serde(default)]
+    pub api: SourceFilterConfig,
+    #[serde(default)]
+    pub sql: SourceFilterConfig,
+    #[serde(default)]
+    pub body: SourceFilterConfig,
+    /// Batch job (📦) logs captured in Karate output
+    #[serde(default)]
+    pub batch: SourceFilterConfig,
+    /// Karate output lines
+    #[serde(default)]
+    pub karate: SourceFilterConfig,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SourceFilterConfig {
+    /// Set to false to hide this source entirely
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    #[serde(default)]
+    pub level: Option<String>,
+    #[serde(default)]
+    pub include_patterns: Option<Vec<String>>,
+    #[serde(default)]
+    pub exclude_patterns: Option<Vec<String>>,
+    /// Filter expression (not applied to Karate lines)
+    #[serde(default)]
+    pub filter: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProgressConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// Per-feature durations from previous runs, used for the ETA (empty disables)
+    ///
+    /// Relative paths are under `karate.report_dir`.
+    #[serde(default = "default_progress_history_path")]
+    pub history_path: String,
+    /// Seconds between status lines when stdout is not a terminal
+    #[serde(default = "default_progress_interval")]
+    pub status_interval_secs: u64,
 }
 
 // Default value functions
@@ -151,20 +459,44 @@ fn default_true() -> bool {
 fn default_export_format() -> String {
     "json".to_string()
 }
-fn default_api_prefix() -> String {
-    "🔷".to_string()
+fn default_slowest_scenarios() -> usize {
+    10
+}
+fn default_rate_limit_window() -> u64 {
+    60
+}
+fn default_redaction_preset() -> String {
+    "default".to_string()
+}
+fn default_redaction_mask() -> String {
+    "***".to_string()
+}
+fn default_progress_history_path() -> String {
+    ".karate-monitor-history.json".to_string()
+}
+fn default_progress_interval() -> u64 {
+    30
+}
+fn default_theme() -> String {
+    "default".to_string()
+}
+fn default_body_max_items() -> usize {
+    20
+}
+fn default_body_max_string_len() -> usize {
+    200
 }
-fn default_karate_prefix() -> String {
-    "🔶".to_string()
+fn default_sql_style() -> String {
+    "pretty".to_string()
 }
-fn default_sql_prefix() -> String {
-    "🗃️".to_string()
+fn default_sql_compact_max_len() -> usize {
+    160
 }
-fn default_error_prefix() -> String {
-    "❌".to_string()
+fn default_slow_query_ms() -> f64 {
+    100.0
 }
-fn default_success_prefix() -> String {
-    "✅".to_string()
+fn default_schema_format() -> String {
+    "auto".to_string()
 }
 
 impl Default for Config {
@@ -175,6 +507,11 @@ impl Default for Config {
             logging: LoggingConfig::default(),
             display: DisplayConfig::default(),
             analysis: AnalysisConfig::default(),
+            redaction: RedactionConfig::default(),
+            progress: ProgressConfig::default(),
+            schema: SchemaConfig::default(),
+            classify: ClassifyConfig::default(),
+            filters: FiltersConfig::default(),
         }
     }
 }
@@ -211,6 +548,14 @@ impl Default for LoggingConfig {
             level: default_level(),
             include_patterns: vec![],
             exclude_patterns: vec![],
+            filter: None,
+            context_before: 0,
+            context_after: 0,
+            context_same_request: false,
+            dedup: false,
+            rate_limit: 0,
+            rate_limit_window_secs: default_rate_limit_window(),
+            dedup_export: false,
             colors: true,
             export_path: String::new(),
             export_format: default_export_format(),
@@ -221,12 +566,20 @@ impl Default for LoggingConfig {
 impl Default for DisplayConfig {
     fn default() -> Self {
         Self {
-            api_prefix: default_api_prefix(),
-            karate_prefix: default_karate_prefix(),
-            sql_prefix: default_sql_prefix(),
-            error_prefix: default_error_prefix(),
-            success_prefix: default_success_prefix(),
+            theme: default_theme(),
+            api_prefix: None,
+            karate_prefix: None,
+            sql_prefix: None,
+            error_prefix: None,
+            success_prefix: None,
             show_timestamps: false,
+            pretty_bodies: true,
+            body_max_items: default_body_max_items(),
+            body_max_string_len: default_body_max_string_len(),
+            sql_style: default_sql_style(),
+            sql_compact_max_len: default_sql_compact_max_len(),
+            slow_query_ms: default_slow_query_ms(),
+            tui: false,
         }
     }
 }
@@ -238,23 +591,417 @@ impl Default for AnalysisConfig {
             track_sql: true,
             show_sql_stats: false,
             failed_only: false,
+            slowest_scenarios: default_slowest_scenarios(),
+            feature_breakdown: true,
+        }
+    }
+}
+
+impl Default for RedactionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            preset: default_redaction_preset(),
+            fields: vec![],
+            paths: vec![],
+            patterns: vec![],
+            sql_literals: false,
+            mask: default_redaction_mask(),
+        }
+    }
+}
+
+impl Default for SchemaConfig {
+    fn default() -> Self {
+        Self {
+            format: default_schema_format(),
+            pattern: None,
+            fields: BTreeMap::new(),
+            levels: BTreeMap::new(),
+            duration_units: BTreeMap::new(),
+        }
+    }
+}
+
+impl Default for ClassifyConfig {
+    fn default() -> Self {
+        Self {
+            builtin_rules: true,
+            api: Vec::new(),
+            karate: Vec::new(),
+        }
+    }
+}
+
+impl Default for SourceFilterConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            level: None,
+            include_patterns: None,
+            exclude_patterns: None,
+            filter: None,
+        }
+    }
+}
+
+impl Default for ProgressConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            history_path: default_progress_history_path(),
+            status_interval_secs: default_progress_interval(),
         }
     }
 }
 
 impl Config {
-    pub fn load(path: &Path) -> Result<Self, ConfigError> {
-        let content = std::fs::read_to_string(path)?;
+    /// Load a config file and its includes, overlaying `[profiles.<name>]` when a profile is given
+    pub fn load(path: &Path, profile: Option<&str>) -> Result<Self, ConfigError> {
+        let mut value = load_value(path, &mut Vec::new())?;
+        match profile {
+            Some(name) => Self::with_profile(value, name),
+            None => {
+                if let Some(root) = value.as_object_mut() {
+                    root.remove("profiles");
+                }
+                serde_json::from_value(value).map_err(ConfigError::InvalidMerged)
+            }
+        }
+    }
 
-        let extension = path
-            .extension()
-            .and_then(|e| e.to_str())
-            .unwrap_or("toml");
+    fn with_profile(mut base: serde_json::Value, name: &str) -> Result<Self, ConfigError> {
+        let profiles = base
+            .as_object_mut()
+            .and_then(|root| root.remove("profiles"))
+            .unwrap_or_default();
+        let Some(overlay) = profiles.get(name) else {
+            let names: Vec<&str> = profiles
+                .as_object()
+                .map(|p| p.keys().map(String::as_str).collect())
+                .unwrap_or_default();
+            return Err(ConfigError::UnknownProfile {
+                name: name.to_string(),
+                available: if names.is_empty() {
+                    "none defined".to_string()
+                } else {
+                    names.join(", ")
+                },
+            });
+        };
 
-        match extension {
-            "toml" => Ok(toml::from_str(&content)?),
-            "json" => Ok(serde_json::from_str(&content)?),
-            ext => Err(ConfigError::UnsupportedFormat(ext.to_string())),
+        merge(&mut base, overlay.clone());
+        serde_json::from_value(base).map_err(|source| ConfigError::InvalidProfile {
+            name: name.to_string(),
+            source,
+        })
+    }
+
+    /// Apply `KARATE_MONITOR_<SECTION>__<KEY>` variables, returning the overridden key paths
+    ///
+    /// Nested keys add more `__` segments (`KARATE_MONITOR_FILTERS__SQL__LEVEL`). Lists take
+    /// comma-separated values or a JSON array.
+    pub fn apply_env_overrides(
+        &mut self,
+        vars: impl IntoIterator<Item = (String, String)>,
+    ) -> Result<Vec<String>, ConfigError> {
+        let mut vars: Vec<(String, String)> = vars
+            .into_iter()
+            .filter(|(name, _)| name.starts_with(ENV_PREFIX) && name.contains("__"))
+            .collect();
+        vars.sort();
+
+        let mut applied = Vec::new();
+        for (var, raw) in vars {
+            let path: Vec<String> = var[ENV_PREFIX.len()..]
+                .split("__")
+                .map(str::to_ascii_lowercase)
+                .collect();
+            let key = path.join(".");
+            let error = |message: String| ConfigError::EnvOverride {
+                var: var.clone(),
+                message,
+            };
+
+            let mut value = serde_json::to_value(&*self).map_err(|e| error(e.to_string()))?;
+            // Unset optional keys are skipped when serializing; put back their empty values
+            for (optional, empty) in OPTIONAL_KEYS {
+                let (section, name) = optional.split_once('.').unwrap_or_default();
+                if let Some(table) = value.get_mut(section).and_then(|v| v.as_object_mut()) {
+                    table
+                        .entry(name)
+                        .or_insert_with(|| serde_json::from_str(empty).unwrap_or_default());
+                }
+            }
+            let pointer = format!("/{}", path.join("/"));
+            let current = value
+                .pointer(&pointer)
+                .ok_or_else(|| error(format!("unknown config key {}", key)))?;
+
+            let mut last_error = String::new();
+            let mut updated = None;
+            for candidate in env_candidates(current, &raw) {
+                let mut value = value.clone();
+                if let Some(slot) = value.pointer_mut(&pointer) {
+                    *slot = candidate;
+                }
+                match serde_json::from_value::<Config>(value) {
+                    Ok(config) => {
+                        updated = Some(config);
+                        break;
+                    }
+                    Err(e) => last_error = e.to_string(),
+                }
+            }
+            *self = updated.ok_or_else(|| error(format!("{} ({})", last_error, key)))?;
+            applied.push(key);
+        }
+        Ok(applied)
+    }
+}
+
+/// A config file merged with its includes, before it becomes a `Config`
+pub fn load_raw(path: &Path) -> Result<serde_json::Value, ConfigError> {
+    load_value(path, &mut Vec::new())
+}
+
+/// Read one file as a generic value, merged over the files it includes
+///
+/// Include paths are relative to the including file; later includes and the file itself win.
+fn load_value(path: &Path, stack: &mut Vec<PathBuf>) -> Result<serde_json::Value, ConfigError> {
+    let canonical = path.canonicalize()?;
+    if stack.contains(&canonical) {
+        return Err(ConfigError::IncludeCycle(path.display().to_string()));
+    }
+
+    let content = std::fs::read_to_string(path)?;
+    let mut value = ConfigFormat::of(path)?.parse(&content)?;
+    let includes = match value.as_object_mut().and_then(|root| root.remove("include")) {
+        None => Vec::new(),
+        Some(serde_json::Value::String(include)) => vec![include],
+        Some(list) => serde_json::from_value::<Vec<String>>(list)
+            .map_err(|e| ConfigError::InvalidInclude(e.to_string()))?,
+    };
+
+    stack.push(canonical);
+    let dir = path.parent().unwrap_or(Path::new("."));
+    let mut merged = serde_json::Value::Object(Default::default());
+    for include in includes {
+        let include_path = dir.join(include);
+        let included = load_value(&include_path, stack).map_err(|source| ConfigError::Include {
+            path: include_path.display().to_string(),
+            source: Box::new(source),
+        })?;
+        merge(&mut merged, included);
+    }
+    stack.pop();
+
+    merge(&mut merged, value);
+    Ok(merged)
+}
+
+/// Values to try for an override, based on the type of the current value
+fn env_candidates(current: &serde_json::Value, raw: &str) -> Vec<serde_json::Value> {
+    use serde_json::Value;
+
+    let list = || -> Value {
+        match serde_json::from_str::<Value>(raw) {
+            Ok(array @ Value::Array(_)) => array,
+            _ => Value::Array(
+                raw.split(',')
+                    .map(str::trim)
+                    .filter(|item| !item.is_empty())
+                    .map(|item| Value::String(item.to_string()))
+                    .collect(),
+            ),
+        }
+    };
+    let json = || serde_json::from_str::<Value>(raw).ok();
+
+    match current {
+        Value::Bool(_) => match raw.trim().to_ascii_lowercase().as_str() {
+            "1" | "true" | "yes" | "on" => vec![Value::Bool(true)],
+            "0" | "false" | "no" | "off" => vec![Value::Bool(false)],
+            _ => vec![Value::String(raw.to_string())],
+        },
+        Value::Number(_) => vec![json().unwrap_or_else(|| Value::String(raw.to_string()))],
+        Value::Array(_) => vec![list()],
+        Value::String(_) => vec![Value::String(raw.to_string())],
+        // Unset optional values: the type is unknown, so try the likely shapes in turn
+        _ => json()
+            .into_iter()
+            .chain([Value::String(raw.to_string()), list()])
+            .collect(),
+    }
+}
+
+/// Overlay `overlay` onto `base`: tables merge key by key, everything else is replaced
+fn merge(base: &mut serde_json::Value, overlay: serde_json::Value) {
+    match (base, overlay) {
+        (serde_json::Value::Object(base), serde_json::Value::Object(overlay)) => {
+            for (key, value) in overlay {
+                match base.get_mut(&key) {
+                    Some(existing) => merge(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
         }
+        (base, overlay) => *base = overlay,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = r#"
+[logging]
+level = "INFO"
+exclude_patterns = ["health"]
+
+[display]
+theme = "minimal"
+
+[profiles.ci.logging]
+level = "ERROR"
+
+[profiles.ci.display]
+tui = false
+theme = "ascii"
+"#;
+
+    fn base() -> serde_json::Value {
+        serde_json::to_value(toml::from_str::<toml::Value>(SOURCE).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_profile_overlays_base() {
+        let config = Config::with_profile(base(), "ci").unwrap();
+        assert_eq!(config.logging.level, "ERROR");
+        assert_eq!(config.logging.exclude_patterns, vec!["health"]);
+        assert_eq!(config.display.theme, "ascii");
+        assert!(config.logging.colors);
+    }
+
+    #[test]
+    fn test_unknown_profile_lists_available() {
+        let err = Config::with_profile(base(), "nightly").unwrap_err();
+        assert_eq!(err.to_string(), "Unknown profile \"nightly\" (available: ci)");
+    }
+
+    fn vars(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    }
+
+    #[test]
+    fn test_env_overrides_typed_values() {
+        let mut config = Config::default();
+        let applied = config
+            .apply_env_overrides(vars(&[
+                ("KARATE_MONITOR_LOGGING__LEVEL", "ERROR"),
+                ("KARATE_MONITOR_LOGGING__INCLUDE_PATTERNS", "karte, patient"),
+                ("KARATE_MONITOR_KARATE__CLASSPATH", r#"["/a.jar","/b"]"#),
+                ("KARATE_MONITOR_KARATE__THREADS", "4"),
+                ("KARATE_MONITOR_DISPLAY__TUI", "yes"),
+                ("KARATE_MONITOR_FILTERS__SQL__EXCLUDE_PATTERNS", "flyway"),
+                ("KARATE_MONITOR_PROFILE", "ci"),
+                ("PATH", "/bin"),
+            ]))
+            .unwrap();
+
+        assert_eq!(applied.len(), 6);
+        assert_eq!(config.logging.level, "ERROR");
+        assert_eq!(config.logging.include_patterns, vec!["karte", "patient"]);
+        assert_eq!(config.karate.classpath, vec!["/a.jar", "/b"]);
+        assert_eq!(config.karate.threads, 4);
+        assert!(config.display.tui);
+        assert_eq!(config.filters.sql.exclude_patterns, Some(vec!["flyway".to_string()]));
+    }
+
+    #[test]
+    fn test_env_override_errors() {
+        let mut config = Config::default();
+        let err = config
+            .apply_env_overrides(vars(&[("KARATE_MONITOR_LOGGING__LEVL", "ERROR")]))
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Invalid environment override KARATE_MONITOR_LOGGING__LEVL: unknown config key logging.levl"
+        );
+        assert!(config
+            .apply_env_overrides(vars(&[("KARATE_MONITOR_KARATE__THREADS", "many")]))
+            .is_err());
+    }
+
+    #[test]
+    fn test_env_overrides_unset_optional_keys() {
+        let mut config = Config::default();
+        let applied = config
+            .apply_env_overrides(vars(&[
+                ("KARATE_MONITOR_SCHEMA__PATTERN", r"^(?P<level>\w+) (?P<msg>.*)$"),
+                ("KARATE_MONITOR_SCHEMA__LEVELS", r#"{"30": "INFO"}"#),
+                ("KARATE_MONITOR_CLASSIFY__API", r#"[{"type": "sql", "when": "msg ~ query"}]"#),
+            ]))
+            .unwrap();
+
+        assert_eq!(applied, vec!["classify.api", "schema.levels", "schema.pattern"]);
+        assert_eq!(config.schema.pattern.as_deref(), Some(r"^(?P<level>\w+) (?P<msg>.*)$"));
+        assert_eq!(config.schema.levels.get("30").map(String::as_str), Some("INFO"));
+        assert_eq!(config.classify.api[0].log_type, "sql");
+        assert!(config
+            .apply_env_overrides(vars(&[("KARATE_MONITOR_SCHEMA__PATERN", "x")]))
+            .is_err());
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("karate-monitor-{}-{}", name, std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_includes_yaml_and_profiles() {
+        let dir = temp_dir("include");
+        std::fs::write(
+            dir.join("base.yaml"),
+            "logging:\n  level: WARN\n  exclude_patterns: [health]\nprofiles:\n  ci:\n    display:\n      theme: ascii\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("karate-monitor.toml"),
+            "include = [\"base.yaml\"]\n\n[logging]\nlevel = \"ERROR\"\n",
+        )
+        .unwrap();
+
+        let config = Config::load(&dir.join("karate-monitor.toml"), Some("ci")).unwrap();
+        assert_eq!(config.logging.level, "ERROR");
+        assert_eq!(config.logging.exclude_patterns, vec!["health"]);
+        assert_eq!(config.display.theme, "ascii");
+
+        std::fs::write(dir.join("base.yaml"), "include: karate-monitor.toml\n").unwrap();
+        let err = Config::load(&dir.join("karate-monitor.toml"), None).unwrap_err();
+        assert!(err.to_string().contains("Include cycle"), "{}", err);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_discovery_order() {
+        let dir = temp_dir("discover");
+        let repo = dir.join("repo");
+        let cwd = repo.join("tests").join("e2e");
+        let xdg = dir.join("xdg");
+        std::fs::create_dir_all(repo.join(".git")).unwrap();
+        std::fs::create_dir_all(&cwd).unwrap();
+        std::fs::create_dir_all(xdg.join("karate-monitor")).unwrap();
+
+        std::fs::write(xdg.join("karate-monitor").join("config.yaml"), "").unwrap();
+        assert_eq!(discover(&cwd, Some(&xdg)), Some(xdg.join("karate-monitor").join("config.yaml")));
+        std::fs::write(repo.join("karate-monitor.json"), "{}").unwrap();
+        assert_eq!(discover(&cwd, Some(&xdg)), Some(repo.join("karate-monitor.json")));
+        std::fs::write(cwd.join("karate-monitor.yml"), "").unwrap();
+        assert_eq!(discover(&cwd, Some(&xdg)), Some(cwd.join("karate-monitor.yml")));
+        std::fs::remove_dir_all(dir).unwrap();
     }
 }
@@ -0,0 +1,113 @@
+//! Output routing: plain terminal output or events for the dashboard
+
+use crate::dashboard::{DashboardEvent, DashboardFailure};
+use crate::log_parser::ApiLogEntry;
+use crossterm::cursor::MoveToColumn;
+use crossterm::execute;
+use crossterm::terminal::{Clear, ClearType};
+use std::io::Write;
+use std::sync::mpsc::Sender;
+use std::sync::{Arc, Mutex};
+
+/// Where process output goes
+#[derive(Clone)]
+pub enum Console {
+    /// Print lines to stdout/stderr, keeping the status line (if any) below them
+    Terminal(Arc<Mutex<Option<String>>>),
+    /// Send structured events to the full-screen dashboard
+    Dashboard(Sender<DashboardEvent>),
+}
+
+impl Console {
+    pub fn terminal() -> Self {
+        Console::Terminal(Arc::default())
+    }
+
+    /// Whether output goes to the dashboard
+    pub fn is_dashboard(&self) -> bool {
+        matches!(self, Console::Dashboard(_))
+    }
+
+    /// Print a line to stdout (a status message on the dashboard)
+    pub fn line(&self, text: impl Into<String>) {
+        match self {
+            Console::Terminal(status) => print_above(status, false, &text.into()),
+            Console::Dashboard(tx) => {
+                let text = text.into();
+                if !text.trim().is_empty() {
+                    let _ = tx.send(DashboardEvent::Message(text));
+                }
+            }
+        }
+    }
+
+    /// Print a line to stderr (a status message on the dashboard)
+    pub fn error_line(&self, text: impl Into<String>) {
+        match self {
+            Console::Terminal(status) => print_above(status, true, &text.into()),
+            Console::Dashboard(_) => self.line(text),
+        }
+    }
+
+    /// Show an API log entry; `formatted` is only rendered for the terminal
+    pub fn api_log(&self, entry: &ApiLogEntry, formatted: impl FnOnce() -> String) {
+        match self {
+            Console::Terminal(status) => print_above(status, false, &formatted()),
+            Console::Dashboard(tx) => {
+                let _ = tx.send(DashboardEvent::ApiLog(Box::new(entry.clone())));
+            }
+        }
+    }
+
+    /// Show `text` on the terminal's last line, replacing the previous status
+    pub fn status(&self, text: impl Into<String>) {
+        if let Console::Terminal(status) = self {
+            let mut status = status.lock().unwrap_or_else(|e| e.into_inner());
+            let text = text.into();
+            let mut out = std::io::stdout().lock();
+            let _ = execute!(out, MoveToColumn(0), Clear(ClearType::CurrentLine));
+            let _ = write!(out, "{}", text);
+            let _ = out.flush();
+            *status = Some(text);
+        }
+    }
+
+    /// Leave the status line as ordinary output; later lines print below it
+    pub fn finish_status(&self) {
+        if let Console::Terminal(status) = self {
+            if status.lock().unwrap_or_else(|e| e.into_inner()).take().is_some() {
+                println!();
+            }
+        }
+    }
+
+    /// Send a structured event to the dashboard (no-op for the terminal)
+    pub fn event(&self, event: impl FnOnce() -> DashboardEvent) {
+        if let Console::Dashboard(tx) = self {
+            let _ = tx.send(event());
+        }
+    }
+
+    /// Report a failure with its correlated API logs (dashboard only)
+    pub fn failure(&self, failure: impl FnOnce() -> DashboardFailure) {
+        self.event(|| DashboardEvent::Failure(failure()));
+    }
+}
+
+/// Print a line, clearing the status line first and redrawing it after
+fn print_above(status: &Mutex<Option<String>>, stderr: bool, text: &str) {
+    let status = status.lock().unwrap_or_else(|e| e.into_inner());
+    let mut out = std::io::stdout().lock();
+    if status.is_some() {
+        let _ = execute!(out, MoveToColumn(0), Clear(ClearType::CurrentLine));
+    }
+    if stderr {
+        eprintln!("{}", text);
+    } else {
+        let _ = writeln!(out, "{}", text);
+    }
+    if let Some(status) = status.as_ref() {
+        let _ = write!(out, "{}", status);
+        let _ = out.flush();
+    }
+}
@@ -0,0 +1,186 @@
+//! grep-style context around API log entries that pass the filters
+
+use std::collections::{HashMap, VecDeque};
+
+/// Requests tracked at once for same-request context
+const MAX_TRACKED_REQUESTS: usize = 256;
+
+/// Something to print, in stream order
+#[derive(Debug, Clone, PartialEq)]
+pub enum ContextLine<T> {
+    /// An entry that passed the filters
+    Match(T),
+    /// A surrounding entry shown for context
+    Context(T),
+    /// Entries were skipped between two groups (grep's `--`)
+    Separator,
+}
+
+/// Keeps recent rejected entries and emits them around matches
+pub struct ContextBuffer<T> {
+    before: usize,
+    after: usize,
+    same_request: bool,
+    /// Rejected entries that may still become "before" context, keyed by request
+    /// (`None` holds everything when context is not limited to the same request)
+    recent: HashMap<Option<String>, VecDeque<(u64, T)>>,
+    /// Request order for evicting `recent` keys
+    request_order: VecDeque<Option<String>>,
+    /// Remaining "after" context, per request or under `None`
+    remaining_after: HashMap<Option<String>, usize>,
+    next_seq: u64,
+    last_emitted: Option<u64>,
+}
+
+impl<T> ContextBuffer<T> {
+    pub fn new(before: usize, after: usize, same_request: bool) -> Self {
+        Self {
+            before,
+            after,
+            same_request,
+            recent: HashMap::new(),
+            request_order: VecDeque::new(),
+            remaining_after: HashMap::new(),
+            next_seq: 0,
+            last_emitted: None,
+        }
+    }
+
+    fn key(&self, request_id: Option<&str>) -> Option<Option<String>> {
+        if !self.same_request {
+            return Some(None);
+        }
+        // Entries without a request_id never count as same-request context
+        request_id.map(|id| Some(id.to_string()))
+    }
+
+    /// Add the next entry; returns what to print now
+    pub fn push(&mut self, item: T, request_id: Option<&str>, matched: bool) -> Vec<ContextLine<T>> {
+        let seq = self.next_seq;
+        self.next_seq += 1;
+        let key = self.key(request_id);
+        let mut out = Vec::new();
+
+        if matched {
+            if let Some(key) = &key {
+                let before: Vec<(u64, T)> = self
+                    .recent
+                    .get_mut(key)
+                    .map(|queue| queue.drain(..).collect())
+                    .unwrap_or_default();
+                for (before_seq, before_item) in before {
+                    let starts_group = out.is_empty();
+                    self.emit(&mut out, before_seq, ContextLine::Context(before_item), starts_group);
+                }
+                if self.after > 0 {
+                    self.remaining_after.insert(key.clone(), self.after);
+                }
+            }
+            let starts_group = out.is_empty();
+            self.emit(&mut out, seq, ContextLine::Match(item), starts_group);
+            return out;
+        }
+
+        let Some(key) = key else {
+            return out;
+        };
+
+        if let Some(remaining) = self.remaining_after.get_mut(&key) {
+            *remaining -= 1;
+            if *remaining == 0 {
+                self.remaining_after.remove(&key);
+            }
+            self.emit(&mut out, seq, ContextLine::Context(item), false);
+            return out;
+        }
+
+        if self.before > 0 {
+            self.remember(key, seq, item);
+        }
+        out
+    }
+
+    fn remember(&mut self, key: Option<String>, seq: u64, item: T) {
+        if !self.recent.contains_key(&key) {
+            self.request_order.push_back(key.clone());
+            if self.request_order.len() > MAX_TRACKED_REQUESTS {
+                if let Some(oldest) = self.request_order.pop_front() {
+                    self.recent.remove(&oldest);
+                    self.remaining_after.remove(&oldest);
+                }
+            }
+        }
+
+        let queue = self.recent.entry(key).or_default();
+        queue.push_back((seq, item));
+        if queue.len() > self.before {
+            queue.pop_front();
+        }
+    }
+
+    /// Lines within a group (before context, match, after context) are never separated;
+    /// a new group is when it does not touch the previous output
+    fn emit(&mut self, out: &mut Vec<ContextLine<T>>, seq: u64, line: ContextLine<T>, starts_group: bool) {
+        if starts_group && self.last_emitted.is_some_and(|last| seq > last + 1) {
+            out.push(ContextLine::Separator);
+        }
+        self.last_emitted = Some(seq);
+        out.push(line);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(buffer: &mut ContextBuffer<u32>, items: &[(u32, Option<&str>, bool)]) -> Vec<ContextLine<u32>> {
+        items
+            .iter()
+            .flat_map(|&(item, request_id, matched)| buffer.push(item, request_id, matched))
+            .collect()
+    }
+
+    #[test]
+    fn test_before_and_after_with_separator() {
+        use ContextLine::*;
+        let mut buffer = ContextBuffer::new(1, 1, false);
+        let lines = run(
+            &mut buffer,
+            &[
+                (1, None, false),
+                (2, None, false),
+                (3, None, true),
+                (4, None, false),
+                (5, None, false),
+                (6, None, false),
+                (7, None, true),
+            ],
+        );
+        assert_eq!(
+            lines,
+            vec![Context(2), Match(3), Context(4), Separator, Context(6), Match(7)]
+        );
+    }
+
+    #[test]
+    fn test_same_request_only() {
+        use ContextLine::*;
+        let mut buffer = ContextBuffer::new(2, 1, true);
+        let lines = run(
+            &mut buffer,
+            &[
+                (1, Some("a"), false),
+                (2, Some("b"), false),
+                (3, None, false),
+                (4, Some("a"), true),
+                (5, Some("b"), false),
+                (6, Some("a"), false),
+            ],
+        );
+        assert_eq!(lines, vec![Context(1), Match(4), Context(6)]);
+
+        // A later match of the same request is a new group
+        let lines = run(&mut buffer, &[(7, Some("b"), false), (8, Some("a"), true)]);
+        assert_eq!(lines, vec![Separator, Match(8)]);
+    }
+}
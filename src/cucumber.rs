@@ -0,0 +1,353 @@
+//! Karate's `cucumber:json` reports, read after the run for authoritative results
+
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// Result of a step, or of a scenario as a whole
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    Passed,
+    Failed,
+    Skipped,
+}
+
+#[derive(Debug, Clone)]
+pub struct StepReport {
+    pub keyword: String,
+    pub name: String,
+    pub line: u32,
+    pub status: Status,
+    pub duration: Duration,
+    pub error_message: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ScenarioReport {
+    pub name: String,
+    pub line: u32,
+    /// Generated from a Scenario Outline examples row
+    pub outline: bool,
+    pub started: Option<DateTime<Utc>>,
+    pub tags: Vec<String>,
+    /// Background steps first, then the scenario's own
+    pub steps: Vec<StepReport>,
+}
+
+impl ScenarioReport {
+    pub fn status(&self) -> Status {
+        if self.steps.iter().any(|s| s.status == Status::Failed) {
+            Status::Failed
+        } else if self.steps.iter().all(|s| s.status == Status::Skipped) {
+            Status::Skipped
+        } else {
+            Status::Passed
+        }
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.steps.iter().map(|s| s.duration).sum()
+    }
+
+    pub fn failing_step(&self) -> Option<&StepReport> {
+        self.steps.iter().find(|s| s.status == Status::Failed)
+    }
+
+    /// Whether a feature file line falls inside this scenario
+    pub fn contains_line(&self, line: u32) -> bool {
+        let last = self.steps.iter().map(|s| s.line).max().unwrap_or(self.line);
+        (self.line..=last.max(self.line)).contains(&line)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FeatureReport {
+    /// As Karate reports it, e.g. `classpath:features/users.feature`
+    pub uri: String,
+    pub name: String,
+    pub tags: Vec<String>,
+    pub scenarios: Vec<ScenarioReport>,
+}
+
+/// Every feature found in a report directory
+#[derive(Debug, Clone, Default)]
+pub struct CucumberReport {
+    pub features: Vec<FeatureReport>,
+}
+
+impl CucumberReport {
+    /// Parse one report file (an array of features)
+    pub fn parse(json: &str) -> Result<Vec<FeatureReport>, serde_json::Error> {
+        let raw: Vec<RawFeature> = serde_json::from_str(json)?;
+        Ok(raw.into_iter().map(RawFeature::into_report).collect())
+    }
+
+    /// Read the `*.json` reports in `dir` written at or after `since`
+    pub fn load_dir(dir: &Path, since: SystemTime) -> std::io::Result<Self> {
+        Ok(Self {
+            features: ReportWatcher::new(dir, since).poll()?,
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.features.is_empty()
+    }
+
+    pub fn scenarios(&self) -> impl Iterator<Item = (&FeatureReport, &ScenarioReport)> {
+        self.features
+            .iter()
+            .flat_map(|f| f.scenarios.iter().map(move |s| (f, s)))
+    }
+
+    pub fn count(&self, status: Status) -> u32 {
+        self.scenarios().filter(|(_, s)| s.status() == status).count() as u32
+    }
+}
+
+/// Picks up report files as Karate writes them, one per finished feature
+pub struct ReportWatcher {
+    dir: PathBuf,
+    since: SystemTime,
+    seen: HashSet<PathBuf>,
+}
+
+impl ReportWatcher {
+    /// Subdirectories searched below the report dir; Karate 1.x writes to `<dir>/karate-reports/`
+    const MAX_DEPTH: usize = 2;
+
+    pub fn new(dir: &Path, since: SystemTime) -> Self {
+        Self {
+            dir: dir.to_path_buf(),
+            since,
+            seen: HashSet::new(),
+        }
+    }
+
+    /// Features from report files not read before
+    ///
+    /// Files that are not cucumber reports (other tools share the directory) are skipped;
+    /// files that fail to parse are retried on the next poll in case they were still being written.
+    pub fn poll(&mut self) -> std::io::Result<Vec<FeatureReport>> {
+        let mut paths = Vec::new();
+        json_files(&self.dir, Self::MAX_DEPTH, &mut paths)?;
+        let mut paths: Vec<_> = paths
+            .into_iter()
+            .filter(|path| !self.seen.contains(path))
+            .filter(|path| {
+                std::fs::metadata(path)
+                    .and_then(|m| m.modified())
+                    .is_ok_and(|modified| modified >= self.since)
+            })
+            .collect();
+        paths.sort();
+
+        let mut features = Vec::new();
+        for path in paths {
+            let Ok(json) = std::fs::read_to_string(&path) else {
+                continue;
+            };
+            if let Ok(parsed) = CucumberReport::parse(&json) {
+                features.extend(parsed);
+                self.seen.insert(path);
+            }
+        }
+        Ok(features)
+    }
+}
+
+/// `.json` files in `dir` and, down to `depth` levels, its subdirectories
+fn json_files(dir: &Path, depth: usize, out: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(dir)?.filter_map(Result::ok) {
+        let path = entry.path();
+        if path.is_dir() {
+            if depth > 0 {
+                // Unreadable subdirectories are skipped; only the report dir itself must exist
+                let _ = json_files(&path, depth - 1, out);
+            }
+        } else if path.extension().is_some_and(|ext| ext == "json") {
+            out.push(path);
+        }
+    }
+    Ok(())
+}
+
+#[derive(Deserialize)]
+struct RawFeature {
+    #[serde(default)]
+    uri: String,
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    tags: Vec<RawTag>,
+    #[serde(default)]
+    elements: Vec<RawElement>,
+}
+
+#[derive(Deserialize)]
+struct RawTag {
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct RawElement {
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    line: u32,
+    #[serde(default, rename = "type")]
+    kind: String,
+    #[serde(default)]
+    keyword: String,
+    #[serde(default)]
+    start_timestamp: Option<String>,
+    #[serde(default)]
+    tags: Vec<RawTag>,
+    #[serde(default)]
+    steps: Vec<RawStep>,
+}
+
+#[derive(Deserialize)]
+struct RawStep {
+    #[serde(default)]
+    keyword: String,
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    line: u32,
+    #[serde(default)]
+    result: RawResult,
+}
+
+#[derive(Deserialize, Default)]
+struct RawResult {
+    #[serde(default)]
+    status: String,
+    /// Nanoseconds
+    #[serde(default)]
+    duration: u64,
+    #[serde(default)]
+    error_message: Option<String>,
+}
+
+impl RawFeature {
+    fn into_report(self) -> FeatureReport {
+        let mut scenarios = Vec::new();
+        // Background elements precede each scenario they ran for
+        let mut background = Vec::new();
+        for element in self.elements {
+            let steps = element.steps.into_iter().map(RawStep::into_report);
+            if element.kind == "background" {
+                background.extend(steps);
+                continue;
+            }
+            let mut all_steps = std::mem::take(&mut background);
+            all_steps.extend(steps);
+            scenarios.push(ScenarioReport {
+                name: element.name,
+                line: element.line,
+                outline: element.keyword.contains("Outline"),
+                started: element
+                    .start_timestamp
+                    .and_then(|t| DateTime::parse_from_rfc3339(&t).ok())
+                    .map(|t| t.with_timezone(&Utc)),
+                tags: element.tags.into_iter().map(|t| t.name).collect(),
+                steps: all_steps,
+            });
+        }
+
+        FeatureReport {
+            uri: self.uri,
+            name: self.name,
+            tags: self.tags.into_iter().map(|t| t.name).collect(),
+            scenarios,
+        }
+    }
+}
+
+impl RawStep {
+    fn into_report(self) -> StepReport {
+        StepReport {
+            keyword: self.keyword.trim().to_string(),
+            name: self.name,
+            line: self.line,
+            status: match self.result.status.as_str() {
+                "passed" => Status::Passed,
+                "skipped" => Status::Skipped,
+                // failed, undefined, pending, ambiguous
+                _ => Status::Failed,
+            },
+            duration: Duration::from_nanos(self.result.duration),
+            error_message: self.result.error_message,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const REPORT: &str = r#"[{
+        "uri": "classpath:features/users.feature",
+        "name": "Users",
+        "tags": [{"name": "@users", "line": 1}],
+        "elements": [
+            {"type": "background", "line": 3, "name": "", "steps": [
+                {"keyword": "*", "name": "url baseUrl", "line": 4, "result": {"status": "passed", "duration": 1000000}}
+            ]},
+            {"type": "scenario", "line": 6, "name": "list users", "tags": [{"name": "@smoke"}], "steps": [
+                {"keyword": "*", "name": "method get", "line": 7, "result": {"status": "passed", "duration": 20000000}}
+            ]},
+            {"type": "background", "line": 3, "name": "", "steps": [
+                {"keyword": "*", "name": "url baseUrl", "line": 4, "result": {"status": "passed", "duration": 1000000}}
+            ]},
+            {"type": "scenario", "keyword": "Scenario Outline", "line": 9, "name": "create user", "start_timestamp": "2024-05-01T10:00:00.000Z", "steps": [
+                {"keyword": "*", "name": "method post", "line": 10, "result": {"status": "passed", "duration": 5000000}},
+                {"keyword": "*", "name": "match response.id == 1", "line": 11, "result": {"status": "failed", "duration": 100, "error_message": "match failed: EQUALS\n  $.id | not equal"}},
+                {"keyword": "*", "name": "print 'done'", "line": 12, "result": {"status": "skipped"}}
+            ]}
+        ]
+    }]"#;
+
+    #[test]
+    fn test_parse_report_with_backgrounds() {
+        let features = CucumberReport::parse(REPORT).unwrap();
+        let report = CucumberReport { features };
+
+        assert_eq!(report.count(Status::Passed), 1);
+        assert_eq!(report.count(Status::Failed), 1);
+
+        let scenarios: Vec<&ScenarioReport> = report.scenarios().map(|(_, s)| s).collect();
+        assert_eq!(scenarios[0].tags, vec!["@smoke"]);
+        assert_eq!(scenarios[0].steps.len(), 2);
+        assert_eq!(scenarios[0].duration(), Duration::from_millis(21));
+
+        let failing = scenarios[1].failing_step().unwrap();
+        assert_eq!(failing.line, 11);
+        assert!(failing.error_message.as_deref().unwrap().starts_with("match failed"));
+        assert!(scenarios[1].contains_line(11));
+        assert!(scenarios[1].outline && !scenarios[0].outline);
+        assert_eq!(scenarios[1].started.unwrap().to_rfc3339(), "2024-05-01T10:00:00+00:00");
+        assert!(!scenarios[1].contains_line(7));
+    }
+
+    #[test]
+    fn test_non_report_json_is_rejected() {
+        assert!(CucumberReport::parse(r#"{"features": {}}"#).is_err());
+    }
+
+    #[test]
+    fn test_reports_found_under_karate_reports() {
+        let dir = std::env::temp_dir().join(format!("karate-monitor-reports-{}", std::process::id()));
+        let nested = dir.join("karate-reports");
+        std::fs::create_dir_all(&nested).unwrap();
+        std::fs::write(nested.join("features.users.json"), REPORT).unwrap();
+        std::fs::write(dir.join("other.json"), r#"{"tool": "coverage"}"#).unwrap();
+
+        let report = CucumberReport::load_dir(&dir, SystemTime::UNIX_EPOCH).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(report.features.len(), 1);
+        assert_eq!(report.features[0].uri, "classpath:features/users.feature");
+    }
+}
@@ -0,0 +1,758 @@
+//! Full-screen terminal dashboard for long local runs
+
+use crate::analysis::{SqlStats, TestSummary};
+use crate::classify::Classifier;
+use crate::log_parser::{ApiLogEntry, LogLevel, LogType};
+use crate::progress::{format_duration, ProgressStatus};
+use crate::theme::{self, Theme};
+use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use crossterm::execute;
+use crossterm::terminal::{
+    disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen,
+};
+use ratatui::backend::CrosstermBackend;
+use ratatui::layout::{Constraint, Direction, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::symbols::border;
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Clear, Gauge, List, ListItem, ListState, Paragraph, Wrap};
+use ratatui::{Frame, Terminal};
+use std::collections::VecDeque;
+use std::io;
+use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+use tokio::sync::{oneshot, Mutex};
+
+/// Maximum API entries kept for the log pane
+const MAX_API_LOGS: usize = 5000;
+/// Maximum status messages kept
+const MAX_MESSAGES: usize = 200;
+/// Pane borders for themes without box-drawing glyphs
+const ASCII_BORDER: border::Set = border::Set {
+    top_left: "+",
+    top_right: "+",
+    bottom_left: "+",
+    bottom_right: "+",
+    vertical_left: "|",
+    vertical_right: "|",
+    horizontal_top: "-",
+    horizontal_bottom: "-",
+};
+
+/// A failure shown in the failures list, with its correlated API logs
+#[derive(Debug, Clone)]
+pub struct DashboardFailure {
+    pub location: String,
+    pub message: String,
+    pub request_id: Option<String>,
+    pub logs: Vec<(String, ApiLogEntry)>,
+}
+
+/// Events sent from the process handlers to the dashboard
+#[derive(Debug, Clone)]
+pub enum DashboardEvent {
+    /// A status line (startup, Java flags, stderr…)
+    Message(String),
+    /// An API log entry that passed the configured patterns
+    ApiLog(Box<ApiLogEntry>),
+    FeatureStarted(String),
+    FeatureFinished { feature: String, failed: bool },
+    Failure(DashboardFailure),
+    /// Progress against the discovered features, sent after each feature
+    Progress(ProgressStatus),
+    /// The run is over; carries the Karate exit code
+    Finished(i32),
+}
+
+/// Dashboard state
+struct Dashboard {
+    theme: Theme,
+    classifier: Arc<Classifier>,
+    sql_stats: Arc<Mutex<SqlStats>>,
+    test_summary: Arc<Mutex<TestSummary>>,
+    messages: VecDeque<String>,
+    api_logs: VecDeque<ApiLogEntry>,
+    failures: Vec<DashboardFailure>,
+    failure_state: ListState,
+    level: Option<LogLevel>,
+    /// Number of API entries visible when scrolling was paused
+    paused_at: Option<usize>,
+    scroll: usize,
+    current_feature: Option<String>,
+    /// Latest progress and when it arrived (the ETA counts down from there)
+    progress: Option<(ProgressStatus, Instant)>,
+    features_done: usize,
+    features_failed: usize,
+    started: Instant,
+    finished: Option<i32>,
+    detail_open: bool,
+    quit_armed: bool,
+    /// Tells the process manager to stop the run when the user aborts
+    abort: Option<oneshot::Sender<()>>,
+}
+
+/// Start the dashboard on its own thread
+///
+/// The returned sender feeds it events; the thread ends when the user quits after
+/// the run has finished, or aborts it. An abort is signalled on the returned receiver
+/// so the run can stop its processes, and the thread then returns `Ok(false)`.
+pub fn spawn(
+    level: &str,
+    theme: Theme,
+    classifier: Arc<Classifier>,
+    sql_stats: Arc<Mutex<SqlStats>>,
+    test_summary: Arc<Mutex<TestSummary>>,
+) -> (Sender<DashboardEvent>, oneshot::Receiver<()>, JoinHandle<io::Result<bool>>) {
+    let (tx, rx) = mpsc::channel();
+    let (abort_tx, abort_rx) = oneshot::channel();
+    let mut dashboard = Dashboard::new(level, theme, classifier, sql_stats, test_summary);
+    dashboard.abort = Some(abort_tx);
+
+    let handle = std::thread::spawn(move || dashboard.run(rx));
+    (tx, abort_rx, handle)
+}
+
+fn parse_level(level: &str) -> Option<LogLevel> {
+    match level.to_uppercase().as_str() {
+        "DEBUG" => Some(LogLevel::Debug),
+        "INFO" => Some(LogLevel::Info),
+        "WARN" => Some(LogLevel::Warn),
+        "ERROR" => Some(LogLevel::Error),
+        _ => None,
+    }
+}
+
+impl Dashboard {
+    fn new(
+        level: &str,
+        theme: Theme,
+        classifier: Arc<Classifier>,
+        sql_stats: Arc<Mutex<SqlStats>>,
+        test_summary: Arc<Mutex<TestSummary>>,
+    ) -> Self {
+        Self {
+            theme,
+            classifier,
+            sql_stats,
+            test_summary,
+            messages: VecDeque::new(),
+            api_logs: VecDeque::new(),
+            failures: Vec::new(),
+            failure_state: ListState::default(),
+            level: parse_level(level),
+            paused_at: None,
+            scroll: 0,
+            current_feature: None,
+            progress: None,
+            features_done: 0,
+            features_failed: 0,
+            started: Instant::now(),
+            finished: None,
+            detail_open: false,
+            quit_armed: false,
+            abort: None,
+        }
+    }
+
+    /// Returns `Ok(false)` when the user aborted the run
+    fn run(mut self, rx: Receiver<DashboardEvent>) -> io::Result<bool> {
+        // A panic on any thread must not leave the terminal raw and on the alternate screen
+        let default_hook = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            let _ = disable_raw_mode();
+            let _ = execute!(io::stdout(), LeaveAlternateScreen, crossterm::cursor::Show);
+            default_hook(info);
+        }));
+
+        enable_raw_mode()?;
+        execute!(io::stdout(), EnterAlternateScreen)?;
+        let mut terminal = Terminal::new(CrosstermBackend::new(io::stdout()))?;
+
+        let result = self.event_loop(&mut terminal, &rx);
+
+        disable_raw_mode()?;
+        execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+        terminal.show_cursor()?;
+
+        if let Ok(false) = result {
+            // Aborted before the run finished: the process manager stops and cleans up
+            if let Some(abort) = self.abort.take() {
+                let _ = abort.send(());
+            }
+        }
+        result
+    }
+
+    /// Returns `Ok(true)` when closed after the run finished, `Ok(false)` when aborted
+    fn event_loop(
+        &mut self,
+        terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+        rx: &Receiver<DashboardEvent>,
+    ) -> io::Result<bool> {
+        loop {
+            loop {
+                match rx.try_recv() {
+                    Ok(event) => self.apply(event),
+                    Err(TryRecvError::Empty) => break,
+                    Err(TryRecvError::Disconnected) => {
+                        if self.finished.is_none() {
+                            self.finished = Some(1);
+                        }
+                        break;
+                    }
+                }
+            }
+
+            terminal.draw(|frame| self.draw(frame))?;
+
+            if !event::poll(Duration::from_millis(100))? {
+                continue;
+            }
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+
+            let abort = key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL);
+            match key.code {
+                _ if abort => return Ok(false),
+                KeyCode::Char('q') | KeyCode::Esc if self.detail_open => self.detail_open = false,
+                KeyCode::Char('q') => {
+                    if self.finished.is_some() {
+                        return Ok(true);
+                    }
+                    if self.quit_armed {
+                        return Ok(false);
+                    }
+                    self.quit_armed = true;
+                }
+                KeyCode::Char('l') => self.cycle_level(),
+                KeyCode::Char('p') | KeyCode::Char(' ') => self.toggle_pause(),
+                KeyCode::PageUp => {
+                    self.pause();
+                    self.scroll += 10;
+                }
+                KeyCode::PageDown => self.scroll = self.scroll.saturating_sub(10),
+                KeyCode::Up => self.select_failure(-1),
+                KeyCode::Down => self.select_failure(1),
+                KeyCode::Enter => {
+                    self.detail_open = self.failure_state.selected().is_some();
+                }
+                _ => {}
+            }
+            if key.code != KeyCode::Char('q') {
+                self.quit_armed = false;
+            }
+        }
+    }
+
+    fn apply(&mut self, event: DashboardEvent) {
+        match event {
+            DashboardEvent::Message(text) => {
+                self.messages.push_back(text);
+                if self.messages.len() > MAX_MESSAGES {
+                    self.messages.pop_front();
+                }
+            }
+            DashboardEvent::ApiLog(entry) => {
+                self.api_logs.push_back(*entry);
+                if self.api_logs.len() > MAX_API_LOGS {
+                    self.api_logs.pop_front();
+                }
+            }
+            DashboardEvent::FeatureStarted(feature) => self.current_feature = Some(feature),
+            DashboardEvent::FeatureFinished { feature, failed } => {
+                self.features_done += 1;
+                if failed {
+                    self.features_failed += 1;
+                }
+                let borders = &self.theme.borders;
+                let marker = if failed { &borders.fail_marker } else { &borders.pass_marker };
+                self.apply(DashboardEvent::Message(format!("{} {}", marker, feature)));
+            }
+            DashboardEvent::Failure(failure) => {
+                self.failures.push(failure);
+                if self.failure_state.selected().is_none() {
+                    self.failure_state.select(Some(0));
+                }
+            }
+            DashboardEvent::Progress(status) => self.progress = Some((status, Instant::now())),
+            DashboardEvent::Finished(code) => self.finished = Some(code),
+        }
+    }
+
+    fn cycle_level(&mut self) {
+        self.level = match self.level {
+            None => Some(LogLevel::Debug),
+            Some(LogLevel::Debug) => Some(LogLevel::Info),
+            Some(LogLevel::Info) => Some(LogLevel::Warn),
+            Some(LogLevel::Warn) => Some(LogLevel::Error),
+            Some(LogLevel::Error) => None,
+        };
+        self.scroll = 0;
+    }
+
+    fn pause(&mut self) {
+        if self.paused_at.is_none() {
+            self.paused_at = Some(self.api_logs.len());
+        }
+    }
+
+    fn toggle_pause(&mut self) {
+        if self.paused_at.is_some() {
+            self.paused_at = None;
+            self.scroll = 0;
+        } else {
+            self.pause();
+        }
+    }
+
+    fn select_failure(&mut self, delta: isize) {
+        if self.failures.is_empty() {
+            return;
+        }
+        let current = self.failure_state.selected().unwrap_or(0) as isize;
+        let next = (current + delta).clamp(0, self.failures.len() as isize - 1);
+        self.failure_state.select(Some(next as usize));
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let rows = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(3),
+                Constraint::Length(4),
+                Constraint::Min(8),
+                Constraint::Length(6),
+                Constraint::Length(1),
+            ])
+            .split(frame.area());
+
+        self.draw_progress(frame, rows[0]);
+        self.draw_counters(frame, rows[1]);
+
+        let panes = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(40), Constraint::Percentage(60)])
+            .split(rows[2]);
+        self.draw_failures(frame, panes[0]);
+        self.draw_api_logs(frame, panes[1]);
+        self.draw_messages(frame, rows[3]);
+        self.draw_help(frame, rows[4]);
+
+        if self.detail_open {
+            self.draw_failure_detail(frame);
+        }
+    }
+
+    fn draw_progress(&self, frame: &mut Frame, area: Rect) {
+        let elapsed = self.started.elapsed().as_secs();
+        let sep = &self.theme.borders.separator;
+        let starting = format!("starting{}", self.theme.borders.ellipsis);
+        let feature = self.current_feature.as_deref().unwrap_or(&starting);
+        let status = match self.finished {
+            Some(0) => "finished".to_string(),
+            Some(code) => format!("finished (exit {})", code),
+            None => "running".to_string(),
+        };
+        let title = format!(
+            " Karate Monitor{}{}{}{:02}:{:02} ",
+            sep,
+            status,
+            sep,
+            elapsed / 60,
+            elapsed % 60
+        );
+
+        let discovered = self.progress.as_ref().and_then(|(p, _)| p.features_total);
+        let total = discovered
+            .unwrap_or_else(|| self.test_summary.blocking_lock().total_features as usize)
+            .max(self.features_done);
+        let mut label = format!(
+            "features {}{} ({} failed)",
+            self.features_done,
+            if total > 0 { format!("/{}", total) } else { String::new() },
+            self.features_failed,
+        );
+        if let Some((status, received)) = &self.progress {
+            label.push_str(&format!("{}scenarios {}", sep, status.scenarios_done));
+            if let Some(total) = status.scenarios_total {
+                label.push_str(&format!("/{}", total));
+            }
+            if let (Some(eta), None) = (status.eta, self.finished) {
+                label.push_str(&format!(
+                    "{}ETA {}",
+                    sep,
+                    format_duration(eta.saturating_sub(received.elapsed()))
+                ));
+            }
+        }
+        label.push_str(&format!("{}{}", sep, feature));
+        let ratio = if self.finished.is_some() {
+            1.0
+        } else if total > 0 {
+            self.features_done as f64 / total as f64
+        } else {
+            0.0
+        };
+        let palette = &self.theme.palette;
+        let style = if self.features_failed > 0 { palette.error } else { palette.success };
+
+        let gauge = Gauge::default()
+            .block(self.block(title))
+            .gauge_style(tui_style(style))
+            .ratio(ratio.clamp(0.0, 1.0))
+            .label(label);
+        frame.render_widget(gauge, area);
+    }
+
+    fn draw_counters(&self, frame: &mut Frame, area: Rect) {
+        let (total, passed, failed) = {
+            let summary = self.test_summary.blocking_lock();
+            (summary.total_scenarios, summary.passed, summary.failed)
+        };
+        let (queries, sql_errors, sql_ms) = {
+            let stats = self.sql_stats.blocking_lock();
+            (stats.total_queries, stats.error_count, stats.total_elapsed_ms)
+        };
+
+        let palette = &self.theme.palette;
+        let count = |n: u32| tui_style(if n > 0 { palette.error } else { palette.success });
+        let lines = vec![
+            Line::from(vec![
+                Span::raw("Scenarios: "),
+                Span::styled(total.to_string(), tui_style(palette.text_strong)),
+                Span::raw("  passed "),
+                Span::styled(passed.to_string(), tui_style(palette.success)),
+                Span::raw("  failed "),
+                Span::styled(failed.to_string(), count(failed)),
+                Span::raw(format!("  failures seen {}", self.failures.len())),
+            ]),
+            Line::from(vec![
+                Span::raw("SQL: "),
+                Span::styled(queries.to_string(), tui_style(palette.text_strong)),
+                Span::raw(" queries  errors "),
+                Span::styled(sql_errors.to_string(), count(sql_errors)),
+                Span::raw(format!("  total {:.1}ms", sql_ms)),
+            ]),
+        ];
+
+        let counters = Paragraph::new(lines).block(self.block(" Counters ".to_string()));
+        frame.render_widget(counters, area);
+    }
+
+    fn draw_failures(&mut self, frame: &mut Frame, area: Rect) {
+        let palette = &self.theme.palette;
+        let items: Vec<ListItem> = self
+            .failures
+            .iter()
+            .map(|failure| {
+                ListItem::new(vec![
+                    Line::from(Span::styled(
+                        format!("{} {}", self.theme.borders.fail_marker, failure.location),
+                        tui_style(palette.error_strong),
+                    )),
+                    Line::from(Span::styled(format!("  {}", failure.message), tui_style(palette.muted))),
+                ])
+            })
+            .collect();
+
+        let list = List::new(items)
+            .block(self.block(format!(" Failures ({}) ", self.failures.len())))
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+        frame.render_stateful_widget(list, area, &mut self.failure_state);
+    }
+
+    fn draw_api_logs(&self, frame: &mut Frame, area: Rect) {
+        let visible: Vec<&ApiLogEntry> = self
+            .api_logs
+            .iter()
+            .take(self.paused_at.unwrap_or(self.api_logs.len()))
+            .filter(|entry| self.level.is_none_or(|min| entry.log_level() >= min))
+            .collect();
+
+        let height = area.height.saturating_sub(2) as usize;
+        let end = visible.len().saturating_sub(self.scroll);
+        let start = end.saturating_sub(height);
+        let lines: Vec<Line> = visible[start..end]
+            .iter()
+            .map(|entry| api_line(entry, &self.classifier, &self.theme.palette))
+            .collect();
+
+        let level = match self.level {
+            None => "ALL".to_string(),
+            Some(level) => format!("{:?}", level).to_uppercase(),
+        };
+        let sep = &self.theme.borders.separator;
+        let mut title = format!(" API logs{}level {} ", sep, level);
+        if self.paused_at.is_some() {
+            title.push_str(&format!("{}paused ", sep.trim_start()));
+        }
+
+        let pane = Paragraph::new(lines).block(self.block(title));
+        frame.render_widget(pane, area);
+    }
+
+    fn draw_messages(&self, frame: &mut Frame, area: Rect) {
+        let height = area.height.saturating_sub(2) as usize;
+        let start = self.messages.len().saturating_sub(height);
+        let lines: Vec<Line> = self
+            .messages
+            .iter()
+            .skip(start)
+            .map(|m| Line::from(m.as_str()))
+            .collect();
+        let pane = Paragraph::new(lines).block(self.block(" Messages ".to_string()));
+        frame.render_widget(pane, area);
+    }
+
+    fn draw_help(&self, frame: &mut Frame, area: Rect) {
+        let keys: &[&str] = if self.finished.is_some() {
+            &[
+                "q close and print summary",
+                "l level",
+                "p pause",
+                "PgUp/PgDn scroll",
+                "Up/Down select",
+                "Enter logs",
+            ]
+        } else {
+            &[
+                "q quit",
+                "l level",
+                "p pause",
+                "PgUp/PgDn scroll",
+                "Up/Down select failure",
+                "Enter correlated logs",
+            ]
+        };
+        let text = if self.quit_armed {
+            "Run in progress: press q again to abort, any other key to continue".to_string()
+        } else {
+            keys.join(&self.theme.borders.separator)
+        };
+        frame.render_widget(
+            Paragraph::new(text).style(tui_style(self.theme.palette.muted)),
+            area,
+        );
+    }
+
+    fn draw_failure_detail(&self, frame: &mut Frame) {
+        let Some(failure) = self.failure_state.selected().and_then(|i| self.failures.get(i)) else {
+            return;
+        };
+
+        let palette = &self.theme.palette;
+        let area = centered(frame.area(), 90, 80);
+        let mut lines = vec![
+            Line::from(Span::styled(failure.location.clone(), tui_style(palette.error_strong))),
+            Line::from(failure.message.clone()),
+            Line::from(""),
+        ];
+        if failure.logs.is_empty() {
+            lines.push(Line::from(Span::styled("No correlated API logs", tui_style(palette.muted))));
+        }
+        lines.extend(failure.logs.iter().map(|(_, entry)| api_line(entry, &self.classifier, palette)));
+
+        let title = format!(
+            " Related API Logs: {} (Esc to close) ",
+            failure.request_id.as_deref().unwrap_or("-")
+        );
+        frame.render_widget(Clear, area);
+        frame.render_widget(
+            Paragraph::new(lines)
+                .wrap(Wrap { trim: false })
+                .block(self.block(title)),
+            area,
+        );
+    }
+
+    /// A bordered pane in the theme's frame colour, with ASCII corners for ASCII themes
+    fn block(&self, title: String) -> Block<'static> {
+        let set = if self.theme.borders.rule.is_ascii() { ASCII_BORDER } else { border::PLAIN };
+        Block::default()
+            .borders(Borders::ALL)
+            .border_set(set)
+            .border_style(tui_style(self.theme.palette.frame))
+            .title(title)
+    }
+}
+
+/// One-line summary of an API entry, coloured by level and type
+fn api_line(entry: &ApiLogEntry, classifier: &Classifier, palette: &theme::Palette) -> Line<'static> {
+    let time = entry
+        .time
+        .as_deref()
+        .and_then(|t| t.get(11..19))
+        .unwrap_or("--:--:--")
+        .to_string();
+
+    let style = tui_style(match (entry.log_level(), classifier.api(entry).log_type) {
+        (LogLevel::Error, _) | (_, LogType::ApiError) => palette.error,
+        (LogLevel::Warn, _) => palette.warn,
+        (_, LogType::ApiSql) => palette.sql,
+        (LogLevel::Debug, _) => palette.muted,
+        _ => palette.text,
+    });
+
+    let mut text = entry.msg.clone();
+    if let Some(method) = &entry.method {
+        text.push_str(&format!(" {}", method));
+    }
+    if let Some(uri) = &entry.uri {
+        text.push_str(&format!(" {}", uri));
+    }
+    if let Some(status) = entry.status {
+        text.push_str(&format!(" {}", status));
+    }
+    if let Some(sql) = &entry.sql {
+        text.push_str(&format!(" {}", sql.split_whitespace().collect::<Vec<_>>().join(" ")));
+    }
+    let (logged, parsed) = match &entry.elapsed {
+        Some(elapsed) => (Some(elapsed), entry.elapsed_duration),
+        None => (entry.latency_human.as_ref(), entry.latency),
+    };
+    // Durations in one unit, whatever the logger printed
+    match (parsed, logged) {
+        (Some(duration), _) => text.push_str(&format!(" [{:.2}ms]", duration.as_nanos() as f64 / 1e6)),
+        (None, Some(logged)) => text.push_str(&format!(" [{}]", logged)),
+        (None, None) => {}
+    }
+    if let Some(err) = &entry.err {
+        text.push_str(&format!(" err={}", err));
+    }
+
+    Line::from(vec![
+        Span::styled(format!("{} ", time), tui_style(palette.muted)),
+        Span::styled(format!("{:<5} ", entry.level), style),
+        Span::styled(text, style),
+    ])
+}
+
+/// The ratatui equivalent of a theme style
+fn tui_style(style: theme::Style) -> Style {
+    let mut out = Style::default();
+    if let Some(color) = style.fg {
+        out = out.fg(tui_color(color));
+    }
+    if style.bold {
+        out = out.add_modifier(Modifier::BOLD);
+    }
+    if style.dimmed {
+        out = out.add_modifier(Modifier::DIM);
+    }
+    out
+}
+
+fn tui_color(color: colored::Color) -> Color {
+    use colored::Color as C;
+    match color {
+        C::Black => Color::Black,
+        C::Red => Color::Red,
+        C::Green => Color::Green,
+        C::Yellow => Color::Yellow,
+        C::Blue => Color::Blue,
+        C::Magenta => Color::Magenta,
+        C::Cyan => Color::Cyan,
+        C::White => Color::Gray,
+        C::BrightBlack => Color::DarkGray,
+        C::BrightRed => Color::LightRed,
+        C::BrightGreen => Color::LightGreen,
+        C::BrightYellow => Color::LightYellow,
+        C::BrightBlue => Color::LightBlue,
+        C::BrightMagenta => Color::LightMagenta,
+        C::BrightCyan => Color::LightCyan,
+        C::BrightWhite => Color::White,
+        C::TrueColor { r, g, b } => Color::Rgb(r, g, b),
+    }
+}
+
+/// A rectangle centered in `area` taking the given percentages
+fn centered(area: Rect, width_pct: u16, height_pct: u16) -> Rect {
+    let width = (area.width as u32 * width_pct as u32 / 100) as u16;
+    let height = (area.height as u32 * height_pct as u32 / 100) as u16;
+    Rect {
+        x: area.x.saturating_add(area.width.saturating_sub(width) / 2),
+        y: area.y.saturating_add(area.height.saturating_sub(height) / 2),
+        width,
+        height,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dashboard() -> Dashboard {
+        Dashboard::new(
+            "ALL",
+            Theme::ascii(),
+            Arc::new(Classifier::new(&Default::default())),
+            Arc::new(Mutex::new(SqlStats::new())),
+            Arc::new(Mutex::new(TestSummary::new())),
+        )
+    }
+
+    #[test]
+    fn test_cycle_level_wraps_to_all() {
+        let mut d = dashboard();
+        assert_eq!(d.level, None);
+        for _ in 0..4 {
+            d.cycle_level();
+        }
+        assert_eq!(d.level, Some(LogLevel::Error));
+        d.cycle_level();
+        assert_eq!(d.level, None);
+    }
+
+    #[test]
+    fn test_centered_on_wide_and_tiny_areas() {
+        let wide = centered(Rect::new(0, 0, 1000, 300), 90, 80);
+        assert_eq!((wide.x, wide.y, wide.width, wide.height), (50, 30, 900, 240));
+        let tiny = centered(Rect::new(2, 1, 1, 1), 90, 80);
+        assert_eq!((tiny.x, tiny.y, tiny.width, tiny.height), (2, 1, 0, 0));
+    }
+
+    #[test]
+    fn test_events_update_state() {
+        let mut d = dashboard();
+        d.apply(DashboardEvent::FeatureStarted("users.feature".to_string()));
+        d.apply(DashboardEvent::FeatureFinished {
+            feature: "users.feature".to_string(),
+            failed: true,
+        });
+        d.apply(DashboardEvent::Failure(DashboardFailure {
+            location: "users.feature:12".to_string(),
+            message: "status code was: 500".to_string(),
+            request_id: None,
+            logs: vec![],
+        }));
+
+        assert_eq!(d.features_done, 1);
+        assert_eq!(d.features_failed, 1);
+        assert_eq!(d.failure_state.selected(), Some(0));
+        assert_eq!(d.messages.back().map(String::as_str), Some("x users.feature"));
+    }
+
+    #[test]
+    fn test_ascii_theme_draws_ascii_only() {
+        let mut d = dashboard();
+        d.apply(DashboardEvent::Failure(DashboardFailure {
+            location: "users.feature:12".to_string(),
+            message: "status code was: 500".to_string(),
+            request_id: None,
+            logs: vec![],
+        }));
+        d.detail_open = true;
+        let mut terminal = Terminal::new(ratatui::backend::TestBackend::new(100, 30)).unwrap();
+        terminal.draw(|frame| d.draw(frame)).unwrap();
+
+        let screen: String = terminal.backend().buffer().content().iter().map(|c| c.symbol()).collect();
+        assert!(screen.is_ascii(), "{}", screen);
+        assert!(screen.contains("Karate Monitor | running | 00:00"));
+    }
+}
@@ -0,0 +1,204 @@
+//! Collapsing of repeated API entries and per-message rate limiting
+
+use crate::log_parser::ApiLogEntry;
+use regex::Regex;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// What to do after an entry was pushed, in order
+#[derive(Debug, Clone, PartialEq)]
+pub enum DedupEvent<T> {
+    /// Show (or export) this entry
+    Show(T),
+    /// The entry shown earlier was repeated `count` more times in a row
+    Repeated { item: T, count: usize },
+    /// Entries with this message were dropped by the rate limit during one window
+    RateLimited { msg: String, count: usize, window: Duration },
+}
+
+/// Rate limit state for one message
+struct Window {
+    started: Instant,
+    shown: usize,
+    suppressed: usize,
+}
+
+/// Collapses consecutive near-identical entries and rate-limits noisy messages
+pub struct Deduplicator<T> {
+    collapse: bool,
+    /// Entries per message and window; 0 disables the limit
+    rate_limit: usize,
+    window: Duration,
+    /// Key, first occurrence and repeat count of the current run
+    last: Option<(String, T, usize)>,
+    windows: HashMap<String, Window>,
+    /// UUIDs and standalone numbers (path segments, IDs), ignored when comparing entries;
+    /// numbers inside words such as `v1` are kept
+    ids: Regex,
+}
+
+impl<T: Clone> Deduplicator<T> {
+    pub fn new(collapse: bool, rate_limit: usize, window: Duration) -> Self {
+        Self {
+            collapse,
+            rate_limit,
+            window,
+            last: None,
+            windows: HashMap::new(),
+            ids: Regex::new(r"[0-9a-fA-F]{8}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{12}|\b\d+\b")
+                .expect("valid id regex"),
+        }
+    }
+
+    fn normalize(&self, text: &str) -> String {
+        self.ids.replace_all(text, "#").into_owned()
+    }
+
+    /// Add the next entry; `item` is what gets shown (typically the raw line and entry)
+    pub fn push(&mut self, item: T, entry: &ApiLogEntry, now: Instant) -> Vec<DedupEvent<T>> {
+        let mut events = self.expire_windows(now);
+
+        let key = format!(
+            "{}|{}|{}",
+            entry.level,
+            self.normalize(&entry.msg),
+            self.normalize(entry.uri.as_deref().unwrap_or_default())
+        );
+
+        if self.collapse {
+            if let Some((last_key, _, count)) = &mut self.last {
+                if *last_key == key {
+                    *count += 1;
+                    return events;
+                }
+            }
+        }
+
+        if self.rate_limit > 0 {
+            let msg_key = format!("{}|{}", entry.level, self.normalize(&entry.msg));
+            let window = self.windows.entry(msg_key).or_insert(Window {
+                started: now,
+                shown: 0,
+                suppressed: 0,
+            });
+            if window.shown >= self.rate_limit {
+                window.suppressed += 1;
+                return events;
+            }
+            window.shown += 1;
+        }
+
+        events.extend(self.end_run());
+        if self.collapse {
+            self.last = Some((key, item.clone(), 0));
+        }
+        events.push(DedupEvent::Show(item));
+        events
+    }
+
+    /// Report pending repeats and suppressed counts (at the end of the stream)
+    pub fn flush(&mut self) -> Vec<DedupEvent<T>> {
+        let mut events: Vec<_> = self.end_run().into_iter().collect();
+        events.extend(self.drain_windows(|_| true));
+        events
+    }
+
+    fn end_run(&mut self) -> Option<DedupEvent<T>> {
+        match self.last.take() {
+            Some((_, item, count)) if count > 0 => Some(DedupEvent::Repeated { item, count }),
+            _ => None,
+        }
+    }
+
+    fn expire_windows(&mut self, now: Instant) -> Vec<DedupEvent<T>> {
+        let window = self.window;
+        self.drain_windows(|w| now.duration_since(w.started) >= window)
+    }
+
+    fn drain_windows(&mut self, expired: impl Fn(&Window) -> bool) -> Vec<DedupEvent<T>> {
+        let keys: Vec<String> = self
+            .windows
+            .iter()
+            .filter(|(_, w)| expired(w))
+            .map(|(k, _)| k.clone())
+            .collect();
+
+        let mut events = Vec::new();
+        for key in keys {
+            if let Some(w) = self.windows.remove(&key) {
+                if w.suppressed > 0 {
+                    let msg = key.split_once('|').map(|(_, msg)| msg).unwrap_or(&key);
+                    events.push(DedupEvent::RateLimited {
+                        msg: msg.to_string(),
+                        count: w.suppressed,
+                        window: self.window,
+                    });
+                }
+            }
+        }
+        events
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(msg: &str, uri: &str) -> ApiLogEntry {
+        ApiLogEntry {
+            msg: msg.to_string(),
+            uri: Some(uri.to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_collapses_consecutive_entries_ignoring_ids() {
+        let mut dedup = Deduplicator::new(true, 0, Duration::from_secs(60));
+        let now = Instant::now();
+        let poll = |id: u32| entry("REQUEST", &format!("/api/v1/jobs/{}/status", id));
+
+        let mut events = Vec::new();
+        let entries = [poll(1), poll(2), poll(3), entry("REQUEST", "/api/v2/jobs/4/status")];
+        for (i, e) in entries.iter().enumerate() {
+            events.extend(dedup.push(i, e, now));
+        }
+        events.extend(dedup.flush());
+
+        assert_eq!(
+            events,
+            vec![
+                DedupEvent::Show(0),
+                DedupEvent::Repeated { item: 0, count: 2 },
+                DedupEvent::Show(3),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_rate_limit_per_message_window() {
+        let window = Duration::from_secs(10);
+        let mut dedup = Deduplicator::new(false, 2, window);
+        let start = Instant::now();
+        let retry = entry("retrying connection", "/db");
+
+        let shown = (0..5)
+            .flat_map(|i| dedup.push(i, &retry, start))
+            .filter(|e| matches!(e, DedupEvent::Show(_)))
+            .count();
+        assert_eq!(shown, 2);
+
+        let later = dedup.push(9, &retry, start + window);
+        assert_eq!(
+            later,
+            vec![
+                DedupEvent::RateLimited {
+                    msg: "retrying connection".to_string(),
+                    count: 3,
+                    window,
+                },
+                DedupEvent::Show(9),
+            ]
+        );
+    }
+}
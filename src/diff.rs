@@ -0,0 +1,186 @@
+//! Structural JSON diff for Karate `match` failures
+
+use crate::theme::Theme;
+use serde_json::Value;
+
+/// Kind of difference at a JSON path
+#[derive(Debug, Clone, PartialEq)]
+pub enum DiffKind {
+    /// Present in actual but not expected
+    Added,
+    /// Present in expected but missing from actual
+    Removed,
+    /// Present in both with different values
+    Changed,
+}
+
+/// A single difference between expected and actual
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiffEntry {
+    pub path: String,
+    pub kind: DiffKind,
+    pub expected: Option<Value>,
+    pub actual: Option<Value>,
+}
+
+/// Parse a value as printed by Karate, falling back to a plain string
+pub fn parse_value(text: &str) -> Value {
+    let trimmed = text.trim();
+    serde_json::from_str(trimmed).unwrap_or_else(|_| {
+        // Karate prints strings in single quotes in some messages
+        let unquoted = trimmed
+            .strip_prefix('\'')
+            .and_then(|s| s.strip_suffix('\''))
+            .unwrap_or(trimmed);
+        Value::String(unquoted.to_string())
+    })
+}
+
+/// Compare two JSON values, returning differences in document order
+pub fn json_diff(expected: &Value, actual: &Value) -> Vec<DiffEntry> {
+    let mut entries = Vec::new();
+    diff_at("$", expected, actual, &mut entries);
+    entries
+}
+
+fn diff_at(path: &str, expected: &Value, actual: &Value, out: &mut Vec<DiffEntry>) {
+    match (expected, actual) {
+        (Value::Object(exp), Value::Object(act)) => {
+            for (key, exp_value) in exp {
+                let child = format!("{}.{}", path, key);
+                match act.get(key) {
+                    Some(act_value) => diff_at(&child, exp_value, act_value, out),
+                    None => out.push(DiffEntry {
+                        path: child,
+                        kind: DiffKind::Removed,
+                        expected: Some(exp_value.clone()),
+                        actual: None,
+                    }),
+                }
+            }
+            for (key, act_value) in act {
+                if !exp.contains_key(key) {
+                    out.push(DiffEntry {
+                        path: format!("{}.{}", path, key),
+                        kind: DiffKind::Added,
+                        expected: None,
+                        actual: Some(act_value.clone()),
+                    });
+                }
+            }
+        }
+        (Value::Array(exp), Value::Array(act)) => {
+            for i in 0..exp.len().max(act.len()) {
+                let child = format!("{}[{}]", path, i);
+                match (exp.get(i), act.get(i)) {
+                    (Some(e), Some(a)) => diff_at(&child, e, a, out),
+                    (Some(e), None) => out.push(DiffEntry {
+                        path: child,
+                        kind: DiffKind::Removed,
+                        expected: Some(e.clone()),
+                        actual: None,
+                    }),
+                    (None, Some(a)) => out.push(DiffEntry {
+                        path: child,
+                        kind: DiffKind::Added,
+                        expected: None,
+                        actual: Some(a.clone()),
+                    }),
+                    (None, None) => {}
+                }
+            }
+        }
+        (e, a) if e != a => out.push(DiffEntry {
+            path: path.to_string(),
+            kind: DiffKind::Changed,
+            expected: Some(e.clone()),
+            actual: Some(a.clone()),
+        }),
+        _ => {}
+    }
+}
+
+/// Render up to `max_entries` diff entries as coloured lines, each prefixed with `indent`
+pub fn render_diff(entries: &[DiffEntry], theme: &Theme, indent: &str, max_entries: usize) -> String {
+    let palette = &theme.palette;
+    let compact = |v: &Option<Value>| {
+        v.as_ref()
+            .map(|v| serde_json::to_string(v).unwrap_or_default())
+            .unwrap_or_default()
+    };
+
+    let mut lines: Vec<String> = entries
+        .iter()
+        .take(max_entries)
+        .map(|entry| {
+            let line = match entry.kind {
+                DiffKind::Added => palette
+                    .success
+                    .paint(&format!("+ {}: {}", entry.path, compact(&entry.actual)))
+                    .to_string(),
+                DiffKind::Removed => palette
+                    .error
+                    .paint(&format!("- {}: {}", entry.path, compact(&entry.expected)))
+                    .to_string(),
+                DiffKind::Changed => format!(
+                    "{} {} {} {}",
+                    palette.warn.paint(&format!("~ {}:", entry.path)),
+                    palette.error.paint(&compact(&entry.expected)),
+                    palette.muted.paint(&theme.borders.arrow),
+                    palette.success.paint(&compact(&entry.actual))
+                ),
+            };
+            format!("{}{}", indent, line)
+        })
+        .collect();
+
+    if entries.len() > max_entries {
+        lines.push(format!(
+            "{}{}",
+            indent,
+            palette
+                .muted
+                .paint(&format!("{} {} more differences", theme.borders.ellipsis, entries.len() - max_entries))
+        ));
+    }
+
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_object_diff() {
+        let expected = json!({"a": 1, "b": 3, "gone": true, "list": [1, 2]});
+        let actual = json!({"a": 1, "b": 2, "extra": "x", "list": [1, 2, 3]});
+        let diff = json_diff(&expected, &actual);
+
+        assert_eq!(diff.len(), 4);
+        assert_eq!(diff[0].path, "$.b");
+        assert_eq!(diff[0].kind, DiffKind::Changed);
+        assert_eq!(diff[1].path, "$.gone");
+        assert_eq!(diff[1].kind, DiffKind::Removed);
+        assert_eq!(diff[2].path, "$.list[2]");
+        assert_eq!(diff[2].kind, DiffKind::Added);
+        assert_eq!(diff[3].path, "$.extra");
+        assert_eq!(diff[3].kind, DiffKind::Added);
+    }
+
+    #[test]
+    fn test_parse_value_fallback() {
+        assert_eq!(parse_value(" 42 "), json!(42));
+        assert_eq!(parse_value("'abc'"), json!("abc"));
+        assert_eq!(parse_value("not json"), json!("not json"));
+    }
+
+    #[test]
+    fn test_render_uses_theme_glyphs() {
+        let diff = json_diff(&json!({"a": 1, "b": 2}), &json!({"a": 3, "b": 4}));
+        let out = render_diff(&diff, &Theme::ascii(), "", 1);
+        let out = regex::Regex::new(r"\x1b\[[0-9;]*m").unwrap().replace_all(&out, "");
+        assert_eq!(out, "~ $.a: 1 -> 3\n... 1 more differences");
+    }
+}
@@ -77,10 +77,12 @@ impl LogExporter {
     }
 
     /// Write an API log entry
-    pub fn write_api_log(&mut self, raw_json: &str, _entry: &ApiLogEntry) -> std::io::Result<()> {
-        // Write to JSON (collect for array output)
+    pub fn write_api_log(&mut self, raw_json: &str, entry: &ApiLogEntry) -> std::io::Result<()> {
+        // Write to JSON (collect for array output); logfmt and plain lines export the parsed entry
         if self.json_writer.is_some() {
-            if let Ok(value) = serde_json::from_str::<serde_json::Value>(raw_json) {
+            let value = serde_json::from_str::<serde_json::Value>(raw_json)
+                .or_else(|_| serde_json::to_value(entry));
+            if let Ok(value) = value {
                 self.json_entries.push(value);
             }
         }
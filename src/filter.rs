@@ -1,41 +1,53 @@
 //! Log filtering based on level and patterns
 
-use crate::log_parser::{ApiLogEntry, LogLevel};
+use crate::classify::Classifier;
+use crate::config::{FiltersConfig, LoggingConfig, SourceFilterConfig};
+use crate::filter_expr::FilterExpr;
+use crate::formatter::summary_has_failures;
+use crate::log_parser::{ApiLogEntry, LogLevel, LogType};
 use regex::Regex;
+use std::sync::Arc;
 
 /// Filter configuration for log entries
 pub struct LogFilter {
     pub level: Option<LogLevel>,
     pub include_patterns: Vec<Regex>,
     pub exclude_patterns: Vec<Regex>,
+    /// Field-aware expression applied after the level and patterns
+    pub expression: Option<FilterExpr>,
 }
 
 impl LogFilter {
-    /// Create a new filter from configuration
-    pub fn new(level: &str, include: &[String], exclude: &[String]) -> Self {
+    /// Create a new filter from configuration, failing on an unknown level or invalid regex
+    pub fn new(level: &str, include: &[String], exclude: &[String]) -> Result<Self, String> {
         let level = match level.to_uppercase().as_str() {
+            "ALL" => None,
             "DEBUG" => Some(LogLevel::Debug),
             "INFO" => Some(LogLevel::Info),
             "WARN" => Some(LogLevel::Warn),
             "ERROR" => Some(LogLevel::Error),
-            "ALL" | _ => None,
+            _ => return Err(format!("unknown level \"{}\"", level)),
         };
 
-        let include_patterns = include
-            .iter()
-            .filter_map(|p| Regex::new(p).ok())
-            .collect();
-
-        let exclude_patterns = exclude
-            .iter()
-            .filter_map(|p| Regex::new(p).ok())
-            .collect();
+        let compile = |patterns: &[String]| {
+            patterns
+                .iter()
+                .map(|p| Regex::new(p).map_err(|e| format!("invalid regex\n{}", e)))
+                .collect::<Result<Vec<_>, _>>()
+        };
 
-        Self {
+        Ok(Self {
             level,
-            include_patterns,
-            exclude_patterns,
-        }
+            include_patterns: compile(include)?,
+            exclude_patterns: compile(exclude)?,
+            expression: None,
+        })
+    }
+
+    /// Also require entries to match a compiled filter expression
+    pub fn with_expression(mut self, expression: Option<FilterExpr>) -> Self {
+        self.expression = expression;
+        self
     }
 
     /// Check if an API log entry should be included
@@ -64,11 +76,13 @@ impl LogFilter {
         }
 
         // Check include patterns (if any exist, at least one must match)
-        if !self.include_patterns.is_empty() {
-            return self.include_patterns.iter().any(|p| p.is_match(&searchable));
+        if !self.include_patterns.is_empty()
+            && !self.include_patterns.iter().any(|p| p.is_match(&searchable))
+        {
+            return false;
         }
 
-        true
+        self.expression.as_ref().is_none_or(|expr| expr.matches(entry))
     }
 
     /// Check if a raw line should be included
@@ -89,13 +103,200 @@ impl LogFilter {
     }
 }
 
+/// Where a log line came from, for per-source filter rules
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogSource {
+    Api,
+    Sql,
+    Body,
+    Batch,
+    Karate,
+}
+
+impl LogSource {
+    /// Source of an API log entry printed by the API process
+    pub fn of_api_entry(entry: &ApiLogEntry, classifier: &Classifier) -> Self {
+        match classifier.api(entry).log_type {
+            LogType::ApiSql => LogSource::Sql,
+            LogType::ApiError if entry.sql.is_some() => LogSource::Sql,
+            LogType::ApiBodyDump => LogSource::Body,
+            _ => LogSource::Api,
+        }
+    }
+}
+
+/// Level of a Karate output line: failures are errors, other lines use the Java log level
+pub fn karate_line_level(line: &str, log_type: &LogType) -> LogLevel {
+    match log_type {
+        LogType::KarateFailure => LogLevel::Error,
+        LogType::KarateSummary if summary_has_failures(line) => LogLevel::Error,
+        _ if line.contains(" ERROR ") => LogLevel::Error,
+        _ if line.contains(" WARN ") => LogLevel::Warn,
+        _ if line.contains(" DEBUG ") => LogLevel::Debug,
+        _ => LogLevel::Info,
+    }
+}
+
+/// Filter expressions of `[logging]` and the API sources, parsed once at startup
+#[derive(Debug, Clone, Default)]
+pub struct FilterExprs {
+    logging: Option<FilterExpr>,
+    api: Option<FilterExpr>,
+    sql: Option<FilterExpr>,
+    body: Option<FilterExpr>,
+    batch: Option<FilterExpr>,
+}
+
+impl FilterExprs {
+    /// Parse every configured expression; the error names the key of the first invalid one
+    pub fn parse(logging: &LoggingConfig, filters: &FiltersConfig) -> Result<Self, String> {
+        let parse = |path: &str, filter: &Option<String>| {
+            filter
+                .as_deref()
+                .map(|f| FilterExpr::parse(f).map_err(|e| format!("{}: {}", path, e.render(f))))
+                .transpose()
+        };
+        Ok(Self {
+            logging: parse("logging.filter", &logging.filter)?,
+            api: parse("filters.api.filter", &filters.api.filter)?,
+            sql: parse("filters.sql.filter", &filters.sql.filter)?,
+            body: parse("filters.body.filter", &filters.body.filter)?,
+            batch: parse("filters.batch.filter", &filters.batch.filter)?,
+        })
+    }
+}
+
+/// One filter per log source; a disabled source has no filter and hides everything
+pub struct SourceFilters {
+    api: Option<LogFilter>,
+    sql: Option<LogFilter>,
+    body: Option<LogFilter>,
+    batch: Option<LogFilter>,
+    karate: Option<LogFilter>,
+    /// Decides which source an API entry belongs to
+    classifier: Arc<Classifier>,
+}
+
+impl SourceFilters {
+    /// Build filters from `[logging]` defaults and `[filters.*]` overrides
+    ///
+    /// The error names the source whose level or patterns are invalid.
+    pub fn new(
+        logging: &LoggingConfig,
+        filters: &FiltersConfig,
+        exprs: &FilterExprs,
+        classifier: Arc<Classifier>,
+    ) -> Result<Self, String> {
+        let api_source = |name: &str, source: &SourceFilterConfig, expr: &Option<FilterExpr>| {
+            source
+                .enabled
+                .then(|| {
+                    LogFilter::new(
+                        source.level.as_deref().unwrap_or(&logging.level),
+                        source.include_patterns.as_ref().unwrap_or(&logging.include_patterns),
+                        source.exclude_patterns.as_ref().unwrap_or(&logging.exclude_patterns),
+                    )
+                    .map(|filter| filter.with_expression(expr.as_ref().or(exprs.logging.as_ref()).cloned()))
+                    .map_err(|e| format!("filters.{}: {}", name, e))
+                })
+                .transpose()
+        };
+
+        let karate = &filters.karate;
+        Ok(Self {
+            api: api_source("api", &filters.api, &exprs.api)?,
+            sql: api_source("sql", &filters.sql, &exprs.sql)?,
+            body: api_source("body", &filters.body, &exprs.body)?,
+            batch: api_source("batch", &filters.batch, &exprs.batch)?,
+            karate: karate
+                .enabled
+                .then(|| {
+                    LogFilter::new(
+                        karate.level.as_deref().unwrap_or("ALL"),
+                        karate.include_patterns.as_deref().unwrap_or_default(),
+                        karate.exclude_patterns.as_deref().unwrap_or_default(),
+                    )
+                    .map_err(|e| format!("filters.karate: {}", e))
+                })
+                .transpose()?,
+            classifier,
+        })
+    }
+
+    fn get(&self, source: LogSource) -> Option<&LogFilter> {
+        match source {
+            LogSource::Api => self.api.as_ref(),
+            LogSource::Sql => self.sql.as_ref(),
+            LogSource::Body => self.body.as_ref(),
+            LogSource::Batch => self.batch.as_ref(),
+            LogSource::Karate => self.karate.as_ref(),
+        }
+    }
+
+    /// Check an API log entry against the filter for its source
+    pub fn should_include_api(&self, entry: &ApiLogEntry) -> bool {
+        self.get(LogSource::of_api_entry(entry, &self.classifier))
+            .is_some_and(|filter| filter.should_include_api(entry))
+    }
+
+    /// Whether the entry's source is disabled, so it is never shown (not even as context)
+    pub fn is_hidden_api(&self, entry: &ApiLogEntry) -> bool {
+        self.get(LogSource::of_api_entry(entry, &self.classifier)).is_none()
+    }
+
+    /// Check a batch log line; unparsed lines only go through the patterns
+    pub fn should_include_batch(&self, raw: &str, entry: Option<&ApiLogEntry>) -> bool {
+        self.get(LogSource::Batch).is_some_and(|filter| match entry {
+            Some(entry) => filter.should_include_api(entry),
+            None => filter.should_include_line(raw),
+        })
+    }
+
+    /// Check a Karate output line against the Karate level and patterns
+    pub fn should_include_karate(&self, line: &str, log_type: &LogType) -> bool {
+        self.get(LogSource::Karate).is_some_and(|filter| {
+            filter
+                .level
+                .is_none_or(|min| karate_line_level(line, log_type) >= min)
+                && filter.should_include_line(line)
+        })
+    }
+}
+
+impl Default for ApiLogEntry {
+    fn default() -> Self {
+        Self {
+            time: None,
+            level: "INFO".to_string(),
+            msg: String::new(),
+            request_id: None,
+            uri: None,
+            method: None,
+            status: None,
+            latency_human: None,
+            latency: None,
+            sql: None,
+            elapsed: None,
+            elapsed_duration: None,
+            rows_affected: None,
+            err: None,
+            func: None,
+            office_id: None,
+            user_id: None,
+            request_body: None,
+            response_body: None,
+            extra: std::collections::HashMap::new(),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[test]
     fn test_level_filter() {
-        let filter = LogFilter::new("WARN", &[], &[]);
+        let filter = LogFilter::new("WARN", &[], &[]).unwrap();
 
         let info_log = ApiLogEntry {
             level: "INFO".to_string(),
@@ -111,11 +312,13 @@ mod tests {
 
         assert!(!filter.should_include_api(&info_log));
         assert!(filter.should_include_api(&error_log));
+        assert!(LogFilter::new("LOUD", &[], &[]).is_err());
+        assert!(LogFilter::new("ALL", &["(unclosed".to_string()], &[]).is_err());
     }
 
     #[test]
     fn test_exclude_pattern() {
-        let filter = LogFilter::new("ALL", &[], &["health.*check".to_string()]);
+        let filter = LogFilter::new("ALL", &[], &["health.*check".to_string()]).unwrap();
 
         let health_log = ApiLogEntry {
             level: "INFO".to_string(),
@@ -132,29 +335,69 @@ mod tests {
         assert!(!filter.should_include_api(&health_log));
         assert!(filter.should_include_api(&normal_log));
     }
-}
 
-impl Default for ApiLogEntry {
-    fn default() -> Self {
-        Self {
-            time: None,
-            level: "INFO".to_string(),
-            msg: String::new(),
-            request_id: None,
-            uri: None,
-            method: None,
-            status: None,
-            latency_human: None,
-            sql: None,
-            elapsed: None,
-            rows_affected: None,
-            err: None,
-            func: None,
-            office_id: None,
-            user_id: None,
-            request_body: None,
-            response_body: None,
-            extra: std::collections::HashMap::new(),
-        }
+    #[test]
+    fn test_expression_with_patterns() {
+        let filter = LogFilter::new("ALL", &["karte".to_string()], &[])
+            .unwrap()
+            .with_expression(FilterExpr::parse("status >= 400").ok());
+
+        let failed = ApiLogEntry {
+            msg: "REQUEST".to_string(),
+            uri: Some("/api/v1/karte".to_string()),
+            status: Some(500),
+            ..Default::default()
+        };
+        let ok = ApiLogEntry {
+            status: Some(200),
+            ..failed.clone()
+        };
+
+        assert!(filter.should_include_api(&failed));
+        assert!(!filter.should_include_api(&ok));
+    }
+
+    #[test]
+    fn test_source_filters() {
+        let logging = LoggingConfig {
+            exclude_patterns: vec!["health".to_string()],
+            ..Default::default()
+        };
+        let mut filters = FiltersConfig::default();
+        filters.sql.level = Some("ERROR".to_string());
+        filters.body.enabled = false;
+        filters.karate.level = Some("ERROR".to_string());
+
+        let classifier = Arc::new(Classifier::new(&Default::default()));
+        let sources = SourceFilters::new(&logging, &filters, &FilterExprs::default(), classifier).unwrap();
+
+        let sql = ApiLogEntry {
+            level: "DEBUG".to_string(),
+            msg: "SQL".to_string(),
+            sql: Some("SELECT 1".to_string()),
+            ..Default::default()
+        };
+        let sql_error = ApiLogEntry {
+            level: "ERROR".to_string(),
+            err: Some("deadlock".to_string()),
+            ..sql.clone()
+        };
+        let body = ApiLogEntry {
+            msg: "request / response body dump".to_string(),
+            ..Default::default()
+        };
+        let health = ApiLogEntry {
+            msg: "health".to_string(),
+            ..Default::default()
+        };
+
+        assert!(!sources.should_include_api(&sql));
+        assert!(sources.should_include_api(&sql_error));
+        assert!(!sources.should_include_api(&body));
+        assert!(!sources.should_include_api(&health));
+
+        assert!(!sources.should_include_karate("12:00:00 INFO  - step", &LogType::KarateInfo));
+        let failure = "status code was: 500, expected: 200";
+        assert!(sources.should_include_karate(failure, &LogType::KarateFailure));
     }
 }
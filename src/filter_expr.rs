@@ -0,0 +1,604 @@
+//! Field-aware filter expressions over API log entries
+//!
+//! Example: `status >= 400 && uri ~ "/karte" && !(msg == "health")`
+//!
+//! Comparisons are `==`, `!=`, `>`, `>=`, `<`, `<=`, `~` (regex) and `!~`; they combine
+//! with `&&`, `||`, `!` and parentheses. A bare field is true when it is present.
+//! Values are quoted strings, numbers, durations (`50ms`, `1.5s`), `true`, `false`,
+//! `null` or bare words (`level >= WARN`). Dotted fields look into `extra` and bodies:
+//! `extra.office_id`, `request_body.items`.
+
+use crate::log_parser::{duration_unit_ms, parse_go_duration, ApiLogEntry, LogLevel};
+use regex::Regex;
+use serde_json::Value;
+use thiserror::Error;
+
+/// A syntax error with the character position it was found at
+#[derive(Error, Debug, Clone, PartialEq)]
+#[error("{message} at position {position}")]
+pub struct ExprError {
+    pub message: String,
+    pub position: usize,
+}
+
+impl ExprError {
+    fn new(message: impl Into<String>, position: usize) -> Self {
+        Self {
+            message: message.into(),
+            position,
+        }
+    }
+
+    /// The error with the source expression and a caret under the position
+    pub fn render(&self, source: &str) -> String {
+        format!("{}\n  {}\n  {}^", self, source, " ".repeat(self.position))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Str(String),
+    Num(f64),
+    /// Milliseconds
+    Duration(f64),
+    And,
+    Or,
+    Not,
+    LParen,
+    RParen,
+    Cmp(CmpOp),
+    /// `~`, or `!~` when negated
+    Regex(bool),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum CmpOp {
+    Eq,
+    Ne,
+    Gt,
+    Ge,
+    Lt,
+    Le,
+}
+
+#[derive(Debug, Clone)]
+enum Literal {
+    Str(String),
+    Num(f64),
+    /// Milliseconds
+    Duration(f64),
+    Bool(bool),
+    Null,
+}
+
+/// A field of `ApiLogEntry`; dotted paths descend into JSON values
+#[derive(Debug, Clone, PartialEq)]
+enum Field {
+    Level,
+    Msg,
+    Time,
+    RequestId,
+    Uri,
+    Method,
+    Status,
+    Latency,
+    Sql,
+    Elapsed,
+    RowsAffected,
+    Err,
+    Func,
+    OfficeId,
+    UserId,
+    RequestBody(Vec<String>),
+    ResponseBody(Vec<String>),
+    Extra(Vec<String>),
+}
+
+#[derive(Debug, Clone)]
+enum Expr {
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Exists(Field),
+    Compare { field: Field, op: CmpOp, value: Literal },
+    Matches { field: Field, regex: Regex, negate: bool },
+}
+
+/// A field value resolved from an entry
+enum Resolved<'a> {
+    Str(&'a str),
+    Num(f64),
+    Bool(bool),
+    Json(&'a Value),
+}
+
+/// A compiled filter expression
+#[derive(Debug, Clone)]
+pub struct FilterExpr {
+    root: Expr,
+}
+
+impl FilterExpr {
+    /// Compile an expression
+    pub fn parse(source: &str) -> Result<Self, ExprError> {
+        let tokens = tokenize(source)?;
+        let mut parser = Parser {
+            tokens,
+            pos: 0,
+            end: source.chars().count(),
+        };
+        let root = parser.parse_or()?;
+        if let Some((token, position)) = parser.tokens.get(parser.pos) {
+            return Err(ExprError::new(format!("unexpected {}", describe(token)), *position));
+        }
+        Ok(Self { root })
+    }
+
+    /// Evaluate against an API log entry
+    pub fn matches(&self, entry: &ApiLogEntry) -> bool {
+        eval(&self.root, entry)
+    }
+}
+
+fn describe(token: &Token) -> String {
+    match token {
+        Token::Ident(name) => format!("'{}'", name),
+        Token::Str(s) => format!("string \"{}\"", s),
+        Token::Num(n) => format!("number {}", n),
+        Token::Duration(ms) => format!("duration {}ms", ms),
+        Token::And => "'&&'".to_string(),
+        Token::Or => "'||'".to_string(),
+        Token::Not => "'!'".to_string(),
+        Token::LParen => "'('".to_string(),
+        Token::RParen => "')'".to_string(),
+        Token::Cmp(_) | Token::Regex(_) => "comparison operator".to_string(),
+    }
+}
+
+/// Split an expression into tokens with their character positions
+fn tokenize(source: &str) -> Result<Vec<(Token, usize)>, ExprError> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let start = i;
+        let next = chars.get(i + 1).copied();
+
+        let (token, len) = match c {
+            _ if c.is_whitespace() => {
+                i += 1;
+                continue;
+            }
+            '(' => (Token::LParen, 1),
+            ')' => (Token::RParen, 1),
+            '&' if next == Some('&') => (Token::And, 2),
+            '|' if next == Some('|') => (Token::Or, 2),
+            '=' if next == Some('=') => (Token::Cmp(CmpOp::Eq), 2),
+            '!' if next == Some('=') => (Token::Cmp(CmpOp::Ne), 2),
+            '>' if next == Some('=') => (Token::Cmp(CmpOp::Ge), 2),
+            '<' if next == Some('=') => (Token::Cmp(CmpOp::Le), 2),
+            '!' if next == Some('~') => (Token::Regex(true), 2),
+            '~' => (Token::Regex(false), 1),
+            '!' => (Token::Not, 1),
+            '>' => (Token::Cmp(CmpOp::Gt), 1),
+            '<' => (Token::Cmp(CmpOp::Lt), 1),
+            '"' | '\'' => {
+                let (value, end) = read_string(&chars, i)?;
+                tokens.push((Token::Str(value), start));
+                i = end;
+                continue;
+            }
+            _ if c.is_ascii_digit() || (c == '-' && next.is_some_and(|n| n.is_ascii_digit())) => {
+                let (token, end) = read_number(&chars, i)?;
+                tokens.push((token, start));
+                i = end;
+                continue;
+            }
+            _ if c.is_alphabetic() || c == '_' => {
+                let mut end = i;
+                while end < chars.len() && (chars[end].is_alphanumeric() || matches!(chars[end], '_' | '.' | '-')) {
+                    end += 1;
+                }
+                tokens.push((Token::Ident(chars[i..end].iter().collect()), start));
+                i = end;
+                continue;
+            }
+            _ => return Err(ExprError::new(format!("unexpected character '{}'", c), i)),
+        };
+
+        tokens.push((token, start));
+        i += len;
+    }
+
+    Ok(tokens)
+}
+
+fn read_string(chars: &[char], start: usize) -> Result<(String, usize), ExprError> {
+    let quote = chars[start];
+    let mut value = String::new();
+    let mut i = start + 1;
+
+    while i < chars.len() {
+        match chars[i] {
+            '\\' if i + 1 < chars.len() => {
+                value.push(match chars[i + 1] {
+                    'n' => '\n',
+                    't' => '\t',
+                    other => other,
+                });
+                i += 2;
+            }
+            c if c == quote => return Ok((value, i + 1)),
+            c => {
+                value.push(c);
+                i += 1;
+            }
+        }
+    }
+
+    Err(ExprError::new("unterminated string", start))
+}
+
+fn read_number(chars: &[char], start: usize) -> Result<(Token, usize), ExprError> {
+    let mut i = start + 1;
+    while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+        i += 1;
+    }
+    let number: String = chars[start..i].iter().collect();
+    let value: f64 = number
+        .parse()
+        .map_err(|_| ExprError::new(format!("invalid number '{}'", number), start))?;
+
+    let unit_start = i;
+    while i < chars.len() && chars[i].is_alphabetic() {
+        i += 1;
+    }
+    if unit_start == i {
+        return Ok((Token::Num(value), i));
+    }
+
+    let unit: String = chars[unit_start..i].iter().collect();
+    let ms = duration_unit_ms(&unit)
+        .ok_or_else(|| ExprError::new(format!("unknown duration unit '{}'", unit), unit_start))?;
+    Ok((Token::Duration(value * ms), i))
+}
+
+/// Parse a logged Go duration such as "1.235ms" or "1m2s" to milliseconds; bare numbers are milliseconds
+fn duration_ms(text: &str) -> Option<f64> {
+    match parse_go_duration(text) {
+        Some(duration) => Some(duration.as_nanos() as f64 / 1e6),
+        None => text.trim().parse().ok(),
+    }
+}
+
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    pos: usize,
+    /// Position reported for errors at the end of input
+    end: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(t, _)| t)
+    }
+
+    fn position(&self) -> usize {
+        self.tokens.get(self.pos).map(|(_, p)| *p).unwrap_or(self.end)
+    }
+
+    fn next(&mut self) -> Option<(Token, usize)> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn parse_or(&mut self) -> Result<Expr, ExprError> {
+        let mut left = self.parse_and()?;
+        while self.peek() == Some(&Token::Or) {
+            self.pos += 1;
+            left = Expr::Or(Box::new(left), Box::new(self.parse_and()?));
+        }
+        Ok(left)
+    }
+
+    fn parse_and(&mut self) -> Result<Expr, ExprError> {
+        let mut left = self.parse_unary()?;
+        while self.peek() == Some(&Token::And) {
+            self.pos += 1;
+            left = Expr::And(Box::new(left), Box::new(self.parse_unary()?));
+        }
+        Ok(left)
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr, ExprError> {
+        match self.next() {
+            Some((Token::Not, _)) => Ok(Expr::Not(Box::new(self.parse_unary()?))),
+            Some((Token::LParen, _)) => {
+                let inner = self.parse_or()?;
+                match self.next() {
+                    Some((Token::RParen, _)) => Ok(inner),
+                    _ => Err(ExprError::new("expected ')'", self.end)),
+                }
+            }
+            Some((Token::Ident(name), position)) => {
+                let field = parse_field(&name, position)?;
+                self.parse_comparison(field)
+            }
+            Some((token, position)) => Err(ExprError::new(
+                format!("expected a field, '!' or '(' but found {}", describe(&token)),
+                position,
+            )),
+            None => Err(ExprError::new("unexpected end of expression", self.end)),
+        }
+    }
+
+    fn parse_comparison(&mut self, field: Field) -> Result<Expr, ExprError> {
+        if let Some(&Token::Regex(negate)) = self.peek() {
+            self.pos += 1;
+            let position = self.position();
+            let pattern = match self.next() {
+                Some((Token::Str(s), _)) | Some((Token::Ident(s), _)) => s,
+                _ => return Err(ExprError::new("expected a pattern after '~'", position)),
+            };
+            let regex = Regex::new(&pattern)
+                .map_err(|e| ExprError::new(format!("invalid regex: {}", e), position))?;
+            return Ok(Expr::Matches { field, regex, negate });
+        }
+
+        let op = match self.peek() {
+            Some(Token::Cmp(op)) => *op,
+            _ => return Ok(Expr::Exists(field)),
+        };
+        self.pos += 1;
+
+        let position = self.position();
+        let value = match self.next() {
+            Some((Token::Str(s), _)) => Literal::Str(s),
+            Some((Token::Num(n), _)) => Literal::Num(n),
+            Some((Token::Duration(ms), _)) => Literal::Duration(ms),
+            Some((Token::Ident(word), _)) => match word.as_str() {
+                "true" => Literal::Bool(true),
+                "false" => Literal::Bool(false),
+                "null" => Literal::Null,
+                _ => Literal::Str(word),
+            },
+            _ => return Err(ExprError::new("expected a value after the comparison", position)),
+        };
+
+        Ok(Expr::Compare { field, op, value })
+    }
+}
+
+fn parse_field(name: &str, position: usize) -> Result<Field, ExprError> {
+    // Typed fields are parsed out of the line and never kept in `extra`, so
+    // `extra.office_id` means the typed `office_id`
+    if let Some(path) = name.strip_prefix("extra.") {
+        if path.is_empty() || path.split('.').any(str::is_empty) {
+            return Err(ExprError::new(format!("invalid field '{}'", name), position));
+        }
+        return parse_field(path, position);
+    }
+    let mut segments = name.split('.').map(str::to_string);
+    let first = segments.next().unwrap_or_default();
+    let rest: Vec<String> = segments.collect();
+
+    let simple = match first.as_str() {
+        "level" => Some(Field::Level),
+        "msg" => Some(Field::Msg),
+        "time" => Some(Field::Time),
+        "request_id" => Some(Field::RequestId),
+        "uri" => Some(Field::Uri),
+        "method" => Some(Field::Method),
+        "status" => Some(Field::Status),
+        "latency" | "latency_human" => Some(Field::Latency),
+        "sql" => Some(Field::Sql),
+        "elapsed" => Some(Field::Elapsed),
+        "rows" | "rows_affected" => Some(Field::RowsAffected),
+        "err" => Some(Field::Err),
+        "func" => Some(Field::Func),
+        "office_id" => Some(Field::OfficeId),
+        "user_id" => Some(Field::UserId),
+        "request_body" => return Ok(Field::RequestBody(rest)),
+        "response_body" => return Ok(Field::ResponseBody(rest)),
+        _ => None,
+    };
+
+    match simple {
+        Some(_) if !rest.is_empty() => Err(ExprError::new(
+            format!("field '{}' has no sub-fields", first),
+            position,
+        )),
+        Some(field) => Ok(field),
+        None => Ok(Field::Extra(
+            std::iter::once(first).chain(rest).collect(),
+        )),
+    }
+}
+
+fn resolve<'a>(field: &Field, entry: &'a ApiLogEntry) -> Option<Resolved<'a>> {
+    let text = |s: &'a Option<String>| s.as_deref().map(Resolved::Str);
+    match field {
+        Field::Level => Some(Resolved::Str(&entry.level)),
+        Field::Msg => Some(Resolved::Str(&entry.msg)),
+        Field::Time => text(&entry.time),
+        Field::RequestId => text(&entry.request_id),
+        Field::Uri => text(&entry.uri),
+        Field::Method => text(&entry.method),
+        Field::Status => entry.status.map(|s| Resolved::Num(s as f64)),
+        Field::Latency => text(&entry.latency_human),
+        Field::Sql => text(&entry.sql),
+        Field::Elapsed => text(&entry.elapsed),
+        Field::RowsAffected => entry.rows_affected.map(|n| Resolved::Num(n as f64)),
+        Field::Err => text(&entry.err),
+        Field::Func => text(&entry.func),
+        Field::OfficeId => entry.office_id.map(|n| Resolved::Num(n as f64)),
+        Field::UserId => entry.user_id.map(|n| Resolved::Num(n as f64)),
+        Field::RequestBody(path) => json_path(entry.request_body.as_ref()?, path),
+        Field::ResponseBody(path) => json_path(entry.response_body.as_ref()?, path),
+        Field::Extra(path) => json_path(entry.extra.get(&path[0])?, &path[1..]),
+    }
+}
+
+fn json_path<'a>(value: &'a Value, path: &[String]) -> Option<Resolved<'a>> {
+    let mut current = value;
+    for segment in path {
+        current = match current {
+            Value::Object(map) => map.get(segment)?,
+            Value::Array(items) => items.get(segment.parse::<usize>().ok()?)?,
+            // Bodies are sometimes logged as JSON encoded in a string
+            _ => return None,
+        };
+    }
+
+    match current {
+        Value::Null => None,
+        Value::String(s) => Some(Resolved::Str(s)),
+        Value::Number(n) => n.as_f64().map(Resolved::Num),
+        Value::Bool(b) => Some(Resolved::Bool(*b)),
+        other => Some(Resolved::Json(other)),
+    }
+}
+
+fn eval(expr: &Expr, entry: &ApiLogEntry) -> bool {
+    match expr {
+        Expr::And(a, b) => eval(a, entry) && eval(b, entry),
+        Expr::Or(a, b) => eval(a, entry) || eval(b, entry),
+        Expr::Not(inner) => !eval(inner, entry),
+        Expr::Exists(field) => match resolve(field, entry) {
+            None => false,
+            Some(Resolved::Str(s)) => !s.is_empty(),
+            Some(Resolved::Bool(b)) => b,
+            Some(_) => true,
+        },
+        Expr::Matches { field, regex, negate } => {
+            let matched = resolve(field, entry).is_some_and(|value| match value {
+                Resolved::Str(s) => regex.is_match(s),
+                Resolved::Num(n) => regex.is_match(&n.to_string()),
+                Resolved::Bool(b) => regex.is_match(&b.to_string()),
+                Resolved::Json(v) => regex.is_match(&v.to_string()),
+            });
+            matched != *negate
+        }
+        Expr::Compare { field, op, value } => {
+            let Some(resolved) = resolve(field, entry) else {
+                // Missing fields are only "not equal" to anything but null
+                return match value {
+                    Literal::Null => *op == CmpOp::Eq,
+                    _ => *op == CmpOp::Ne,
+                };
+            };
+
+            if *field == Field::Level {
+                if let Literal::Str(level) = value {
+                    return compare(entry.log_level().cmp(&LogLevel::from_str(level)), *op);
+                }
+            }
+
+            // Durations parsed when the entry was built; unparsed text falls through below
+            let parsed = match field {
+                Field::Latency => entry.latency,
+                Field::Elapsed => entry.elapsed_duration,
+                _ => None,
+            };
+            if let (Some(duration), Literal::Duration(ms)) = (parsed, value) {
+                let elapsed_ms = duration.as_nanos() as f64 / 1e6;
+                return elapsed_ms.partial_cmp(ms).is_some_and(|ordering| compare(ordering, *op));
+            }
+
+            let ordering = match (resolved, value) {
+                (_, Literal::Null) => return *op == CmpOp::Ne,
+                (Resolved::Num(n), Literal::Num(v)) => n.partial_cmp(v),
+                (Resolved::Str(s), Literal::Num(v)) => s.trim().parse::<f64>().ok().and_then(|n| n.partial_cmp(v)),
+                (Resolved::Num(n), Literal::Duration(ms)) => n.partial_cmp(ms),
+                (Resolved::Str(s), Literal::Duration(ms)) => duration_ms(s).and_then(|n| n.partial_cmp(ms)),
+                (Resolved::Str(s), Literal::Str(v)) => Some(s.cmp(v.as_str())),
+                (Resolved::Num(n), Literal::Str(v)) => match v.parse::<f64>() {
+                    Ok(v) => n.partial_cmp(&v),
+                    Err(_) => Some(n.to_string().as_str().cmp(v.as_str())),
+                },
+                (Resolved::Bool(b), Literal::Bool(v)) => Some(b.cmp(v)),
+                (Resolved::Bool(b), Literal::Str(v)) => Some(b.to_string().as_str().cmp(v.as_str())),
+                (Resolved::Json(j), Literal::Str(v)) => Some(j.to_string().as_str().cmp(v.as_str())),
+                _ => None,
+            };
+
+            match ordering {
+                Some(ordering) => compare(ordering, *op),
+                // Values of different kinds are never equal
+                None => *op == CmpOp::Ne,
+            }
+        }
+    }
+}
+
+fn compare(ordering: std::cmp::Ordering, op: CmpOp) -> bool {
+    use std::cmp::Ordering::*;
+    match op {
+        CmpOp::Eq => ordering == Equal,
+        CmpOp::Ne => ordering != Equal,
+        CmpOp::Gt => ordering == Greater,
+        CmpOp::Ge => ordering != Less,
+        CmpOp::Lt => ordering == Less,
+        CmpOp::Le => ordering != Greater,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(json: &str) -> ApiLogEntry {
+        ApiLogEntry::parse(json).unwrap()
+    }
+
+    #[test]
+    fn test_combined_expression() {
+        let expr = FilterExpr::parse(r#"status >= 400 && uri ~ "/karte" && !(msg == "health")"#).unwrap();
+
+        assert!(expr.matches(&entry(r#"{"level":"ERROR","msg":"REQUEST","uri":"/api/v1/karte/1","status":500}"#)));
+        assert!(!expr.matches(&entry(r#"{"level":"INFO","msg":"REQUEST","uri":"/api/v1/karte/1","status":200}"#)));
+        assert!(!expr.matches(&entry(r#"{"level":"ERROR","msg":"health","uri":"/api/v1/karte","status":503}"#)));
+    }
+
+    #[test]
+    fn test_durations_levels_and_extra() {
+        let slow = FilterExpr::parse("elapsed > 50ms || latency >= 1s").unwrap();
+        assert!(slow.matches(&entry(r#"{"level":"DEBUG","msg":"SQL","elapsed":"51.2ms"}"#)));
+        assert!(slow.matches(&entry(r#"{"level":"INFO","msg":"REQUEST","latency_human":"1.5s"}"#)));
+        assert!(!slow.matches(&entry(r#"{"level":"DEBUG","msg":"SQL","elapsed":"900µs"}"#)));
+        assert!(slow.matches(&entry(r#"{"level":"DEBUG","msg":"SQL","elapsed":"1m2s"}"#)));
+        let fast = FilterExpr::parse("elapsed < 900μs").unwrap();
+        assert!(fast.matches(&entry(r#"{"level":"DEBUG","msg":"SQL","elapsed":"850µs"}"#)));
+
+        let office = FilterExpr::parse("office_id == 12 && extra.tenant.region == 'jp' && level >= WARN").unwrap();
+        assert!(office.matches(&entry(r#"{"level":"WARN","msg":"x","office_id":12,"tenant":{"region":"jp"}}"#)));
+        assert!(!office.matches(&entry(r#"{"level":"INFO","msg":"x","office_id":12,"tenant":{"region":"jp"}}"#)));
+        assert!(!office.matches(&entry(r#"{"level":"ERROR","msg":"x","office_id":13}"#)));
+
+        // `extra.` before a typed field names the typed field
+        let extra_office = FilterExpr::parse("elapsed > 50ms || extra.office_id == 12").unwrap();
+        assert!(extra_office.matches(&entry(r#"{"level":"INFO","msg":"x","office_id":12}"#)));
+        assert!(!extra_office.matches(&entry(r#"{"level":"INFO","msg":"x","office_id":13}"#)));
+        assert!(FilterExpr::parse("extra.").is_err());
+
+        let has_err = FilterExpr::parse("err && err != null").unwrap();
+        assert!(has_err.matches(&entry(r#"{"level":"ERROR","msg":"x","err":"boom"}"#)));
+        assert!(!has_err.matches(&entry(r#"{"level":"ERROR","msg":"x"}"#)));
+    }
+
+    #[test]
+    fn test_syntax_errors_have_positions() {
+        let err = FilterExpr::parse("status >= && uri").unwrap_err();
+        assert_eq!(err.position, 10);
+
+        let err = FilterExpr::parse("(status > 1").unwrap_err();
+        assert_eq!(err.message, "expected ')'");
+
+        assert!(FilterExpr::parse("elapsed > 5parsecs").is_err());
+        assert!(FilterExpr::parse("uri ~ \"[\"").is_err());
+    }
+}
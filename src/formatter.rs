@@ -1,134 +1,371 @@
 //! Colored output formatting for logs
 
+use crate::body::{BodyLimits, BodyRenderer};
+use crate::classify::{Class, Classifier};
 use crate::config::DisplayConfig;
-use crate::log_parser::{ApiLogEntry, LogLevel, LogType};
-use colored::Colorize;
+use crate::diff::{json_diff, parse_value, render_diff};
+use crate::karate_http::HttpExchange;
+use crate::log_parser::{ApiLogEntry, KarateFailure, LogLevel, LogType};
+use crate::sql_format::{SqlRenderOptions, SqlRenderer};
+use crate::theme::{Style, Theme};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// How SQL logs are rendered
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SqlStyle {
+    /// Statement on its own indented lines
+    Pretty,
+    /// Statement on the header line, truncated
+    Compact,
+    /// The raw JSON line with the statement highlighted
+    Raw,
+}
+
+impl SqlStyle {
+    pub fn from_str(s: &str) -> Self {
+        match s.to_lowercase().as_str() {
+            "compact" => SqlStyle::Compact,
+            "raw" => SqlStyle::Raw,
+            _ => SqlStyle::Pretty,
+        }
+    }
+}
 
 /// Formatter for log output
 pub struct LogFormatter {
-    config: DisplayConfig,
+    theme: Theme,
     show_timestamps: bool,
+    /// Body rendering limits, `None` when pretty bodies are disabled
+    body_limits: Option<BodyLimits>,
+    sql_style: SqlStyle,
+    sql_options: SqlRenderOptions,
+    slow_query_ms: f64,
+    /// Types, prefixes and colours of API entries
+    classifier: Arc<Classifier>,
 }
 
 impl LogFormatter {
-    pub fn new(config: DisplayConfig) -> Self {
+    pub fn new(config: DisplayConfig, classifier: Arc<Classifier>) -> Self {
         Self {
+            theme: Theme::from_config(&config),
             show_timestamps: config.show_timestamps,
-            config,
+            body_limits: config.pretty_bodies.then_some(BodyLimits {
+                max_items: config.body_max_items,
+                max_string_len: config.body_max_string_len,
+                indent: 2,
+            }),
+            sql_style: SqlStyle::from_str(&config.sql_style),
+            sql_options: SqlRenderOptions {
+                indent: 2,
+                compact_max_len: config.sql_compact_max_len,
+            },
+            slow_query_ms: config.slow_query_ms,
+            classifier,
         }
     }
 
+    /// The theme used for prefixes, colours and borders
+    pub fn theme(&self) -> &Theme {
+        &self.theme
+    }
+
     /// Format an API log entry with colors and prefixes
     pub fn format_api_log(&self, entry: &ApiLogEntry, raw_json: &str) -> String {
-        let log_type = entry.log_type();
-        let prefix = self.get_api_prefix(&log_type, entry);
+        let class = self.classifier.api(entry);
+        let log_type = class.log_type;
+        let prefix = match class.prefix {
+            Some(prefix) => prefix.to_string(),
+            None => self.get_api_prefix(&log_type, entry),
+        };
 
         let formatted = match log_type {
-            LogType::ApiError => self.format_error_log(raw_json),
+            LogType::ApiError if entry.sql.is_some() => self.format_sql_log(raw_json, entry),
+            LogType::ApiError if entry.extra.contains_key("stack") => self.format_stack_trace(entry),
+            LogType::ApiError => self.format_error_log(raw_json, class.style),
             LogType::ApiSql => self.format_sql_log(raw_json, entry),
-            LogType::ApiBodyDump => self.format_body_dump(raw_json),
-            LogType::ApiRequest => self.format_request_log(raw_json, entry),
-            _ => self.format_general_log(raw_json),
+            LogType::ApiBodyDump => self.format_body_dump(raw_json, entry),
+            LogType::ApiRequest => self.format_request_log(raw_json, entry, class.style),
+            _ => self.format_general_log(raw_json, class.style),
         };
 
-        format!("{}{} {}", prefix, self.config.api_prefix, formatted)
+        Theme::prefixed(&format!("{}{}", prefix, self.theme.prefixes.api), &formatted)
     }
 
-    /// Format a Karate log line
-    pub fn format_karate_log(&self, line: &str, log_type: &LogType) -> String {
-        let prefix = self.get_karate_prefix(log_type, line);
-        let formatted = match log_type {
-            LogType::KarateFailure => line.red().to_string(),
-            LogType::KarateSummary => {
-                if line.contains("failed:") && !line.contains("failed: 0") && !line.contains("failed:  0") {
-                    line.red().bold().to_string()
+    /// Line reporting that the entry shown above occurred `count` more times in a row
+    pub fn format_repeated(&self, entry: &ApiLogEntry, count: usize) -> String {
+        let palette = &self.theme.palette;
+        let mut what = entry.msg.clone();
+        if let Some(uri) = &entry.uri {
+            what.push(' ');
+            what.push_str(uri);
+        }
+        format!(
+            "{}{} {}",
+            palette.warn.paint(&self.theme.prefixes.repeat),
+            palette.warn.paint(&(count + 1).to_string()),
+            palette.muted.paint(&what)
+        )
+    }
+
+    /// Line reporting entries dropped by the per-message rate limit
+    pub fn format_rate_limited(&self, msg: &str, count: usize, window: Duration) -> String {
+        let palette = &self.theme.palette;
+        Theme::prefixed(
+            &palette.warn.paint(&self.theme.prefixes.warn).to_string(),
+            &palette
+                .muted
+                .paint(&format!(
+                    "rate limit: suppressed {} \"{}\" entries in {}s",
+                    count,
+                    msg,
+                    window.as_secs()
+                ))
+                .to_string(),
+        )
+    }
+
+    /// Format a Karate log line with the rule that classified it
+    pub fn format_karate_log(&self, line: &str, class: &Class) -> String {
+        let palette = &self.theme.palette;
+        let log_type = &class.log_type;
+        let prefix = match class.prefix {
+            Some(prefix) => prefix.to_string(),
+            None => self.get_karate_prefix(log_type, line),
+        };
+        let style = match (class.style, log_type) {
+            (Some(style), _) => style,
+            (None, LogType::KarateFailure) => palette.error,
+            (None, LogType::KarateSummary) => {
+                if summary_has_failures(line) {
+                    palette.error_strong
                 } else if line.contains("passed:") {
-                    line.green().to_string()
+                    palette.success
                 } else {
-                    line.bright_white().to_string()
+                    palette.text
                 }
             }
-            _ => line.bright_white().to_string(),
+            _ => palette.text,
         };
+        let formatted = style.paint(line).to_string();
 
-        format!("{}{} {}", prefix, self.config.karate_prefix, formatted)
+        Theme::prefixed(&format!("{}{}", prefix, self.theme.prefixes.karate), &formatted)
     }
 
     fn get_api_prefix(&self, log_type: &LogType, entry: &ApiLogEntry) -> String {
+        let prefixes = &self.theme.prefixes;
         match log_type {
-            LogType::ApiError => format!("{}", self.config.error_prefix),
-            LogType::ApiSql => {
-                if entry.err.is_some() || entry.level == "ERROR" {
-                    format!("{}", self.config.sql_prefix)
-                } else {
-                    format!("{}", self.config.sql_prefix)
-                }
-            }
-            LogType::ApiBodyDump => "📄".to_string(),
+            LogType::ApiError => prefixes.error.clone(),
+            LogType::ApiSql => prefixes.sql.clone(),
+            LogType::ApiBodyDump => prefixes.body.clone(),
             _ => {
                 if entry.log_level() == LogLevel::Error {
-                    format!("{}", self.config.error_prefix)
+                    prefixes.error.clone()
                 } else {
-                    format!("{}", self.config.success_prefix)
+                    prefixes.success.clone()
                 }
             }
         }
     }
 
     fn get_karate_prefix(&self, log_type: &LogType, line: &str) -> String {
+        let prefixes = &self.theme.prefixes;
         match log_type {
-            LogType::KarateFailure => format!("{}", self.config.error_prefix),
+            LogType::KarateFailure => prefixes.error.clone(),
             LogType::KarateSummary => {
-                if line.contains("failed:") && !line.contains("failed: 0") && !line.contains("failed:  0") {
-                    format!("{}", self.config.error_prefix)
+                if summary_has_failures(line) {
+                    prefixes.error.clone()
                 } else {
-                    format!("{}", self.config.success_prefix)
+                    prefixes.success.clone()
                 }
             }
-            _ => "🔶".to_string(),
+            _ => String::new(),
         }
     }
 
-    fn format_error_log(&self, json: &str) -> String {
-        json.red().to_string()
+    /// Error entries in red, or in the colour of the rule that classified them
+    fn format_error_log(&self, json: &str, style: Option<Style>) -> String {
+        style.unwrap_or(self.theme.palette.error).paint(json).to_string()
+    }
+
+    /// A folded panic: the headline, the frame it happened in, then the trace
+    fn format_stack_trace(&self, entry: &ApiLogEntry) -> String {
+        const MAX_LINES: usize = 20;
+        let palette = &self.theme.palette;
+
+        let mut out = palette.error_strong.paint(&entry.msg).to_string();
+        if let Some(request_id) = &entry.request_id {
+            out.push_str(&format!(" {}", palette.accent.paint(request_id)));
+        }
+        if let Some(func) = &entry.func {
+            let source = entry.extra.get("source").and_then(|v| v.as_str()).unwrap_or_default();
+            out.push_str(&format!(
+                "\n    at {} {}",
+                palette.error_strong.paint(func),
+                palette.muted.paint(source)
+            ));
+        }
+
+        // The headline is already shown
+        let lines: Vec<&str> = entry
+            .extra
+            .get("stack")
+            .and_then(|v| v.as_array())
+            .map(|lines| lines.iter().skip(1).filter_map(|l| l.as_str()).collect())
+            .unwrap_or_default();
+        let mut origin_location = false;
+        for line in lines.iter().take(MAX_LINES) {
+            let is_origin = entry
+                .func
+                .as_ref()
+                .is_some_and(|func| line.strip_prefix(func.as_str()).is_some_and(|rest| rest.starts_with('(')));
+            let style = if is_origin || origin_location {
+                palette.error
+            } else {
+                palette.muted
+            };
+            origin_location = is_origin;
+            out.push_str(&format!("\n      {}", style.paint(line.trim_start())));
+        }
+        if lines.len() > MAX_LINES {
+            out.push_str(&format!(
+                "\n      {}",
+                palette.muted.paint(&format!(
+                    "{} {} more lines",
+                    self.theme.borders.ellipsis,
+                    lines.len() - MAX_LINES
+                ))
+            ));
+        }
+        out
     }
 
     fn format_sql_log(&self, json: &str, entry: &ApiLogEntry) -> String {
-        // Highlight SQL query in yellow, rows_affected in green
-        let mut result = json.bright_blue().to_string();
+        let sql = match &entry.sql {
+            Some(sql) if self.sql_style != SqlStyle::Raw => sql,
+            _ => return self.format_sql_json(json, entry),
+        };
+
+        let palette = &self.theme.palette;
+        let failed = entry.err.is_some() || entry.log_level() == LogLevel::Error;
+        let renderer = SqlRenderer::new(&self.theme, &self.sql_options);
+
+        let mut header = if failed {
+            palette.error_strong.paint(&entry.msg).to_string()
+        } else {
+            palette.dim.paint(&entry.msg).to_string()
+        };
+        for badge in self.sql_badges(entry) {
+            header.push(' ');
+            header.push_str(&badge);
+        }
+        if let Some(request_id) = &entry.request_id {
+            header.push_str(&format!(" {}", palette.accent.paint(request_id)));
+        }
+        if let Some(err) = &entry.err {
+            header.push_str(&format!(" {}", palette.error.paint(err)));
+        }
+
+        match self.sql_style {
+            SqlStyle::Compact => format!("{} {}", header, renderer.render_compact(sql)),
+            _ => format!("{}\n{}", header, renderer.render_pretty(sql, "      ")),
+        }
+    }
+
+    /// Elapsed time and rows affected as coloured badges; slow queries stand out
+    fn sql_badges(&self, entry: &ApiLogEntry) -> Vec<String> {
+        let palette = &self.theme.palette;
+        let mut badges = Vec::new();
+
+        if let Some(elapsed) = &entry.elapsed {
+            let style = match entry.elapsed_ms() {
+                Some(ms) if ms >= self.slow_query_ms => palette.error_strong,
+                Some(ms) if ms >= self.slow_query_ms / 2.0 => palette.warn,
+                Some(_) => palette.success,
+                // Not a duration; shown as logged
+                None => palette.muted,
+            };
+            badges.push(style.paint(&format!("[{}]", elapsed)).to_string());
+        }
+
+        if let Some(rows) = entry.rows_affected {
+            let label = if rows == 1 { "row" } else { "rows" };
+            badges.push(
+                palette
+                    .success_strong
+                    .paint(&format!("[{} {}]", rows, label))
+                    .to_string(),
+            );
+        }
+
+        badges
+    }
+
+    fn format_sql_json(&self, json: &str, entry: &ApiLogEntry) -> String {
+        let palette = &self.theme.palette;
+
+        if entry.err.is_some() || entry.log_level() == LogLevel::Error {
+            return palette.error.paint(json).to_string();
+        }
+
+        // Highlight SQL query, rows_affected in the success colour
+        let mut result = palette.sql.paint(json).to_string();
 
         if let Some(sql) = &entry.sql {
-            let highlighted_sql = sql.bright_yellow().to_string();
+            let highlighted_sql = palette.highlight.paint(sql).to_string();
             result = result.replace(sql, &highlighted_sql);
         }
 
         if let Some(rows) = entry.rows_affected {
             let rows_str = format!("\"rows_affected\":{}", rows);
-            let highlighted = rows_str.bright_green().bold().to_string();
+            let highlighted = palette.success_strong.paint(&rows_str).to_string();
             result = result.replace(&rows_str, &highlighted);
         }
 
-        if entry.err.is_some() || entry.level == "ERROR" {
-            result = json.red().to_string();
-        }
-
         result
     }
 
-    fn format_body_dump(&self, json: &str) -> String {
-        json.green().to_string()
+    fn format_body_dump(&self, json: &str, entry: &ApiLogEntry) -> String {
+        let palette = &self.theme.palette;
+
+        let limits = match &self.body_limits {
+            Some(limits) if entry.request_body.is_some() || entry.response_body.is_some() => limits,
+            _ => return palette.body.paint(json).to_string(),
+        };
+
+        let renderer = BodyRenderer::new(&self.theme, limits);
+        let mut out = palette.dim.paint(&entry.msg).to_string();
+        if let Some(request_id) = &entry.request_id {
+            out.push_str(&format!(" {}", palette.accent.paint(request_id)));
+        }
+        if let Some(uri) = &entry.uri {
+            out.push_str(&format!(" {}", palette.text.paint(uri)));
+        }
+
+        for (label, body) in [
+            ("request_body", &entry.request_body),
+            ("response_body", &entry.response_body),
+        ] {
+            if let Some(body) = body {
+                out.push_str(&format!("\n    {}\n", palette.section.paint(&format!("{}:", label))));
+                out.push_str(&renderer.render(body, "      "));
+            }
+        }
+
+        out
     }
 
-    fn format_request_log(&self, json: &str, entry: &ApiLogEntry) -> String {
-        let mut result = json.bright_white().dimmed().to_string();
+    fn format_request_log(&self, json: &str, entry: &ApiLogEntry, style: Option<Style>) -> String {
+        let palette = &self.theme.palette;
+        let mut result = style.unwrap_or(palette.dim).paint(json).to_string();
 
         // Highlight status code based on value
         if let Some(status) = entry.status {
             let status_str = format!("\"status\":{}", status);
             let highlighted = if status >= 400 {
-                status_str.red().bold().to_string()
+                palette.error_strong.paint(&status_str).to_string()
             } else {
-                status_str.green().to_string()
+                palette.success.paint(&status_str).to_string()
             };
             result = result.replace(&status_str, &highlighted);
         }
@@ -136,51 +373,109 @@ impl LogFormatter {
         result
     }
 
-    fn format_general_log(&self, json: &str) -> String {
-        // Highlight msg field in cyan
-        json.bright_white().dimmed().to_string()
+    fn format_general_log(&self, json: &str, style: Option<Style>) -> String {
+        style.unwrap_or(self.theme.palette.dim).paint(json).to_string()
     }
 
     /// Format a separator line
     pub fn format_separator(&self) -> String {
-        "─".repeat(60).bright_black().to_string()
+        self.theme.palette.muted.paint(&self.theme.rule(60)).to_string()
     }
 
     /// Format a failure header
     pub fn format_failure_header(&self, feature: &str) -> String {
+        let palette = &self.theme.palette;
+        let borders = &self.theme.borders;
         format!(
             "\n{} {} {}\n{}",
-            "╔".red(),
-            format!("FAILED: {}", feature).red().bold(),
-            "╗".red(),
-            "╚".red()
+            palette.error.paint(&borders.failure_top_left),
+            palette.error_strong.paint(&format!("FAILED: {}", feature)),
+            palette.error.paint(&borders.failure_top_right),
+            palette.error.paint(&borders.failure_bottom_left)
         )
     }
 
+    /// Format the expected-vs-actual diff of a match failure, if it has one
+    pub fn format_match_diff(&self, failure: &KarateFailure) -> Option<String> {
+        let expected = parse_value(failure.expected.as_deref()?);
+        let actual = parse_value(failure.actual.as_deref()?);
+        let diff = json_diff(&expected, &actual);
+        if diff.is_empty() {
+            return None;
+        }
+
+        let location = if failure.line_number > 0 {
+            format!("{}:{}", failure.feature_file, failure.line_number)
+        } else {
+            failure.feature_file.clone()
+        };
+
+        Some(format!(
+            "{}\n{}\n",
+            self.format_failure_header(&location),
+            render_diff(&diff, &self.theme, "  ", 50)
+        ))
+    }
+
+    /// Format an HTTP call Karate made, with its bodies, to show next to the API's logs
+    pub fn format_http_exchange(&self, exchange: &HttpExchange) -> String {
+        let palette = &self.theme.palette;
+        let status = match exchange.status {
+            Some(status) if status >= 400 => palette.error.paint(&exchange.summary()),
+            Some(_) => palette.text.paint(&exchange.summary()),
+            None => palette.warn.paint(&exchange.summary()),
+        };
+        let mut out = Theme::prefixed(&self.theme.prefixes.karate, &status.to_string());
+
+        for (label, body) in [
+            ("request", &exchange.request_body),
+            ("response", &exchange.response_body),
+        ] {
+            let Some(body) = body else {
+                continue;
+            };
+            out.push_str(&format!("\n    {}\n", palette.section.paint(&format!("{}:", label))));
+            match (&self.body_limits, serde_json::from_str::<serde_json::Value>(body)) {
+                (Some(limits), Ok(value)) => {
+                    out.push_str(&BodyRenderer::new(&self.theme, limits).render(&value, "      "))
+                }
+                _ => {
+                    let indented: Vec<String> = body.lines().map(|l| format!("      {}", l)).collect();
+                    out.push_str(&palette.body.paint(&indented.join("\n")).to_string());
+                }
+            }
+        }
+        out
+    }
+
     /// Format correlated logs header
     pub fn format_correlated_header(&self, request_id: &str) -> String {
-        format!(
-            "\n{} {}: {} {}\n",
-            "┌─".bright_yellow(),
-            "Related API Logs".bright_yellow().bold(),
-            request_id.bright_cyan(),
-            "─┐".bright_yellow()
-        )
+        self.format_custom_header("Related API Logs", request_id)
     }
 
     /// Format correlated logs footer
     pub fn format_correlated_footer(&self) -> String {
-        format!("{}\n", "└───────────────────────────────────────┘".bright_yellow())
+        format!(
+            "{}\n",
+            self.theme.palette.border.paint(&self.theme.borders.box_bottom)
+        )
     }
 
     /// Format a custom header with a specific title
     pub fn format_custom_header(&self, title: &str, id: &str) -> String {
+        let palette = &self.theme.palette;
+        let borders = &self.theme.borders;
         format!(
             "\n{} {}: {} {}\n",
-            "┌─".bright_yellow(),
-            title.bright_yellow().bold(),
-            id.bright_cyan(),
-            "─┐".bright_yellow()
+            palette.border.paint(&borders.box_top_left),
+            palette.border.bold().paint(title),
+            palette.accent.paint(id),
+            palette.border.paint(&borders.box_top_right)
         )
     }
 }
+
+/// Whether a Karate summary line reports a non-zero failure count
+pub fn summary_has_failures(line: &str) -> bool {
+    line.contains("failed:") && !line.contains("failed: 0") && !line.contains("failed:  0")
+}
@@ -0,0 +1,720 @@
+//! Gherkin `.feature` file parsing, tag expressions and scenario selection
+
+use crate::theme::Theme;
+use regex::Regex;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Step {
+    /// `Given`, `When`, `Then`, `And`, `But` or `*`
+    pub keyword: String,
+    pub text: String,
+    pub line: u32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExampleRow {
+    pub line: u32,
+    pub values: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Examples {
+    pub name: String,
+    pub line: u32,
+    pub tags: Vec<String>,
+    pub header: Vec<String>,
+    pub rows: Vec<ExampleRow>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Scenario {
+    pub name: String,
+    pub line: u32,
+    pub tags: Vec<String>,
+    pub outline: bool,
+    pub steps: Vec<Step>,
+    pub examples: Vec<Examples>,
+}
+
+/// Karate skips scenarios with this tag unless another feature calls them
+const IGNORE_TAG: &str = "@ignore";
+
+fn ignored(tags: &[String]) -> bool {
+    tags.iter().any(|t| t == IGNORE_TAG)
+}
+
+impl Scenario {
+    /// Runs Karate makes: one, or one per examples row not tagged `@ignore`
+    pub fn run_count(&self) -> usize {
+        if self.outline {
+            self.examples
+                .iter()
+                .filter(|e| !ignored(&e.tags))
+                .map(|e| e.rows.len())
+                .sum()
+        } else {
+            1
+        }
+    }
+
+    /// Last line belonging to this scenario (its last step or example row)
+    pub fn end_line(&self) -> u32 {
+        let steps = self.steps.iter().map(|s| s.line);
+        let rows = self.examples.iter().flat_map(|e| e.rows.iter().map(|r| r.line));
+        steps.chain(rows).max().unwrap_or(self.line).max(self.line)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Background {
+    pub line: u32,
+    pub steps: Vec<Step>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Feature {
+    pub path: PathBuf,
+    pub name: String,
+    pub line: u32,
+    pub tags: Vec<String>,
+    pub background: Option<Background>,
+    pub scenarios: Vec<Scenario>,
+    /// Lines asked for as `file.feature:LINE`; empty for the whole file
+    pub lines: Vec<u32>,
+}
+
+impl Feature {
+    /// Runs Karate makes on its own, leaving out `@ignore` scenarios
+    pub fn run_count(&self) -> usize {
+        self.runnable().map(Scenario::run_count).sum()
+    }
+
+    /// Scenarios Karate runs on its own
+    pub fn runnable(&self) -> impl Iterator<Item = &Scenario> {
+        self.scenarios
+            .iter()
+            .filter(|s| !ignored(&self.tags) && !ignored(&s.tags) && s.run_count() > 0)
+    }
+
+    /// Runs Karate makes for `file.feature:LINE`: every row at an outline's line, otherwise one
+    pub fn runs_at(&self, line: u32) -> usize {
+        match self.scenarios.iter().find(|s| s.line == line) {
+            Some(scenario) if scenario.outline => scenario.run_count(),
+            _ => 1,
+        }
+    }
+
+    /// The scenario a line belongs to, e.g. the step line Karate reports on failure
+    pub fn scenario_at(&self, line: u32) -> Option<&Scenario> {
+        self.scenarios
+            .iter()
+            .find(|s| (s.line..=s.end_line()).contains(&line))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    pub line: u32,
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+const STEP_KEYWORDS: &[&str] = &["Given ", "When ", "Then ", "And ", "But ", "* "];
+
+/// What table rows belong to
+#[derive(PartialEq)]
+enum Block {
+    None,
+    Background,
+    Scenario,
+    Examples,
+}
+
+/// Parse a feature file
+pub fn parse(path: &Path, content: &str) -> Result<Feature, ParseError> {
+    let mut feature: Option<Feature> = None;
+    let mut pending_tags: Vec<String> = Vec::new();
+    let mut block = Block::None;
+    let mut docstring: Option<(&str, u32)> = None;
+
+    let error = |line: u32, message: &str| ParseError {
+        line,
+        message: message.to_string(),
+    };
+
+    for (index, raw) in content.lines().enumerate() {
+        let line_no = index as u32 + 1;
+        let line = raw.trim();
+
+        // Doc strings (JSON, XML, JS) are opaque
+        if let Some((fence, _)) = docstring {
+            if line.starts_with(fence) {
+                docstring = None;
+            }
+            continue;
+        }
+        if let Some(fence) = ["\"\"\"", "```"].into_iter().find(|f| line.starts_with(f)) {
+            docstring = Some((fence, line_no));
+            continue;
+        }
+
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        if line.starts_with('@') {
+            let tags = line.split('#').next().unwrap_or_default();
+            pending_tags.extend(tags.split_whitespace().map(str::to_string));
+            continue;
+        }
+
+        if let Some(name) = line.strip_prefix("Feature:") {
+            if feature.is_some() {
+                return Err(error(line_no, "more than one Feature"));
+            }
+            feature = Some(Feature {
+                path: path.to_path_buf(),
+                name: name.trim().to_string(),
+                line: line_no,
+                tags: std::mem::take(&mut pending_tags),
+                background: None,
+                scenarios: Vec::new(),
+                lines: Vec::new(),
+            });
+            block = Block::None;
+            continue;
+        }
+
+        let Some(current) = feature.as_mut() else {
+            return Err(error(line_no, "expected Feature: before any other keyword"));
+        };
+
+        if line.starts_with("Rule:") {
+            // Rules only group scenarios
+            pending_tags.clear();
+            block = Block::None;
+        } else if line.starts_with("Background:") {
+            current.background = Some(Background {
+                line: line_no,
+                steps: Vec::new(),
+            });
+            block = Block::Background;
+        } else if let Some((outline, name)) = scenario_header(line) {
+            current.scenarios.push(Scenario {
+                name: name.trim().to_string(),
+                line: line_no,
+                tags: std::mem::take(&mut pending_tags),
+                outline,
+                steps: Vec::new(),
+                examples: Vec::new(),
+            });
+            block = Block::Scenario;
+        } else if let Some(name) = line.strip_prefix("Examples:").or_else(|| line.strip_prefix("Scenarios:")) {
+            let Some(scenario) = current.scenarios.last_mut().filter(|s| s.outline) else {
+                return Err(error(line_no, "Examples: outside a Scenario Outline"));
+            };
+            scenario.examples.push(Examples {
+                name: name.trim().to_string(),
+                line: line_no,
+                tags: std::mem::take(&mut pending_tags),
+                header: Vec::new(),
+                rows: Vec::new(),
+            });
+            block = Block::Examples;
+        } else if line.starts_with('|') {
+            if block == Block::Examples {
+                let cells = table_cells(line);
+                let examples = current
+                    .scenarios
+                    .last_mut()
+                    .and_then(|s| s.examples.last_mut())
+                    .expect("examples block is open");
+                if examples.header.is_empty() {
+                    examples.header = cells;
+                } else {
+                    examples.rows.push(ExampleRow {
+                        line: line_no,
+                        values: cells,
+                    });
+                }
+            }
+            // Other tables are step arguments
+        } else if let Some(keyword) = STEP_KEYWORDS.iter().find(|k| line.starts_with(*k)) {
+            let step = Step {
+                keyword: keyword.trim().to_string(),
+                text: line[keyword.len()..].trim().to_string(),
+                line: line_no,
+            };
+            match block {
+                Block::Background => current
+                    .background
+                    .as_mut()
+                    .expect("background is open")
+                    .steps
+                    .push(step),
+                Block::Scenario => current
+                    .scenarios
+                    .last_mut()
+                    .expect("scenario is open")
+                    .steps
+                    .push(step),
+                Block::Examples => return Err(error(line_no, "step after Examples:")),
+                Block::None => return Err(error(line_no, "step outside a Scenario or Background")),
+            }
+        }
+        // Anything else is description text
+    }
+
+    if let Some((_, line)) = docstring {
+        return Err(error(line, "unterminated doc string"));
+    }
+    feature.ok_or_else(|| error(1, "no Feature: found"))
+}
+
+/// `(is_outline, name)` for scenario headers
+fn scenario_header(line: &str) -> Option<(bool, &str)> {
+    for (keyword, outline) in [
+        ("Scenario Outline:", true),
+        ("Scenario Template:", true),
+        ("Scenario:", false),
+        ("Example:", false),
+    ] {
+        if let Some(name) = line.strip_prefix(keyword) {
+            return Some((outline, name));
+        }
+    }
+    None
+}
+
+fn table_cells(line: &str) -> Vec<String> {
+    let inner = line.trim().trim_start_matches('|').trim_end_matches('|');
+    inner.split('|').map(|cell| cell.trim().to_string()).collect()
+}
+
+/// Parse every `.feature` file under the test paths, resolved against `base_dir`
+///
+/// Returns the features and the paths that could not be read (`classpath:` entries,
+/// missing files, parse errors).
+pub fn load_features(test_paths: &[String], base_dir: &Path) -> (Vec<Feature>, Vec<String>) {
+    let mut features = Vec::new();
+    let mut problems = Vec::new();
+    for test_path in test_paths {
+        if test_path.starts_with("classpath:") {
+            problems.push(format!("{}: classpath entries cannot be read", test_path));
+            continue;
+        }
+        let (file, lines) = split_test_path(test_path);
+        let path = base_dir.join(&file);
+        let mut files = Vec::new();
+        if path.is_dir() {
+            collect_feature_files(&path, &mut files);
+        } else if path.is_file() {
+            files.push(path);
+        } else {
+            problems.push(format!("{}: not found", test_path));
+        }
+        for file in files {
+            match std::fs::read_to_string(&file) {
+                Ok(content) => match parse(&file, &content) {
+                    Ok(feature) => features.push(Feature {
+                        lines: lines.clone(),
+                        ..feature
+                    }),
+                    Err(e) => problems.push(format!("{}: {}", file.display(), e)),
+                },
+                Err(e) => problems.push(format!("{}: {}", file.display(), e)),
+            }
+        }
+    }
+    (features, problems)
+}
+
+/// Split `x.feature:12:30` into the file and the lines to run (empty for the whole file)
+pub fn split_test_path(test_path: &str) -> (String, Vec<u32>) {
+    if let Some(end) = test_path.find(".feature:").map(|i| i + ".feature".len()) {
+        let lines: Option<Vec<u32>> = test_path[end + 1..].split(':').map(|l| l.parse().ok()).collect();
+        if let Some(lines) = lines {
+            return (test_path[..end].to_string(), lines);
+        }
+    }
+    (test_path.to_string(), Vec::new())
+}
+
+pub fn collect_feature_files(dir: &Path, out: &mut Vec<PathBuf>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    let mut paths: Vec<PathBuf> = entries.filter_map(|e| e.ok().map(|e| e.path())).collect();
+    paths.sort();
+
+    for path in paths {
+        if path.is_dir() {
+            collect_feature_files(&path, out);
+        } else if path.extension().is_some_and(|ext| ext == "feature") {
+            out.push(path);
+        }
+    }
+}
+
+/// Boolean expression over tags: `@smoke and not (@slow or @wip)`
+///
+/// Karate's shorthand also works: `~@ignore` is `not @ignore`, `@a,@b` is `@a or @b`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TagExpr {
+    Tag(String),
+    Not(Box<TagExpr>),
+    And(Box<TagExpr>, Box<TagExpr>),
+    Or(Box<TagExpr>, Box<TagExpr>),
+}
+
+impl TagExpr {
+    pub fn parse(input: &str) -> Result<Self, String> {
+        let tokens = tag_tokens(input)?;
+        let mut pos = 0;
+        let expr = parse_or(&tokens, &mut pos)?;
+        match tokens.get(pos) {
+            None => Ok(expr),
+            Some(token) => Err(format!("unexpected \"{}\"", token)),
+        }
+    }
+
+    /// `@env` also matches Karate's `@env=dev` style tags
+    pub fn matches(&self, tags: &[String]) -> bool {
+        match self {
+            Self::Tag(tag) => tags
+                .iter()
+                .any(|t| t == tag || t.strip_prefix(tag.as_str()).is_some_and(|rest| rest.starts_with('='))),
+            Self::Not(inner) => !inner.matches(tags),
+            Self::And(a, b) => a.matches(tags) && b.matches(tags),
+            Self::Or(a, b) => a.matches(tags) || b.matches(tags),
+        }
+    }
+}
+
+fn tag_tokens(input: &str) -> Result<Vec<String>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = input.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '(' | ')' | '~' | ',' => {
+                tokens.push(c.to_string());
+                chars.next();
+            }
+            _ => {
+                let mut word = String::new();
+                while let Some(&c) = chars.peek() {
+                    if c.is_whitespace() || "(),~".contains(c) {
+                        break;
+                    }
+                    word.push(c);
+                    chars.next();
+                }
+                let keyword = matches!(word.as_str(), "and" | "or" | "not");
+                if !keyword && !word.starts_with('@') {
+                    return Err(format!("expected a tag like @{} or and/or/not", word));
+                }
+                tokens.push(word);
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+fn parse_or(tokens: &[String], pos: &mut usize) -> Result<TagExpr, String> {
+    let mut left = parse_and(tokens, pos)?;
+    while matches!(tokens.get(*pos).map(String::as_str), Some("or" | ",")) {
+        *pos += 1;
+        let right = parse_and(tokens, pos)?;
+        left = TagExpr::Or(Box::new(left), Box::new(right));
+    }
+    Ok(left)
+}
+
+fn parse_and(tokens: &[String], pos: &mut usize) -> Result<TagExpr, String> {
+    let mut left = parse_not(tokens, pos)?;
+    while tokens.get(*pos).map(String::as_str) == Some("and") {
+        *pos += 1;
+        let right = parse_not(tokens, pos)?;
+        left = TagExpr::And(Box::new(left), Box::new(right));
+    }
+    Ok(left)
+}
+
+fn parse_not(tokens: &[String], pos: &mut usize) -> Result<TagExpr, String> {
+    match tokens.get(*pos).map(String::as_str) {
+        Some("not" | "~") => {
+            *pos += 1;
+            Ok(TagExpr::Not(Box::new(parse_not(tokens, pos)?)))
+        }
+        Some("(") => {
+            *pos += 1;
+            let inner = parse_or(tokens, pos)?;
+            if tokens.get(*pos).map(String::as_str) != Some(")") {
+                return Err("missing \")\"".to_string());
+            }
+            *pos += 1;
+            Ok(inner)
+        }
+        Some(tag) if tag.starts_with('@') => {
+            *pos += 1;
+            Ok(TagExpr::Tag(tag.to_string()))
+        }
+        Some(token) => Err(format!("unexpected \"{}\"", token)),
+        None => Err("expression ends early".to_string()),
+    }
+}
+
+/// Which scenarios to run
+#[derive(Debug, Clone, Default)]
+pub struct Selection {
+    pub name: Option<Regex>,
+    pub tags: Option<TagExpr>,
+}
+
+impl Selection {
+    pub fn is_empty(&self) -> bool {
+        self.name.is_none() && self.tags.is_none()
+    }
+
+    fn matches(&self, name: &str, tags: &[String]) -> bool {
+        self.name.as_ref().is_none_or(|re| re.is_match(name))
+            && self.tags.as_ref().is_none_or(|expr| expr.matches(tags))
+    }
+
+    /// Lines to run in a feature: scenario lines, or example row lines when only
+    /// some examples blocks of an outline match
+    ///
+    /// `@ignore` scenarios and examples are left out. When the feature was given with lines,
+    /// only scenarios containing one of them, or outlines and rows at one of them, are kept.
+    pub fn select_lines(&self, feature: &Feature) -> Vec<u32> {
+        let asked = |range: std::ops::RangeInclusive<u32>| {
+            feature.lines.is_empty() || feature.lines.iter().any(|line| range.contains(line))
+        };
+        let mut lines = Vec::new();
+        for scenario in feature.runnable() {
+            let tags: Vec<String> = feature.tags.iter().chain(&scenario.tags).cloned().collect();
+            if !scenario.outline {
+                if asked(scenario.line..=scenario.end_line()) && self.matches(&scenario.name, &tags) {
+                    lines.push(scenario.line);
+                }
+                continue;
+            }
+
+            let examples: Vec<&Examples> = scenario.examples.iter().filter(|e| !ignored(&e.tags)).collect();
+            let whole = asked(scenario.line..=scenario.line);
+            let rows: Vec<u32> = examples
+                .iter()
+                .filter(|examples| {
+                    let tags: Vec<String> = tags.iter().chain(&examples.tags).cloned().collect();
+                    self.matches(&scenario.name, &tags)
+                })
+                .flat_map(|examples| examples.rows.iter().map(|row| row.line))
+                .filter(|&line| whole || asked(line..=line))
+                .collect();
+            if !rows.is_empty() && rows.len() == scenario.run_count() {
+                lines.push(scenario.line);
+            } else {
+                lines.extend(rows);
+            }
+        }
+        lines
+    }
+}
+
+/// `file.feature:L1:L2` arguments for Karate, one per feature, relative to `base_dir`
+pub fn selected_test_args(features: &[Feature], selection: &Selection, base_dir: &Path) -> Vec<String> {
+    features
+        .iter()
+        .filter_map(|feature| {
+            let lines = selection.select_lines(feature);
+            if lines.is_empty() {
+                return None;
+            }
+            let path = feature.path.strip_prefix(base_dir).unwrap_or(&feature.path);
+            let lines: Vec<String> = lines.iter().map(u32::to_string).collect();
+            Some(format!("{}:{}", path.display(), lines.join(":")))
+        })
+        .collect()
+}
+
+/// Print features and their scenarios with line numbers and tags (for `list`)
+pub fn print_features(features: &[Feature], selection: &Selection, base_dir: &Path, theme: &Theme) {
+    let palette = &theme.palette;
+    let mut scenario_count = 0;
+    let mut run_count = 0;
+    for feature in features {
+        let lines = selection.select_lines(feature);
+        let scenarios: Vec<&Scenario> = feature
+            .scenarios
+            .iter()
+            .filter(|s| lines.iter().any(|line| (s.line..=s.end_line()).contains(line)))
+            .collect();
+        if scenarios.is_empty() {
+            continue;
+        }
+
+        let path = feature.path.strip_prefix(base_dir).unwrap_or(&feature.path);
+        println!(
+            "{}  {} {}",
+            palette.text_strong.paint(&path.display().to_string()),
+            feature.name,
+            palette.muted.paint(&feature.tags.join(" "))
+        );
+        for scenario in scenarios {
+            let runs = if scenario.outline {
+                let rows = scenario
+                    .examples
+                    .iter()
+                    .flat_map(|e| &e.rows)
+                    .filter(|row| lines.contains(&row.line) || lines.contains(&scenario.line))
+                    .count();
+                run_count += rows;
+                format!(" ({} example{})", rows, if rows == 1 { "" } else { "s" })
+            } else {
+                run_count += 1;
+                String::new()
+            };
+            scenario_count += 1;
+            println!(
+                "  {:>5}  {}{} {}",
+                palette.muted.paint(&format!(":{}", scenario.line)),
+                scenario.name,
+                palette.muted.paint(&runs),
+                palette.info.paint(&scenario.tags.join(" "))
+            );
+        }
+    }
+    println!();
+    println!(
+        "{}",
+        palette.muted.paint(&format!("{} scenarios, {} runs", scenario_count, run_count))
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FEATURE: &str = r#"
+@patients
+Feature: Patients
+
+  Background:
+    * url baseUrl
+
+  @smoke
+  Scenario: list patients
+    Given path 'patients'
+    And request
+      """
+      { "Scenario:": "not a scenario" }
+      """
+    When method get
+    Then status 200
+
+  Scenario Outline: get patient <id>
+    * path 'patients', <id>
+
+    @fast
+    Examples:
+      | id |
+      | 1  |
+      | 2  |
+
+    @slow
+    Examples:
+      | id |
+      | 3  |
+"#;
+
+    #[test]
+    fn test_parse_feature() {
+        let feature = parse(Path::new("patients.feature"), FEATURE).unwrap();
+        assert_eq!(feature.name, "Patients");
+        assert_eq!(feature.tags, vec!["@patients"]);
+        assert_eq!(feature.background.as_ref().unwrap().steps.len(), 1);
+        assert_eq!(feature.scenarios.len(), 2);
+        assert_eq!(feature.run_count(), 4);
+
+        let list = &feature.scenarios[0];
+        assert_eq!((list.line, list.tags.clone()), (9, vec!["@smoke".to_string()]));
+        assert_eq!(list.steps.len(), 4);
+
+        let outline = &feature.scenarios[1];
+        assert_eq!(outline.examples[1].tags, vec!["@slow"]);
+        assert_eq!(outline.examples[0].rows[1].values, vec!["2"]);
+        assert_eq!(feature.scenario_at(29).map(|s| s.line), Some(outline.line));
+        assert_eq!(feature.scenario_at(14).map(|s| s.name.as_str()), Some("list patients"));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(
+            parse(Path::new("x"), "  * print 'a'").unwrap_err().to_string(),
+            "line 1: expected Feature: before any other keyword"
+        );
+        let err = parse(Path::new("x"), "Feature: a\nScenario: b\n* def x =\n\"\"\"\n{").unwrap_err();
+        assert_eq!(err.line, 4);
+    }
+
+    #[test]
+    fn test_tag_expressions_and_selection() {
+        let tags = |t: &[&str]| t.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        let expr = TagExpr::parse("@smoke and not (@slow or @wip)").unwrap();
+        assert!(expr.matches(&tags(&["@smoke"])));
+        assert!(!expr.matches(&tags(&["@smoke", "@wip"])));
+        assert!(TagExpr::parse("~@ignore").unwrap().matches(&tags(&["@x"])));
+        assert!(TagExpr::parse("@a,@env").unwrap().matches(&tags(&["@env=dev"])));
+        assert!(TagExpr::parse("@a and").is_err());
+        assert!(TagExpr::parse("smoke").is_err());
+
+        let feature = parse(Path::new("patients.feature"), FEATURE).unwrap();
+        let by_tag = Selection {
+            name: None,
+            tags: Some(TagExpr::parse("not @slow").unwrap()),
+        };
+        assert_eq!(by_tag.select_lines(&feature), vec![9, 24, 25]);
+
+        let by_name = Selection {
+            name: Some(Regex::new("^get").unwrap()),
+            tags: None,
+        };
+        assert_eq!(by_name.select_lines(&feature), vec![18]);
+        assert_eq!(
+            selected_test_args(std::slice::from_ref(&feature), &by_tag, Path::new("")),
+            vec!["patients.feature:9:24:25"]
+        );
+
+        let asked = Feature {
+            lines: vec![13, 25],
+            ..feature.clone()
+        };
+        assert_eq!(by_tag.select_lines(&asked), vec![9, 25]);
+        assert!(by_name.select_lines(&Feature { lines: vec![9], ..feature }).is_empty());
+
+        let ignored = parse(
+            Path::new("ignored.feature"),
+            "Feature: f\n@ignore\nScenario: helper\n* print 1\nScenario Outline: o\n* print <x>\n\
+             Examples:\n| x |\n| 1 |\n@ignore\nExamples:\n| x |\n| 2 |\n",
+        )
+        .unwrap();
+        assert_eq!(Selection::default().select_lines(&ignored), vec![5]);
+    }
+
+    #[test]
+    fn test_split_test_path() {
+        assert_eq!(split_test_path("a/b.feature:9:24"), ("a/b.feature".to_string(), vec![9, 24]));
+        assert_eq!(split_test_path("a/b.feature"), ("a/b.feature".to_string(), vec![]));
+        assert_eq!(split_test_path("a/b.feature:x"), ("a/b.feature:x".to_string(), vec![]));
+    }
+}
@@ -0,0 +1,168 @@
+//! Commented starter configuration for `karate-monitor init`
+
+use crate::config::Config;
+
+/// Comment above each section header
+const SECTION_DOCS: &[(&str, &str)] = &[
+    ("api", "API server under test"),
+    ("karate", "Karate runner"),
+    ("logging", "API log filtering, context, dedup and export"),
+    ("display", "Output formatting"),
+    ("analysis", "Summaries and failed-only mode"),
+    ("redaction", "Masking of secrets and personal data"),
+    ("progress", "Progress bar and ETA"),
+    ("schema", "API log line format and field names, for services not logging JSON like our slog setup"),
+    ("classify", "Rules assigning log types, prefixes and colours; configured rules are tried before the built-in ones"),
+    ("filters.api", "Per-source filters (api, sql, body, batch, karate); unset keys fall back to [logging]"),
+];
+
+/// Comment above each key, by key path
+const KEY_DOCS: &[(&str, &str)] = &[
+    ("api.command", "Command that starts the API server"),
+    ("api.health_url", "Polled until it answers before tests start"),
+    ("api.health_timeout_secs", "Give up waiting for the API after this long"),
+    ("api.health_interval_secs", "Delay between health checks"),
+    ("karate.jar_path", "Karate standalone jar"),
+    ("karate.classpath", "Extra classpath entries"),
+    ("karate.threads", "Parallel threads"),
+    ("karate.output_format", "Karate report formats"),
+    ("karate.report_dir", "Where Karate writes its reports"),
+    ("karate.default_test_path", "Tests to run when none are given on the command line"),
+    ("karate.use_compact_object_headers", "JVM -XX:+UseCompactObjectHeaders"),
+    ("karate.use_zgc", "JVM -XX:+UseZGC"),
+    ("logging.level", "Minimum level: ALL, DEBUG, INFO, WARN, ERROR"),
+    ("logging.include_patterns", "Show only entries matching one of these regexes"),
+    ("logging.exclude_patterns", "Hide entries matching any of these regexes"),
+    ("logging.context_before", "Filtered-out entries shown before each shown entry"),
+    ("logging.context_after", "... and after it"),
+    ("logging.context_same_request", "Only entries with the same request_id count as context"),
+    ("logging.dedup", "Collapse consecutive entries differing only in ids/numbers"),
+    ("logging.rate_limit", "Entries shown per message and window (0 = unlimited)"),
+    ("logging.rate_limit_window_secs", "Rate limit window"),
+    ("logging.dedup_export", "Also collapse/limit exported logs"),
+    ("logging.colors", "Coloured output"),
+    ("logging.export_path", "Export logs to this file (empty = no export)"),
+    ("logging.export_format", "json, text or both"),
+    ("display.theme", "default, ascii, minimal, high-contrast"),
+    ("display.show_timestamps", "Prefix API entries with their timestamp"),
+    ("display.pretty_bodies", "Pretty-print request/response bodies"),
+    ("display.body_max_items", "Array items shown per body before truncating"),
+    ("display.body_max_string_len", "Characters shown per body string before truncating"),
+    ("display.sql_style", "pretty, compact or raw"),
+    ("display.sql_compact_max_len", "Longest SQL kept on one line in compact style"),
+    ("display.slow_query_ms", "Queries slower than this are highlighted"),
+    ("display.tui", "Live dashboard instead of streamed output"),
+    ("analysis.show_test_summary", "Print the test summary at the end"),
+    ("analysis.track_sql", "Collect SQL statistics"),
+    ("analysis.show_sql_stats", "Print SQL statistics at the end"),
+    ("analysis.failed_only", "Show API logs only for failed requests"),
+    ("analysis.slowest_scenarios", "Scenarios listed in the slowest-scenarios report (0 hides it)"),
+    ("analysis.feature_breakdown", "Per-feature results after the test summary"),
+    ("redaction.enabled", "Mask secrets before display, export and correlation"),
+    ("redaction.preset", "default (common secrets) or none"),
+    ("redaction.fields", "Body fields masked at any depth"),
+    ("redaction.paths", "JSON paths masked exactly, e.g. $.request_body.patients[*].name"),
+    ("redaction.patterns", "Regexes masked in messages (group 1 when present)"),
+    ("redaction.sql_literals", "Mask quoted literals in SQL statements"),
+    ("redaction.mask", "Replacement text"),
+    ("progress.enabled", "Show progress and ETA"),
+    ("progress.history_path", "Scenario timings used for the ETA, relative to karate.report_dir"),
+    ("progress.status_interval_secs", "Status line interval when output is not a terminal"),
+    ("schema.format", "auto (JSON, then pattern, then logfmt, per line), json, logfmt or regex"),
+    ("classify.builtin_rules", "Keep the built-in rules after the configured ones"),
+    ("filters.*.enabled", "false hides this source entirely"),
+];
+
+/// Optional keys with no default, shown commented out after the section's keys
+const OPTIONAL_EXAMPLES: &[(&str, &str)] = &[
+    ("logging", "filter = 'status >= 400 && uri ~ \"/karte\"'  # see \"Filter Expressions\" in the README"),
+    ("schema", "pattern = '^(?P<time>\\S+) (?P<level>\\w+) (?P<msg>.*)$'  # named groups are entry fields"),
+    ("schema", "fields = { time = [\"ts\"], msg = [\"message\"], request_id = [\"req_id\"], elapsed = [\"duration\"] }"),
+    ("schema", "levels = { notice = \"INFO\", \"50\" = \"ERROR\" }"),
+    ("schema", "duration_units = { elapsed = \"ns\" }  # ns, us, ms or s"),
+    ("classify", "api = [{ type = \"sql\", when = 'msg ~ \"^DB \"', prefix = \"DB \", color = \"cyan\" }]"),
+    ("classify", "karate = [{ type = \"batch\", patterns = ['^\\[job\\]'] }]  # regexes, all must match"),
+    ("filters.api", "level = \"INFO\""),
+    ("filters.api", "include_patterns = []"),
+    ("filters.api", "exclude_patterns = []"),
+    ("filters.api", "filter = 'status >= 500'"),
+];
+
+/// Starter config in TOML: every default, each key with a comment
+pub fn starter_config() -> String {
+    let defaults = toml::to_string_pretty(&Config::default()).expect("default config serializes");
+    let mut out = String::from(
+        "# karate-monitor configuration\n\
+         #\n\
+         # Values below are the defaults. Other files can be layered underneath with\n\
+         # include = [\"../shared/karate-monitor.toml\"], and [profiles.<name>] tables\n\
+         # overlay these settings when selected with --profile.\n",
+    );
+
+    let mut section = String::new();
+    for line in defaults.lines() {
+        if let Some(header) = line.strip_prefix('[').and_then(|h| h.strip_suffix(']')) {
+            push_examples(&mut out, &section);
+            section = header.to_string();
+            out.push('\n');
+            if let Some(doc) = lookup(SECTION_DOCS, &section) {
+                out.push_str(&format!("# {}\n", doc));
+            }
+            out.push_str(line);
+            out.push('\n');
+            continue;
+        }
+        if line.trim().is_empty() {
+            continue;
+        }
+        if let Some((key, _)) = line.split_once(" = ") {
+            // The five filters.<source> tables share their key docs
+            let path = match section.strip_prefix("filters.") {
+                Some(_) => format!("filters.*.{}", key),
+                None => format!("{}.{}", section, key),
+            };
+            if let Some(doc) = lookup(KEY_DOCS, &path) {
+                out.push_str(&format!("# {}\n", doc));
+            }
+        }
+        out.push_str(line);
+        out.push('\n');
+    }
+    push_examples(&mut out, &section);
+    out
+}
+
+fn lookup(docs: &[(&str, &'static str)], key: &str) -> Option<&'static str> {
+    docs.iter().find(|(k, _)| *k == key).map(|(_, doc)| *doc)
+}
+
+fn push_examples(out: &mut String, section: &str) {
+    for (_, example) in OPTIONAL_EXAMPLES.iter().filter(|(s, _)| *s == section) {
+        out.push_str(&format!("# {}\n", example));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_starter_config_round_trips_to_defaults() {
+        let parsed: Config = toml::from_str(&starter_config()).unwrap();
+        assert_eq!(
+            serde_json::to_value(parsed).unwrap(),
+            serde_json::to_value(Config::default()).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_every_key_is_documented() {
+        let starter = starter_config();
+        let lines: Vec<&str> = starter.lines().collect();
+        for (i, line) in lines.iter().enumerate() {
+            if line.contains(" = ") && !line.starts_with('#') {
+                assert!(lines[i - 1].starts_with('#'), "undocumented key: {}", line);
+            }
+        }
+    }
+}
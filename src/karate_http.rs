@@ -0,0 +1,437 @@
+//! Karate's own HTTP logging: the numbered `request:` / `response:` blocks it prints at DEBUG
+
+use crate::log_parser::{extract_path_query, is_log_line, parse_karate_summary};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+/// One HTTP call made by Karate, with the response when it was logged
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct HttpExchange {
+    /// Karate numbers the calls of each scenario from 1
+    pub number: u32,
+    /// Logging thread, e.g. `pool-1-thread-2`
+    pub thread: Option<String>,
+    pub method: String,
+    pub url: String,
+    pub request_headers: Vec<(String, String)>,
+    pub request_body: Option<String>,
+    pub status: Option<u16>,
+    pub response_time_ms: Option<u64>,
+    pub response_headers: Vec<(String, String)>,
+    pub response_body: Option<String>,
+}
+
+impl HttpExchange {
+    /// `POST /api/v1/users -> 201 (36 ms)`
+    pub fn summary(&self) -> String {
+        let target = extract_path_query(&self.url).unwrap_or_else(|| self.url.clone());
+        let status = self
+            .status
+            .map(|s| s.to_string())
+            .unwrap_or_else(|| "no response".to_string());
+        match self.response_time_ms {
+            Some(ms) => format!("{} {} -> {} ({} ms)", self.method, target, status, ms),
+            None => format!("{} {} -> {}", self.method, target, status),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Direction {
+    Request,
+    Response,
+}
+
+#[derive(Debug)]
+struct Block {
+    direction: Direction,
+    thread: Option<String>,
+    number: Option<u32>,
+    /// `GET http://...` for requests, `200` for responses
+    first: Option<String>,
+    headers: Vec<(String, String)>,
+    body: Vec<String>,
+    /// Blank lines not yet known to be inside the body
+    blank_lines: usize,
+    response_time_ms: Option<u64>,
+}
+
+/// Assembles Karate's request and response blocks into exchanges
+///
+/// ```text
+/// 12:00:01.100 [main] DEBUG com.intuit.karate - request:
+/// 1 > POST http://localhost:1323/api/v1/users
+/// 1 > Content-Type: application/json
+/// {"name":"alice"}
+///
+/// 12:00:01.136 [main] DEBUG com.intuit.karate - response time in milliseconds: 36
+/// 1 < 201
+/// {"id":1}
+/// ```
+///
+/// A block ends at the next log record. Blank lines inside a body are kept; trailing
+/// ones, and Karate's own output after them (`---`, `feature:`, summaries), end it.
+/// Requests wait for the response with the same thread and number; a request
+/// followed by another from its thread is returned without a response.
+#[derive(Debug, Default)]
+pub struct HttpLogParser {
+    block: Option<Block>,
+    pending: Vec<HttpExchange>,
+}
+
+impl HttpLogParser {
+    /// Body lines kept per request or response
+    const MAX_BODY_LINES: usize = 200;
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feed a Karate output line, returning an exchange once it is complete
+    pub fn push(&mut self, line: &str) -> Option<HttpExchange> {
+        let trimmed = line.trim();
+
+        if is_log_line(trimmed) {
+            let done = self.close();
+            self.block = block_header(trimmed);
+            return done;
+        }
+
+        let block = self.block.as_mut()?;
+        if let Some((number, direction, rest)) = marker(trimmed) {
+            if direction == block.direction && block.body.is_empty() {
+                block.number.get_or_insert(number);
+                if block.first.is_none() {
+                    block.first = Some(rest.to_string());
+                } else if let Some((name, value)) = rest.split_once(':') {
+                    block.headers.push((name.trim().to_string(), value.trim().to_string()));
+                }
+                return None;
+            }
+        }
+        if block.first.is_none() {
+            // The header was not followed by a numbered line; not an HTTP block
+            self.block = None;
+            return None;
+        }
+        if trimmed.is_empty() {
+            block.blank_lines += 1;
+            return None;
+        }
+        if block.blank_lines > 0 && ends_body(trimmed) {
+            return self.close();
+        }
+        for _ in 0..std::mem::take(&mut block.blank_lines) {
+            block.body.push(String::new());
+        }
+        block.body.push(line.trim_end().to_string());
+        block.body.truncate(Self::MAX_BODY_LINES);
+        None
+    }
+
+    /// Complete the open block and return every exchange still waiting (call when the stream ends)
+    pub fn finish(&mut self) -> Vec<HttpExchange> {
+        let mut exchanges: Vec<HttpExchange> = self.close().into_iter().collect();
+        exchanges.append(&mut self.pending);
+        exchanges
+    }
+
+    fn close(&mut self) -> Option<HttpExchange> {
+        let block = self.block.take()?;
+        let number = block.number?;
+        let first = block.first?;
+        let body = (!block.body.is_empty()).then(|| block.body.join("\n"));
+
+        match block.direction {
+            Direction::Request => {
+                let (method, url) = first.split_once(' ').unwrap_or((&first, ""));
+                let unanswered = self
+                    .pending
+                    .iter()
+                    .position(|p| p.thread == block.thread)
+                    .map(|i| self.pending.remove(i));
+                self.pending.push(HttpExchange {
+                    number,
+                    thread: block.thread,
+                    method: method.to_string(),
+                    url: url.trim().to_string(),
+                    request_headers: block.headers,
+                    request_body: body,
+                    ..HttpExchange::default()
+                });
+                unanswered
+            }
+            Direction::Response => {
+                let index = self
+                    .pending
+                    .iter()
+                    .position(|p| p.thread == block.thread && p.number == number)?;
+                let mut exchange = self.pending.remove(index);
+                exchange.status = first.split_whitespace().next().and_then(|s| s.parse().ok());
+                exchange.response_time_ms = block.response_time_ms;
+                exchange.response_headers = block.headers;
+                exchange.response_body = body;
+                Some(exchange)
+            }
+        }
+    }
+}
+
+/// Start a block for `request:` and `response time in milliseconds: N` log lines
+fn block_header(line: &str) -> Option<Block> {
+    let message = line.rsplit_once(" - ").map_or(line, |(_, m)| m).trim();
+    let (direction, response_time_ms) = if message == "request:" {
+        (Direction::Request, None)
+    } else if let Some(ms) = message.strip_prefix("response time in milliseconds:") {
+        (Direction::Response, ms.trim().parse().ok())
+    } else {
+        return None;
+    };
+    let thread = line
+        .split_once('[')
+        .and_then(|(_, rest)| rest.split_once(']'))
+        .map(|(thread, _)| thread.to_string());
+
+    Some(Block {
+        direction,
+        thread,
+        number: None,
+        first: None,
+        headers: Vec::new(),
+        body: Vec::new(),
+        blank_lines: 0,
+        response_time_ms,
+    })
+}
+
+/// Karate's own output that can follow a body after a blank line
+fn ends_body(line: &str) -> bool {
+    line.starts_with("---")
+        || line.starts_with(">>>")
+        || line.starts_with("feature:")
+        || parse_karate_summary(line).is_some()
+}
+
+/// `1 > GET http://...` or `1 < 200`
+fn marker(line: &str) -> Option<(u32, Direction, &str)> {
+    let (number, rest) = line.split_once(' ')?;
+    let number = number.parse().ok()?;
+    let direction = match rest.get(..2)? {
+        "> " => Direction::Request,
+        "< " => Direction::Response,
+        _ => return None,
+    };
+    Some((number, direction, rest[2..].trim()))
+}
+
+/// Lines appended to a log file, such as Karate's `karate.log`, since it was first opened
+#[derive(Debug)]
+pub struct LogTail {
+    path: PathBuf,
+    file: Option<File>,
+    offset: u64,
+    /// Bytes of a line not yet terminated
+    partial: Vec<u8>,
+}
+
+impl LogTail {
+    /// Follow `path` from its current end, so an earlier run's lines are skipped;
+    /// a file created later is read from its start
+    pub fn new(path: &Path) -> Self {
+        let offset = std::fs::metadata(path).map_or(0, |m| m.len());
+        Self {
+            path: path.to_path_buf(),
+            file: None,
+            offset,
+            partial: Vec::new(),
+        }
+    }
+
+    /// Complete lines written since the last call
+    pub fn read_lines(&mut self) -> Vec<String> {
+        if self.file.is_none() {
+            self.file = File::open(&self.path).ok();
+        }
+        let Some(file) = self.file.as_mut() else {
+            return Vec::new();
+        };
+        let len = file.metadata().map_or(0, |m| m.len());
+        if len < self.offset {
+            // Truncated when Karate started writing it afresh
+            self.offset = 0;
+            self.partial.clear();
+        }
+        let mut appended = Vec::new();
+        if file.seek(SeekFrom::Start(self.offset)).is_err() || file.read_to_end(&mut appended).is_err() {
+            return Vec::new();
+        }
+        self.offset += appended.len() as u64;
+        self.partial.extend(appended);
+
+        let Some(end) = self.partial.iter().rposition(|b| *b == b'\n') else {
+            return Vec::new();
+        };
+        let complete: Vec<u8> = self.partial.drain(..=end).collect();
+        String::from_utf8_lossy(&complete)
+            .lines()
+            .map(|line| line.trim_end_matches('\r').to_string())
+            .collect()
+    }
+}
+
+/// Calls grouped by scenario, using Karate's per-scenario numbering
+#[derive(Debug, Default)]
+pub struct CallLog {
+    /// Run number and calls of each thread's current scenario
+    scenarios: HashMap<Option<String>, (usize, Vec<HttpExchange>)>,
+    last_thread: Option<Option<String>>,
+    /// Scenario runs seen so far; each example row of an outline is a run
+    runs: usize,
+}
+
+impl CallLog {
+    pub fn push(&mut self, exchange: HttpExchange) {
+        let (run, calls) = self.scenarios.entry(exchange.thread.clone()).or_default();
+        // Numbering starts again at 1 in the next scenario
+        if calls.last().is_none_or(|last| exchange.number <= last.number) {
+            calls.clear();
+            self.runs += 1;
+            *run = self.runs;
+        }
+        self.last_thread = Some(exchange.thread.clone());
+        calls.push(exchange);
+    }
+
+    /// Run number of the scenario that made the most recent call (0 before any call)
+    pub fn current_run(&self) -> usize {
+        self.last_thread
+            .as_ref()
+            .and_then(|thread| self.scenarios.get(thread))
+            .map_or(0, |(run, _)| *run)
+    }
+
+    /// Calls made so far by the scenario that made the most recent call
+    pub fn current_scenario(&self) -> &[HttpExchange] {
+        self.last_thread
+            .as_ref()
+            .and_then(|thread| self.scenarios.get(thread))
+            .map(|(_, calls)| calls.as_slice())
+            .unwrap_or_default()
+    }
+
+    pub fn last(&self) -> Option<&HttpExchange> {
+        self.current_scenario().last()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OUTPUT: &str = "12:00:01.100 [main] DEBUG com.intuit.karate - request:
+1 > POST http://localhost:1323/api/v1/users?notify=true
+1 > Content-Type: application/json; charset=UTF-8
+{
+  \"name\": \"alice\"
+}
+
+12:00:01.136 [main] DEBUG com.intuit.karate - response time in milliseconds: 36
+1 < 201
+1 < Content-Type: application/json
+{\"id\":1}
+
+12:00:01.140 [main] INFO  com.intuit.karate - [print] created
+12:00:01.200 [main] DEBUG com.intuit.karate - request:
+2 > GET http://localhost:1323/api/v1/users/1
+";
+
+    #[test]
+    fn test_request_and_response_blocks() {
+        let mut parser = HttpLogParser::new();
+        let exchanges: Vec<_> = OUTPUT.lines().filter_map(|l| parser.push(l)).collect();
+        assert_eq!(exchanges.len(), 1);
+
+        let created = &exchanges[0];
+        assert_eq!((created.number, created.thread.as_deref()), (1, Some("main")));
+        assert_eq!(created.method, "POST");
+        assert_eq!(created.request_headers[0].1, "application/json; charset=UTF-8");
+        assert_eq!(created.request_body.as_deref(), Some("{\n  \"name\": \"alice\"\n}"));
+        assert_eq!(created.response_body.as_deref(), Some("{\"id\":1}"));
+        assert_eq!(created.summary(), "POST /api/v1/users?notify=true -> 201 (36 ms)");
+
+        // The second request never got a response
+        let rest = parser.finish();
+        assert_eq!(rest.len(), 1);
+        assert_eq!(rest[0].summary(), "GET /api/v1/users/1 -> no response");
+    }
+
+    #[test]
+    fn test_blank_lines_inside_bodies() {
+        let output = "12:00:01.136 [main] DEBUG com.intuit.karate - request:
+1 > POST http://localhost:1323/api/v1/notes
+first paragraph
+
+second paragraph
+
+12:00:01.150 [main] DEBUG com.intuit.karate - response time in milliseconds: 5
+1 < 201
+created
+
+---------------------------------------------------------
+";
+        let mut parser = HttpLogParser::new();
+        let mut exchanges: Vec<_> = output.lines().filter_map(|l| parser.push(l)).collect();
+        exchanges.extend(parser.finish());
+        assert_eq!(exchanges.len(), 1);
+        assert_eq!(
+            exchanges[0].request_body.as_deref(),
+            Some("first paragraph\n\nsecond paragraph")
+        );
+        assert_eq!(exchanges[0].response_body.as_deref(), Some("created"));
+    }
+
+    #[test]
+    fn test_log_tail_reads_appended_lines() {
+        let path = std::env::temp_dir().join(format!("karate-monitor-tail-{}.log", std::process::id()));
+        std::fs::write(&path, "from an earlier run\n").unwrap();
+        let mut tail = LogTail::new(&path);
+        assert!(tail.read_lines().is_empty());
+
+        let append = |text: &str| {
+            use std::io::Write;
+            let mut file = std::fs::OpenOptions::new().append(true).open(&path).unwrap();
+            file.write_all(text.as_bytes()).unwrap();
+        };
+        append("1 > GET http://x/1\n1 > Acc");
+        assert_eq!(tail.read_lines(), vec!["1 > GET http://x/1"]);
+        append("ept: */*\n");
+        assert_eq!(tail.read_lines(), vec!["1 > Accept: */*"]);
+
+        // Started afresh by the next run
+        std::fs::write(&path, "new\n").unwrap();
+        assert_eq!(tail.read_lines(), vec!["new"]);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_call_log_groups_by_scenario() {
+        let call = |number, thread: &str| HttpExchange {
+            number,
+            thread: Some(thread.to_string()),
+            url: format!("http://x/{}/{}", thread, number),
+            ..HttpExchange::default()
+        };
+        let mut log = CallLog::default();
+        log.push(call(1, "a"));
+        log.push(call(2, "a"));
+        log.push(call(1, "b"));
+        assert_eq!(log.current_scenario().len(), 1);
+        assert_eq!(log.current_run(), 2);
+        log.push(call(1, "a"));
+        assert_eq!(log.current_scenario().len(), 1);
+        assert_eq!(log.current_run(), 3);
+        assert_eq!(log.last().map(|c| c.url.as_str()), Some("http://x/a/1"));
+    }
+}
@@ -2,7 +2,10 @@
 
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use regex::Regex;
+use std::collections::{HashMap, HashSet};
+use std::sync::LazyLock;
+use std::time::Duration;
 
 /// Represents a parsed log entry from the Go API
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -23,10 +26,16 @@ pub struct ApiLogEntry {
     pub status: Option<u16>,
     #[serde(default)]
     pub latency_human: Option<String>,
+    /// `latency_human` parsed as a Go duration
+    #[serde(skip)]
+    pub latency: Option<Duration>,
     #[serde(default)]
     pub sql: Option<String>,
     #[serde(default)]
     pub elapsed: Option<String>,
+    /// `elapsed` parsed as a Go duration
+    #[serde(skip)]
+    pub elapsed_duration: Option<Duration>,
     #[serde(default)]
     pub rows_affected: Option<i64>,
     #[serde(default)]
@@ -55,12 +64,13 @@ pub enum LogLevel {
 }
 
 impl LogLevel {
+    /// Read a level name case-insensitively, including the zap, logrus and Python aliases
     pub fn from_str(s: &str) -> Self {
         match s.to_uppercase().as_str() {
-            "DEBUG" => LogLevel::Debug,
+            "TRACE" | "DEBUG" => LogLevel::Debug,
             "INFO" => LogLevel::Info,
             "WARN" | "WARNING" => LogLevel::Warn,
-            "ERROR" => LogLevel::Error,
+            "ERROR" | "CRITICAL" | "FATAL" | "PANIC" | "DPANIC" => LogLevel::Error,
             _ => LogLevel::Info,
         }
     }
@@ -72,6 +82,8 @@ pub struct KarateTestResult {
     pub total_scenarios: u32,
     pub passed: u32,
     pub failed: u32,
+    /// Seconds reported in the `time:` column, when present
+    pub time_secs: Option<f64>,
 }
 
 /// Represents failed test information extracted from Karate output
@@ -81,6 +93,8 @@ pub struct KarateFailure {
     pub line_number: u32,
     pub assertion: String,
     pub url: Option<String>,
+    /// The failing step, e.g. `Then status 400`
+    pub step: Option<String>,
     pub expected: Option<String>,
     pub actual: Option<String>,
     pub response: Option<String>,
@@ -99,41 +113,35 @@ pub enum LogType {
     KarateFailure,
     KarateInfo,
     KarateSummary,
+    /// A batch job line the API printed into Karate's output
+    KarateBatch,
 }
 
 impl ApiLogEntry {
     /// Parse a JSON line into an ApiLogEntry
     pub fn parse(line: &str) -> Option<Self> {
-        serde_json::from_str(line).ok()
+        serde_json::from_str(line).ok().map(Self::with_durations)
     }
 
-    /// Get the log level
-    pub fn log_level(&self) -> LogLevel {
-        LogLevel::from_str(&self.level)
+    /// Build an entry from a JSON object
+    pub fn from_value(value: serde_json::Value) -> Option<Self> {
+        serde_json::from_value(value).ok().map(Self::with_durations)
     }
 
-    /// Determine the type of log entry
-    pub fn log_type(&self) -> LogType {
-        if self.msg.contains("SQL") || self.sql.is_some() {
-            if self.level == "ERROR" || self.err.is_some() {
-                return LogType::ApiError;
-            }
-            return LogType::ApiSql;
-        }
-
-        if self.msg.contains("request / response body dump") {
-            return LogType::ApiBodyDump;
-        }
-
-        if self.msg == "REQUEST" {
-            return LogType::ApiRequest;
-        }
+    fn with_durations(mut self) -> Self {
+        self.elapsed_duration = self.elapsed.as_deref().and_then(parse_go_duration);
+        self.latency = self.latency_human.as_deref().and_then(parse_go_duration);
+        self
+    }
 
-        if self.level == "ERROR" {
-            return LogType::ApiError;
-        }
+    /// `elapsed` in milliseconds, when it parses
+    pub fn elapsed_ms(&self) -> Option<f64> {
+        self.elapsed_duration.map(|d| d.as_nanos() as f64 / 1e6)
+    }
 
-        LogType::ApiGeneral
+    /// Get the log level
+    pub fn log_level(&self) -> LogLevel {
+        LogLevel::from_str(&self.level)
     }
 
     /// Check if this is a final request log (has status)
@@ -152,58 +160,342 @@ impl ApiLogEntry {
     }
 }
 
-/// Parse Karate output line
-pub fn parse_karate_line(line: &str) -> LogType {
-    let trimmed = line.trim();
-
-    // Check for scenario result summary
-    if trimmed.contains("scenarios:") && trimmed.contains("passed:") && trimmed.contains("failed:") {
-        return LogType::KarateSummary;
+/// Parse a Go `time.Duration` string such as `1.2s`, `850µs` or `1h2m3.5s`
+///
+/// A sign is not accepted (durations are never negative in our logs), and a bare
+/// number is only valid as `0`, as in Go.
+pub fn parse_go_duration(text: &str) -> Option<Duration> {
+    let text = text.trim();
+    if text == "0" {
+        return Some(Duration::ZERO);
     }
-
-    // Check for failure indicators
-    if trimmed.contains("status code was:") && trimmed.contains("expected:") {
-        return LogType::KarateFailure;
+    if text.is_empty() {
+        return None;
     }
 
-    if trimmed.starts_with("Scenario:") || trimmed.contains(".feature:") {
-        if trimmed.contains("failed") {
-            return LogType::KarateFailure;
-        }
-        return LogType::KarateScenarioStart;
+    let mut total_ns = 0.0;
+    let mut rest = text;
+    while !rest.is_empty() {
+        let number_end = rest
+            .find(|c: char| !c.is_ascii_digit() && c != '.')
+            .unwrap_or(rest.len());
+        let number: f64 = rest[..number_end].parse().ok()?;
+        rest = &rest[number_end..];
+
+        let unit_end = rest
+            .find(|c: char| c.is_ascii_digit() || c == '.')
+            .unwrap_or(rest.len());
+        let ns_per_unit = match &rest[..unit_end] {
+            "ns" => 1.0,
+            // Go prints U+00B5; U+03BC is accepted as well
+            "us" | "µs" | "μs" => 1e3,
+            "ms" => 1e6,
+            "s" => 1e9,
+            "m" => 60e9,
+            "h" => 3600e9,
+            _ => return None,
+        };
+        total_ns += number * ns_per_unit;
+        rest = &rest[unit_end..];
     }
+    Some(Duration::from_nanos(total_ns.round() as u64))
+}
 
-    // Check for test summary lines
-    if trimmed.starts_with("features:") || trimmed.starts_with("elapsed:") {
-        return LogType::KarateSummary;
+/// Milliseconds in one duration unit, with the units and spellings `parse_go_duration` accepts
+pub fn duration_unit_ms(unit: &str) -> Option<f64> {
+    if unit.is_empty() || !unit.chars().all(char::is_alphabetic) {
+        return None;
     }
-
-    LogType::KarateInfo
+    parse_go_duration(&format!("1{}", unit)).map(|d| d.as_nanos() as f64 / 1e6)
 }
 
 /// Extract test results from Karate summary line
 /// Example: "scenarios:  2 | passed:  1 | failed:  1 | time: 0.4675"
 pub fn parse_karate_summary(line: &str) -> Option<KarateTestResult> {
-    let re = regex::Regex::new(r"scenarios:\s*(\d+)\s*\|\s*passed:\s*(\d+)\s*\|\s*failed:\s*(\d+)")
-        .ok()?;
+    static SUMMARY: LazyLock<Regex> = LazyLock::new(|| {
+        Regex::new(r"scenarios:\s*(\d+)\s*\|\s*passed:\s*(\d+)\s*\|\s*failed:\s*(\d+)(?:\s*\|\s*time:\s*([\d.]+))?")
+            .expect("valid summary regex")
+    });
 
-    let caps = re.captures(line)?;
+    let caps = SUMMARY.captures(line)?;
 
     Some(KarateTestResult {
         total_scenarios: caps.get(1)?.as_str().parse().ok()?,
         passed: caps.get(2)?.as_str().parse().ok()?,
         failed: caps.get(3)?.as_str().parse().ok()?,
+        time_secs: caps.get(4).and_then(|m| m.as_str().parse().ok()),
     })
 }
 
 /// Extract URL from Karate failure line
 /// Example: "status code was: 200, expected: 400, response time in milliseconds: 6, url: http://localhost:1323/api/v1/karte/outcome?patientID=1"
 pub fn extract_failure_url(line: &str) -> Option<String> {
-    let re = regex::Regex::new(r"url:\s*(https?://[^\s,]+)").ok()?;
-    let caps = re.captures(line)?;
+    static URL: LazyLock<Regex> =
+        LazyLock::new(|| Regex::new(r"url:\s*(https?://[^\s,]+)").expect("valid url regex"));
+    let caps = URL.captures(line)?;
     Some(caps.get(1)?.as_str().to_string())
 }
 
+/// Extract a feature location from a line
+/// Example: "classpath:tests/karte.feature:40" -> ("classpath:tests/karte.feature", 40)
+pub fn extract_feature_location(line: &str) -> Option<(String, u32)> {
+    static LOCATION: LazyLock<Regex> =
+        LazyLock::new(|| Regex::new(r"(\S+\.feature):(\d+)").expect("valid feature location regex"));
+    let caps = LOCATION.captures(line)?;
+    Some((caps.get(1)?.as_str().to_string(), caps.get(2)?.as_str().parse().ok()?))
+}
+
+/// Expected/actual values extracted from a Karate `match failed` block
+#[derive(Debug, Clone, PartialEq)]
+struct MatchFailure {
+    assertion: String,
+    expected: String,
+    actual: String,
+}
+
+/// Assembles Karate's multi-line failure output into complete failure records
+///
+/// Karate 1.x logs a failure as an `ERROR` line ending in `classpath:x.feature:12`,
+/// then the failing step, the reason (`match failed: ...` with the path and the
+/// actual and expected values, or `status code was: ...` with the URL and response
+/// body), and the location again. Blocks without a location (older versions, or
+/// output that was cut) use the last location seen. The `>>> failed features:`
+/// recap at the end of the run repeats failures and is skipped.
+#[derive(Debug, Default)]
+pub struct FailureParser {
+    block: Option<FailureBlock>,
+    last_location: Option<(String, u32)>,
+    in_recap: bool,
+    /// Scenario run the caller is in, see `set_scenario_run`
+    scenario_run: usize,
+    /// Failures already returned, so Karate's repeats are not counted twice
+    seen: HashSet<(usize, String, u32, String, Option<String>)>,
+}
+
+#[derive(Debug)]
+struct FailureBlock {
+    scenario_run: usize,
+    location: Option<(String, u32)>,
+    /// Started by an `ERROR` log line or a failure reason, not just a location
+    confirmed: bool,
+    lines: Vec<String>,
+}
+
+impl FailureParser {
+    /// Maximum lines buffered for one block
+    const MAX_LINES: usize = 60;
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Identify the scenario run that output now belongs to, so example rows of an
+    /// outline failing the same way at the same step are counted separately
+    pub fn set_scenario_run(&mut self, run: usize) {
+        self.scenario_run = run;
+    }
+
+    /// The most recent `file.feature:NN` seen in the output
+    pub fn last_location(&self) -> Option<&(String, u32)> {
+        self.last_location.as_ref()
+    }
+
+    /// Feed a Karate output line, returning a failure once its block is complete
+    pub fn push(&mut self, line: &str) -> Option<KarateFailure> {
+        let trimmed = line.trim();
+
+        if trimmed.starts_with(">>> failed features:") {
+            self.in_recap = true;
+            return self.finish();
+        }
+        if self.in_recap {
+            if trimmed.starts_with("<<<") {
+                self.in_recap = false;
+            }
+            return None;
+        }
+
+        let location = extract_feature_location(trimmed);
+        if let Some(block) = self.block.as_mut() {
+            // Karate closes a block by repeating its location
+            if location.is_some() && location == block.location {
+                block.confirmed = true;
+                return self.finish();
+            }
+            let starts_reason = is_failure_reason(trimmed)
+                && block.lines.iter().any(|l| is_failure_reason(l));
+            let ends_block = location.is_some()
+                || starts_reason
+                || is_log_line(trimmed)
+                || trimmed.starts_with("---")
+                || trimmed.starts_with("feature:")
+                || parse_karate_summary(trimmed).is_some()
+                || block.lines.len() >= Self::MAX_LINES;
+            if !ends_block {
+                block.lines.push(trimmed.to_string());
+                return None;
+            }
+            let failure = self.finish();
+            self.start(trimmed, location);
+            return failure;
+        }
+
+        self.start(trimmed, location);
+        None
+    }
+
+    /// Parse whatever block is buffered (call when the stream ends)
+    pub fn finish(&mut self) -> Option<KarateFailure> {
+        let block = self.block.take()?;
+        let scenario_run = block.scenario_run;
+        let failure = parse_failure_block(block)?;
+        let key = (
+            scenario_run,
+            failure.feature_file.clone(),
+            failure.line_number,
+            failure.assertion.clone(),
+            failure.actual.clone(),
+        );
+        self.seen.insert(key).then_some(failure)
+    }
+
+    fn start(&mut self, line: &str, location: Option<(String, u32)>) {
+        if let Some(location) = location {
+            self.last_location = Some(location.clone());
+            self.block = Some(FailureBlock {
+                scenario_run: self.scenario_run,
+                location: Some(location),
+                confirmed: line.contains("ERROR"),
+                lines: Vec::new(),
+            });
+        } else if is_failure_reason(line) {
+            self.block = Some(FailureBlock {
+                scenario_run: self.scenario_run,
+                location: self.last_location.clone(),
+                confirmed: true,
+                lines: vec![line.to_string()],
+            });
+        }
+    }
+}
+
+/// Lines that state why a step failed
+fn is_failure_reason(line: &str) -> bool {
+    (line.contains("match failed:") && !line.starts_with('$'))
+        || line.starts_with("status code was:")
+        || line.starts_with("js failed:")
+        || line.starts_with("assertion failed:")
+}
+
+/// A new Karate log record, e.g. `12:00:01.123 [main] INFO  com.intuit.karate - ...`
+pub fn is_log_line(line: &str) -> bool {
+    let bytes = line.as_bytes();
+    bytes.len() > 12
+        && bytes[..8].iter().enumerate().all(|(i, b)| if i % 3 == 2 { *b == b':' } else { b.is_ascii_digit() })
+        && bytes[8] == b'.'
+}
+
+fn is_step(line: &str) -> bool {
+    ["Given ", "When ", "Then ", "And ", "But ", "* "]
+        .iter()
+        .any(|keyword| line.starts_with(keyword))
+}
+
+/// Build a failure record from a block's lines
+fn parse_failure_block(block: FailureBlock) -> Option<KarateFailure> {
+    let lines = &block.lines;
+    let step = lines.iter().find(|l| is_step(l)).cloned();
+    let reason_idx = lines.iter().position(|l| is_failure_reason(l)).or_else(|| {
+        // An unrecognised reason after a confirmed ERROR: the first line that is not the step
+        block
+            .confirmed
+            .then(|| lines.iter().position(|l| !l.is_empty() && !is_step(l)))
+            .flatten()
+    })?;
+    let reason = &lines[reason_idx];
+    let (feature_file, line_number) = block.location.unwrap_or_else(|| ("unknown".to_string(), 0));
+
+    let mut failure = KarateFailure {
+        feature_file,
+        line_number,
+        assertion: reason.clone(),
+        url: lines.iter().find_map(|l| extract_failure_url(l)),
+        step,
+        expected: None,
+        actual: None,
+        response: None,
+    };
+
+    if reason.contains("match failed:") {
+        if let Some(matched) =
+            parse_single_line_match(reason).or_else(|| parse_match_block(&lines[reason_idx..]))
+        {
+            failure.assertion = matched.assertion;
+            failure.expected = Some(matched.expected);
+            failure.actual = Some(matched.actual);
+        }
+    } else if let Some((actual, expected)) = parse_status_failure(reason) {
+        failure.actual = Some(actual);
+        failure.expected = Some(expected);
+    }
+
+    // `response:` ends the status line; the body follows on its own lines
+    if let Some((_, inline)) = reason.split_once("response:") {
+        let body: Vec<&str> = std::iter::once(inline.trim())
+            .chain(lines[reason_idx + 1..].iter().map(String::as_str))
+            .filter(|l| !l.is_empty())
+            .collect();
+        failure.response = (!body.is_empty()).then(|| body.join("\n"));
+        failure.assertion = reason
+            .split(", url:")
+            .next()
+            .unwrap_or(reason)
+            .trim_end_matches(',')
+            .to_string();
+    }
+
+    Some(failure)
+}
+
+/// Actual and expected status from a status failure
+/// Example: "status code was: 200, expected: 400, ..." -> ("200", "400")
+fn parse_status_failure(line: &str) -> Option<(String, String)> {
+    static STATUS: LazyLock<Regex> = LazyLock::new(|| {
+        Regex::new(r"status code was:\s*(\d+),\s*expected:\s*(\d+)").expect("valid status failure regex")
+    });
+    let caps = STATUS.captures(line)?;
+    Some((caps.get(1)?.as_str().to_string(), caps.get(2)?.as_str().to_string()))
+}
+
+/// Parse the buffered lines of a multi-line match failure
+fn parse_match_block(lines: &[String]) -> Option<MatchFailure> {
+    let assertion = lines.first()?.clone();
+    let reason_idx = lines.iter().position(|l| l.starts_with('$') && l.contains(" | "))?;
+    let mut values = lines[reason_idx + 1..].iter().filter(|l| !l.is_empty());
+    let actual = values.next()?.clone();
+    let expected = values.next()?.clone();
+
+    Some(MatchFailure {
+        assertion,
+        expected,
+        actual,
+    })
+}
+
+/// Parse the single-line match failure format of older Karate versions
+/// Example: "match failed: path: $.b, actual: 2, expected: 3, reason: not equal"
+fn parse_single_line_match(line: &str) -> Option<MatchFailure> {
+    static MATCH: LazyLock<Regex> = LazyLock::new(|| {
+        Regex::new(r"actual:\s*(.*?),\s*expected:\s*(.*?),\s*reason:").expect("valid match failure regex")
+    });
+    let caps = MATCH.captures(line)?;
+    Some(MatchFailure {
+        assertion: line.to_string(),
+        actual: caps.get(1)?.as_str().to_string(),
+        expected: caps.get(2)?.as_str().to_string(),
+    })
+}
+
 /// Extract path and query from full URL
 /// Example: "http://localhost:1323/api/v1/karte/outcome?patientID=1" -> "/api/v1/karte/outcome?patientID=1"
 pub fn extract_path_query(url: &str) -> Option<String> {
@@ -230,6 +522,16 @@ mod tests {
         assert_eq!(entry.request_id.as_deref(), Some("abc123"));
     }
 
+    #[test]
+    fn test_level_aliases() {
+        assert_eq!(LogLevel::from_str("trace"), LogLevel::Debug);
+        assert_eq!(LogLevel::from_str("warning"), LogLevel::Warn);
+        for level in ["fatal", "PANIC", "dpanic", "CRITICAL"] {
+            assert_eq!(LogLevel::from_str(level), LogLevel::Error, "{}", level);
+        }
+        assert_eq!(LogLevel::from_str("notice"), LogLevel::Info);
+    }
+
     #[test]
     fn test_parse_karate_summary() {
         let line = "scenarios:  2 | passed:  1 | failed:  1 | time: 0.4675";
@@ -237,6 +539,7 @@ mod tests {
         assert_eq!(result.total_scenarios, 2);
         assert_eq!(result.passed, 1);
         assert_eq!(result.failed, 1);
+        assert_eq!(result.time_secs, Some(0.4675));
     }
 
     #[test]
@@ -246,10 +549,108 @@ mod tests {
         assert_eq!(url, "http://localhost:1323/api/v1/karte/outcome?patientID=1");
     }
 
+    #[test]
+    fn test_match_failure_block() {
+        let output = "12:00:01.123 [main] ERROR com.intuit.karate - classpath:tests/karte.feature:12
+And match response == { a: 1, b: 3 }
+match failed: EQUALS
+  $ | not equal | match failed for name: 'b' (MAP:MAP)
+  {\"a\":1,\"b\":2}
+  {\"a\":1,\"b\":3}
+
+    $.b | not equal (NUMBER:NUMBER)
+    2
+    3
+
+
+classpath:tests/karte.feature:12";
+        let mut parser = FailureParser::new();
+        let failures: Vec<_> = output.lines().filter_map(|l| parser.push(l)).collect();
+
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].feature_file, "classpath:tests/karte.feature");
+        assert_eq!(failures[0].line_number, 12);
+        assert_eq!(failures[0].step.as_deref(), Some("And match response == { a: 1, b: 3 }"));
+        assert_eq!(failures[0].assertion, "match failed: EQUALS");
+        assert_eq!(failures[0].actual.as_deref(), Some(r#"{"a":1,"b":2}"#));
+        assert_eq!(failures[0].expected.as_deref(), Some(r#"{"a":1,"b":3}"#));
+        assert!(parser.finish().is_none());
+    }
+
+    #[test]
+    fn test_status_failure_with_response_and_recap() {
+        let output = "12:00:02.000 [main] ERROR com.intuit.karate - classpath:tests/karte.feature:40
+Then status 400
+status code was: 200, expected: 400, response time in milliseconds: 6, url: http://localhost:1323/api/v1/karte/outcome?patientID=1, response:
+{\"id\":1}
+classpath:tests/karte.feature:40
+---------------------------------------------------------
+>>> failed features:
+status code was: 200, expected: 400, response time in milliseconds: 6, url: http://localhost:1323/api/v1/karte/outcome?patientID=1, response:
+classpath:tests/karte.feature:40
+<<<";
+        let mut parser = FailureParser::new();
+        let failures: Vec<_> = output.lines().filter_map(|l| parser.push(l)).collect();
+        assert!(parser.finish().is_none());
+
+        assert_eq!(failures.len(), 1);
+        let failure = &failures[0];
+        assert_eq!(failure.line_number, 40);
+        assert_eq!(failure.assertion, "status code was: 200, expected: 400, response time in milliseconds: 6");
+        assert_eq!(failure.url.as_deref(), Some("http://localhost:1323/api/v1/karte/outcome?patientID=1"));
+        assert_eq!((failure.actual.as_deref(), failure.expected.as_deref()), (Some("200"), Some("400")));
+        assert_eq!(failure.response.as_deref(), Some(r#"{"id":1}"#));
+    }
+
+    #[test]
+    fn test_single_line_match_failure() {
+        let mut parser = FailureParser::new();
+        assert!(parser.push("classpath:tests/a.feature:3").is_none());
+        assert!(parser
+            .push("match failed: path: $.b, actual: 2, expected: 3, reason: not equal")
+            .is_none());
+        let failure = parser.finish().unwrap();
+        assert_eq!(failure.actual.as_deref(), Some("2"));
+        assert_eq!(failure.expected.as_deref(), Some("3"));
+        assert_eq!(failure.line_number, 3);
+    }
+
+    #[test]
+    fn test_outline_rows_failing_alike_are_kept_apart() {
+        let block = ["classpath:tests/a.feature:3", "status code was: 500, expected: 200"];
+        let mut parser = FailureParser::new();
+        let mut failures = 0;
+        for run in [1, 1, 2] {
+            parser.set_scenario_run(run);
+            failures += block.iter().filter_map(|l| parser.push(l)).count();
+            failures += parser.finish().into_iter().count();
+        }
+        // The repeat within run 1 is dropped, the same failure in run 2 is not
+        assert_eq!(failures, 2);
+    }
+
     #[test]
     fn test_extract_path_query() {
         let url = "http://localhost:1323/api/v1/karte/outcome?patientID=1";
         let path = extract_path_query(url).unwrap();
         assert_eq!(path, "/api/v1/karte/outcome?patientID=1");
     }
+
+    #[test]
+    fn test_parse_go_duration() {
+        let ms = |text| parse_go_duration(text).map(|d| d.as_nanos() as f64 / 1e6);
+        assert_eq!(ms("1.235ms"), Some(1.235));
+        assert_eq!(ms("1.2s"), Some(1200.0));
+        assert_eq!(ms("850µs"), Some(0.85));
+        assert_eq!(ms("1m2.5s"), Some(62_500.0));
+        assert_eq!(ms("1h"), Some(3_600_000.0));
+        assert_eq!(ms("0"), Some(0.0));
+        for invalid in ["", "12", "-1s", "1.2 s", "ms", "1x"] {
+            assert_eq!(ms(invalid), None, "{}", invalid);
+        }
+
+        let entry = ApiLogEntry::parse(r#"{"msg":"REQUEST","latency_human":"1.5s","elapsed":"soon"}"#).unwrap();
+        assert_eq!(entry.latency, Some(Duration::from_millis(1500)));
+        assert_eq!((entry.elapsed_duration, entry.elapsed_ms()), (None, None));
+    }
 }
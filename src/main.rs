@@ -5,32 +5,68 @@
 //! and log persistence.
 
 mod analysis;
+mod body;
+mod classify;
 mod config;
+mod console;
+mod context;
 mod correlation;
+mod cucumber;
+mod dashboard;
+mod dedup;
+mod diff;
 mod export;
 mod filter;
+mod filter_expr;
 mod formatter;
+mod gherkin;
+mod init;
+mod karate_http;
 mod log_parser;
 mod process;
+mod progress;
+mod redact;
+mod scenarios;
+mod schema;
+mod sql_format;
+mod stacktrace;
+mod theme;
+mod validate;
 
-use clap::Parser;
-use colored::Colorize;
+use clap::{Parser, Subcommand};
 use std::path::PathBuf;
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
 use config::Config;
+use console::Console;
 use correlation::RequestCorrelator;
 use process::ProcessManager;
+use theme::Theme;
 
 /// Karate E2E Test Monitor
 #[derive(Parser, Debug)]
 #[command(name = "karate-monitor")]
 #[command(version, about = "Monitor and analyze Karate E2E tests")]
 struct Args {
-    /// Path to configuration file (TOML or JSON)
-    #[arg(short, long, default_value = "/app/karate-monitor.toml")]
-    config: PathBuf,
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Path to configuration file (TOML, YAML or JSON); searched for when not given
+    #[arg(short, long, global = true)]
+    config: Option<PathBuf>,
+
+    /// Config profile to overlay on the base settings (or KARATE_MONITOR_PROFILE)
+    #[arg(short, long, global = true)]
+    profile: Option<String>,
+
+    /// Run only scenarios whose tags match, e.g. '@smoke and not @slow' (Karate's ~@ignore also works)
+    #[arg(long, global = true, value_name = "EXPR")]
+    tags: Option<String>,
+
+    /// Run only scenarios whose name matches this regex
+    #[arg(long, global = true, value_name = "REGEX")]
+    name: Option<String>,
 
     /// Log level filter (overrides config): DEBUG, INFO, WARN, ERROR, ALL
     #[arg(short, long)]
@@ -44,10 +80,46 @@ struct Args {
     #[arg(long)]
     exclude: Vec<String>,
 
+    /// Filter expression over API log fields, e.g. 'status >= 400 && uri ~ "/karte"'
+    #[arg(long)]
+    filter: Option<String>,
+
+    /// Show N filtered-out API entries around each shown entry (like grep -C)
+    #[arg(short = 'C', long, value_name = "N")]
+    context: Option<usize>,
+
+    /// Show N filtered-out API entries before each shown entry
+    #[arg(short = 'B', long, value_name = "N")]
+    before: Option<usize>,
+
+    /// Show N filtered-out API entries after each shown entry
+    #[arg(short = 'A', long, value_name = "N")]
+    after: Option<usize>,
+
+    /// Only use entries with the same request_id as context
+    #[arg(long)]
+    context_same_request: bool,
+
+    /// Collapse consecutive near-identical API entries into one line with a counter
+    #[arg(long)]
+    dedup: bool,
+
+    /// Show at most N API entries with the same message per window
+    #[arg(long, value_name = "N")]
+    rate_limit: Option<usize>,
+
     /// Disable colors (for CI environments)
     #[arg(long)]
     no_color: bool,
 
+    /// Display theme (overrides config)
+    #[arg(long, value_parser = clap::builder::PossibleValuesParser::new(theme::THEME_NAMES))]
+    theme: Option<String>,
+
+    /// Show a full-screen live dashboard instead of streaming logs
+    #[arg(long)]
+    tui: bool,
+
     /// Export logs to file
     #[arg(long)]
     export: Option<PathBuf>,
@@ -73,6 +145,37 @@ struct Args {
     tests: Vec<String>,
 }
 
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Inspect the configuration
+    Config {
+        #[command(subcommand)]
+        action: ConfigCommand,
+    },
+    /// List features and scenarios under the test paths (honours --tags and --name)
+    List {
+        /// Test paths to scan (defaults to karate.default_test_path)
+        tests: Vec<String>,
+    },
+    /// Write a commented starter config with all defaults
+    Init {
+        /// Where to write it
+        #[arg(default_value = "karate-monitor.toml")]
+        path: PathBuf,
+        /// Overwrite an existing file
+        #[arg(long)]
+        force: bool,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum ConfigCommand {
+    /// Validate the configuration (with CLI overrides) and exit
+    Check,
+    /// Print the effective configuration after profile and CLI overrides
+    Show,
+}
+
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let args = Args::parse();
@@ -82,16 +185,78 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         colored::control::set_override(false);
     }
 
-    // Load configuration
-    let mut config = if args.config.exists() {
-        Config::load(&args.config)?
-    } else {
-        eprintln!(
-            "{} Config file not found at {:?}, using defaults",
-            "⚠️".yellow(),
-            args.config
+    // Precedence: defaults < file < profile < environment < CLI
+    let profile = args
+        .profile
+        .clone()
+        .or_else(|| std::env::var(config::PROFILE_ENV).ok().filter(|p| !p.is_empty()));
+
+    let startup_theme = args
+        .theme
+        .as_deref()
+        .and_then(Theme::by_name)
+        .unwrap_or_default();
+    let startup_error = |message: String| -> ! {
+        eprintln!("{} {}", startup_theme.palette.error.paint(&startup_theme.prefixes.error), message);
+        std::process::exit(2);
+    };
+
+    if let Some(Command::Init { path, force }) = &args.command {
+        if path.exists() && !force {
+            startup_error(format!("{} already exists (use --force to overwrite)", path.display()));
+        }
+        // The starter is commented TOML; YAML and JSON files can include it
+        if !matches!(config::ConfigFormat::of(path), Ok(config::ConfigFormat::Toml)) {
+            startup_error(format!(
+                "{}: init writes TOML, use a .toml path (YAML and JSON configs can include it)",
+                path.display()
+            ));
+        }
+        std::fs::write(path, init::starter_config())?;
+        println!(
+            "{} Wrote starter config to {}",
+            startup_theme.palette.success.paint(&startup_theme.prefixes.success),
+            path.display()
         );
-        Config::default()
+        return Ok(());
+    }
+
+    // An explicit --config must exist; otherwise search the usual places
+    let config_path = match &args.config {
+        Some(path) if !path.exists() => startup_error(format!("Config file {:?} not found", path)),
+        Some(path) => Some(path.clone()),
+        None => {
+            let xdg_config_home = std::env::var_os("XDG_CONFIG_HOME")
+                .filter(|dir| !dir.is_empty())
+                .map(PathBuf::from)
+                .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")));
+            config::discover(&std::env::current_dir()?, xdg_config_home.as_deref())
+        }
+    };
+
+    // Load configuration, keeping the text so problems can point at lines
+    let source = config_path.as_ref().map(std::fs::read_to_string).transpose()?;
+    let mut config = match &config_path {
+        Some(path) => match Config::load(path, profile.as_deref()) {
+            Ok(config) => config,
+            Err(e) => startup_error(e.to_string()),
+        },
+        None => {
+            if let Some(profile) = &profile {
+                startup_error(format!("Profile \"{}\" requested but no config file was found", profile));
+            }
+            eprintln!(
+                "{} No config file found (current directory, repository root, $XDG_CONFIG_HOME/karate-monitor, {}), using defaults",
+                startup_theme.palette.warn.paint(&startup_theme.prefixes.warn),
+                config::CONTAINER_CONFIG_PATH
+            );
+            Config::default()
+        }
+    };
+
+    let env_overrides = match config.apply_env_overrides(std::env::vars()) {
+        Ok(keys) => keys,
+        Err(e) => startup_error(e.to_string()),
     };
 
     // Apply CLI overrides
@@ -104,6 +269,24 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     if !args.exclude.is_empty() {
         config.logging.exclude_patterns = args.exclude.clone();
     }
+    if let Some(filter) = &args.filter {
+        config.logging.filter = Some(filter.clone());
+    }
+    if let Some(n) = args.before.or(args.context) {
+        config.logging.context_before = n;
+    }
+    if let Some(n) = args.after.or(args.context) {
+        config.logging.context_after = n;
+    }
+    if args.context_same_request {
+        config.logging.context_same_request = true;
+    }
+    if args.dedup {
+        config.logging.dedup = true;
+    }
+    if let Some(n) = args.rate_limit {
+        config.logging.rate_limit = n;
+    }
     if args.sql_stats {
         config.analysis.show_sql_stats = true;
     }
@@ -119,54 +302,225 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     if let Some(export_path) = &args.export {
         config.logging.export_path = export_path.to_string_lossy().to_string();
     }
+    if let Some(theme) = &args.theme {
+        config.display.theme = theme.clone();
+    }
+    if args.tui {
+        config.display.tui = true;
+    }
+
+    let theme = Theme::from_config(&config.display);
+
+    // Refuse to run with an invalid configuration
+    let mut issues = validate::validate_config(&config, source.as_deref(), profile.as_deref());
+    if let Some(raw) = config_path.as_deref().and_then(|path| config::load_raw(path).ok()) {
+        issues.extend(validate::unknown_keys(&raw, source.as_deref()));
+    }
+    validate::mark_env_overrides(&mut issues, &env_overrides);
+    let mut origin = match (&config_path, &profile) {
+        (Some(path), Some(profile)) => format!("{}, profile {}", path.display(), profile),
+        (Some(path), None) => path.display().to_string(),
+        (None, _) => "defaults and CLI options".to_string(),
+    };
+    if !env_overrides.is_empty() {
+        origin.push_str(&format!(", {} environment override(s)", env_overrides.len()));
+    }
+    if let Some(Command::Config {
+        action: ConfigCommand::Show,
+    }) = &args.command
+    {
+        println!("# Effective configuration ({})", origin);
+        print!("{}", toml::to_string_pretty(&config)?);
+        if !issues.is_empty() {
+            validate::print_report(&issues, &origin, &theme);
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+    if let Some(Command::Config {
+        action: ConfigCommand::Check,
+    }) = &args.command
+    {
+        if issues.is_empty() {
+            println!(
+                "{} Configuration OK ({})",
+                theme.palette.success.paint(&theme.prefixes.success),
+                origin
+            );
+            return Ok(());
+        }
+        validate::print_report(&issues, &origin, &theme);
+        std::process::exit(1);
+    }
+
+    let selection = gherkin::Selection {
+        name: args.name.as_deref().map(|name| {
+            regex::Regex::new(name).unwrap_or_else(|e| startup_error(format!("Invalid --name regex: {}", e)))
+        }),
+        tags: args.tags.as_deref().map(|tags| {
+            gherkin::TagExpr::parse(tags)
+                .unwrap_or_else(|e| startup_error(format!("Invalid --tags expression: {}", e)))
+        }),
+    };
+    // Test paths resolve like Karate's, which runs in its working directory
+    let feature_base = std::path::Path::new(process::KARATE_WORKDIR);
+    if let Some(Command::List { tests }) = &args.command {
+        let tests = if tests.is_empty() {
+            vec![config.karate.default_test_path.clone()]
+        } else {
+            tests.clone()
+        };
+        let (features, problems) = gherkin::load_features(&tests, feature_base);
+        for problem in &problems {
+            eprintln!("{} {}", theme.palette.warn.paint(&theme.prefixes.warn), problem);
+        }
+        gherkin::print_features(&features, &selection, feature_base, &theme);
+        return Ok(());
+    }
+
+    if !issues.is_empty() {
+        validate::print_report(&issues, &origin, &theme);
+        std::process::exit(2);
+    }
 
     // Determine test paths
-    let test_paths: Vec<String> = if args.tests.is_empty() {
+    let mut test_paths: Vec<String> = if args.tests.is_empty() {
         vec![config.karate.default_test_path.clone()]
     } else {
         args.tests.clone()
     };
 
-    println!("{}", "═".repeat(60).bright_blue());
-    println!(
-        "{} {}",
-        "🥋".bright_yellow(),
-        "Karate Monitor v0.1.0".bright_white().bold()
-    );
-    println!("{}", "═".repeat(60).bright_blue());
-    println!();
+    // Narrow the run to the selected scenarios before Karate starts
+    let mut selected = None;
+    if !selection.is_empty() {
+        let (features, problems) = gherkin::load_features(&test_paths, feature_base);
+        if !problems.is_empty() {
+            startup_error(format!("Cannot select scenarios: {}", problems.join("; ")));
+        }
+        let args = gherkin::selected_test_args(&features, &selection, feature_base);
+        if args.is_empty() {
+            eprintln!(
+                "{} No scenarios match the selection",
+                theme.palette.warn.paint(&theme.prefixes.warn)
+            );
+            std::process::exit(1);
+        }
+        let line_count: usize = features.iter().map(|f| selection.select_lines(f).len()).sum();
+        selected = Some(format!("Selected {} scenario line(s) in {} feature(s)", line_count, args.len()));
+        test_paths = args;
+    }
+
+    if !config.display.tui {
+        println!("{}", theme.palette.frame.paint(&theme.heavy_rule(60)));
+        println!(
+            "{}",
+            Theme::prefixed(
+                &theme.palette.highlight.paint(&theme.prefixes.banner).to_string(),
+                &theme
+                    .palette
+                    .text_strong
+                    .paint("Karate Monitor v0.1.0")
+                    .to_string()
+            )
+        );
+        if !env_overrides.is_empty() {
+            println!(
+                "{}",
+                Theme::prefixed(
+                    &theme.palette.info.paint(&theme.prefixes.info).to_string(),
+                    &format!("Environment overrides: {}", env_overrides.join(", ")),
+                )
+            );
+        }
+        if let Some(selected) = &selected {
+            println!(
+                "{}",
+                Theme::prefixed(&theme.palette.info.paint(&theme.prefixes.info).to_string(), selected)
+            );
+        }
+        println!("{}", theme.palette.frame.paint(&theme.heavy_rule(60)));
+        println!();
+    }
 
     // Create shared state
+    let classifier = Arc::new(classify::Classifier::new(&config.classify));
+    let filter_exprs = filter::FilterExprs::parse(&config.logging, &config.filters)
+        .unwrap_or_else(|e| startup_error(format!("Invalid filter expression in {}", e)));
     let correlator = Arc::new(Mutex::new(RequestCorrelator::new()));
     let sql_stats = Arc::new(Mutex::new(analysis::SqlStats::new()));
     let test_summary = Arc::new(Mutex::new(analysis::TestSummary::new()));
 
+    // The dashboard draws its own colours; process output must stay plain text
+    let dashboard = if config.display.tui {
+        colored::control::set_override(false);
+        Some(dashboard::spawn(
+            &config.logging.level,
+            theme.clone(),
+            classifier.clone(),
+            sql_stats.clone(),
+            test_summary.clone(),
+        ))
+    } else {
+        None
+    };
+    let console = match &dashboard {
+        Some((tx, _, _)) => Console::Dashboard(tx.clone()),
+        None => Console::terminal(),
+    };
+    let (dashboard, abort) = match dashboard {
+        Some((tx, abort, handle)) => (Some((tx, handle)), Some(abort)),
+        None => (None, None),
+    };
+
     // Create process manager
     let mut process_manager = ProcessManager::new(
         config.clone(),
         correlator.clone(),
         sql_stats.clone(),
         test_summary.clone(),
-    );
+        &filter_exprs,
+        classifier,
+        console,
+    )
+    .unwrap_or_else(|e| startup_error(format!("Invalid setting {}", e)))
+    .with_abort(abort);
 
     // Run the test suite
     let exit_code = process_manager.run(&test_paths).await?;
 
+    // Keep the dashboard open until the user closes it
+    if let Some((tx, handle)) = dashboard {
+        let _ = tx.send(dashboard::DashboardEvent::Finished(exit_code));
+        drop(tx);
+        drop(process_manager);
+        match tokio::task::spawn_blocking(move || handle.join()).await? {
+            // Aborted: the processes are stopped and the export is written
+            Ok(Ok(false)) => std::process::exit(130),
+            Ok(_) => {}
+            Err(e) => eprintln!("Dashboard thread panicked: {:?}", e),
+        }
+    }
+
     // Print summaries
     println!();
-    println!("{}", "═".repeat(60).bright_blue());
+    println!("{}", theme.palette.frame.paint(&theme.heavy_rule(60)));
 
     if config.analysis.show_sql_stats {
         let stats = sql_stats.lock().await;
-        stats.print_summary();
+        stats.print_summary(&theme);
     }
 
     if config.analysis.show_test_summary {
         let summary = test_summary.lock().await;
-        summary.print_summary();
+        summary.print_summary(&theme);
+        summary.model.print_report(
+            &theme,
+            config.analysis.feature_breakdown,
+            config.analysis.slowest_scenarios,
+        );
     }
 
-    println!("{}", "═".repeat(60).bright_blue());
+    println!("{}", theme.palette.frame.paint(&theme.heavy_rule(60)));
 
     std::process::exit(exit_code);
 }
@@ -1,21 +1,40 @@
 //! Process management for API and Karate test execution
 
 use crate::analysis::{SqlStats, TestSummary};
+use crate::classify::Classifier;
 use crate::config::Config;
+use crate::console::Console;
+use crate::context::{ContextBuffer, ContextLine};
 use crate::correlation::RequestCorrelator;
+use crate::cucumber::{CucumberReport, ReportWatcher};
+use crate::dashboard::{DashboardEvent, DashboardFailure};
+use crate::dedup::{DedupEvent, Deduplicator};
 use crate::export::{ExportFormat, LogExporter};
-use crate::filter::LogFilter;
+use crate::filter::{FilterExprs, SourceFilters};
 use crate::formatter::LogFormatter;
+use crate::gherkin;
+use crate::karate_http::{CallLog, HttpExchange, HttpLogParser, LogTail};
 use crate::log_parser::{
-    extract_failure_url, parse_karate_line, ApiLogEntry, LogType,
+    extract_failure_url, parse_karate_summary, ApiLogEntry, FailureParser,
+    KarateFailure, LogType,
 };
-use colored::Colorize;
+use crate::progress::{discover_features, ProgressTracker};
+use crate::redact::Redactor;
+use crate::schema::ApiSchema;
+use crate::stacktrace::{Folded, StackTrace, StackTraceFolder};
+use crate::theme::Theme;
+use std::io::IsTerminal;
+use std::path::Path;
 use std::sync::Arc;
+use std::time::Instant;
 use tokio::io::{AsyncBufReadExt, BufReader};
 use tokio::process::{Child, Command};
-use tokio::sync::Mutex;
+use tokio::sync::{oneshot, Mutex};
 use tokio::time::{sleep, Duration};
 
+/// Directory Karate runs in; relative test paths are resolved against it
+pub const KARATE_WORKDIR: &str = "/app";
+
 /// Manages API and Karate test processes
 pub struct ProcessManager {
     config: Config,
@@ -23,23 +42,34 @@ pub struct ProcessManager {
     sql_stats: Arc<Mutex<SqlStats>>,
     test_summary: Arc<Mutex<TestSummary>>,
     formatter: LogFormatter,
-    filter: LogFilter,
-    exporter: Option<LogExporter>,
+    filters: Arc<SourceFilters>,
+    redactor: Arc<Redactor>,
+    schema: Arc<ApiSchema>,
+    classifier: Arc<Classifier>,
+    exporter: Arc<Mutex<Option<LogExporter>>>,
+    console: Console,
+    /// Fires when the dashboard user aborts the run
+    abort: Option<oneshot::Receiver<()>>,
 }
 
 impl ProcessManager {
+    /// Fails, naming the setting, when a filter or redaction pattern is invalid
     pub fn new(
         config: Config,
         correlator: Arc<Mutex<RequestCorrelator>>,
         sql_stats: Arc<Mutex<SqlStats>>,
         test_summary: Arc<Mutex<TestSummary>>,
-    ) -> Self {
-        let formatter = LogFormatter::new(config.display.clone());
-        let filter = LogFilter::new(
-            &config.logging.level,
-            &config.logging.include_patterns,
-            &config.logging.exclude_patterns,
-        );
+        filter_exprs: &FilterExprs,
+        classifier: Arc<Classifier>,
+        console: Console,
+    ) -> Result<Self, String> {
+        let formatter = LogFormatter::new(config.display.clone(), classifier.clone());
+        // The dashboard applies the general level itself so it can be changed live
+        let mut logging = config.logging.clone();
+        if console.is_dashboard() {
+            logging.level = "ALL".to_string();
+        }
+        let filters = Arc::new(SourceFilters::new(&logging, &config.filters, filter_exprs, classifier.clone())?);
 
         let exporter = LogExporter::new(
             &config.logging.export_path,
@@ -47,95 +77,184 @@ impl ProcessManager {
         )
         .ok()
         .flatten();
+        let redactor = Arc::new(
+            Redactor::new(&config.redaction).map_err(|e| format!("redaction.patterns: {}", e))?,
+        );
+        let schema = Arc::new(ApiSchema::new(&config.schema));
 
-        Self {
+        Ok(Self {
             config,
             correlator,
             sql_stats,
             test_summary,
             formatter,
-            filter,
-            exporter,
-        }
+            filters,
+            redactor,
+            schema,
+            classifier,
+            exporter: Arc::new(Mutex::new(exporter)),
+            console,
+            abort: None,
+        })
+    }
+
+    /// Stop the run, killing the API and Karate, when `abort` fires
+    pub fn with_abort(mut self, abort: Option<oneshot::Receiver<()>>) -> Self {
+        self.abort = abort;
+        self
     }
 
     /// Run the full test suite
     pub async fn run(&mut self, test_paths: &[String]) -> Result<i32, Box<dyn std::error::Error>> {
+        let theme = self.formatter.theme().clone();
+        let palette = &theme.palette;
+        let console = self.console.clone();
+
         // Start the API server
-        println!(
+        console.line(format!(
             "{} Starting API server: {}",
-            "🚀".bright_green(),
-            self.config.api.command.bright_yellow()
-        );
+            palette.success_strong.paint(&theme.prefixes.start),
+            palette.highlight.paint(&self.config.api.command)
+        ));
 
         let mut api_process = self.start_api().await?;
 
         // Set up signal handling for cleanup
         let api_pid = api_process.id();
+        let abort = self.abort.take();
+        let aborted = async move {
+            if let Some(abort) = abort {
+                if abort.await.is_ok() {
+                    return;
+                }
+            }
+            // No dashboard, or it closed without aborting
+            std::future::pending::<()>().await
+        };
+        tokio::pin!(aborted);
 
         // Wait for API to be ready
-        if !self.wait_for_api().await {
-            eprintln!("{} API failed to start", "❌".red());
+        let ready = tokio::select! {
+            ready = self.wait_for_api() => ready,
+            _ = &mut aborted => {
+                let _ = api_process.kill().await;
+                return Ok(130);
+            }
+        };
+        if !ready {
+            console.error_line(format!("{} API failed to start", palette.error.paint(&theme.prefixes.error)));
             let _ = api_process.kill().await;
             return Ok(1);
         }
 
-        println!("{} API is ready", "✅".green());
-        println!();
+        console.line(format!("{} API is ready", palette.success.paint(&theme.prefixes.success)));
+        console.line("");
 
         // Start processing API logs in background
         let api_stdout = api_process.stdout.take();
         let api_stderr = api_process.stderr.take();
 
-        let correlator_clone = self.correlator.clone();
-        let sql_stats_clone = self.sql_stats.clone();
-        let config_clone = self.config.clone();
-        let formatter_clone = LogFormatter::new(self.config.display.clone());
-        let filter_clone = LogFilter::new(
-            &self.config.logging.level,
-            &self.config.logging.include_patterns,
-            &self.config.logging.exclude_patterns,
-        );
+        let traces = TraceReporter {
+            stdout: Arc::new(Mutex::new(StackTraceFolder::new())),
+            stderr: Arc::new(Mutex::new(StackTraceFolder::new())),
+            active_request: Arc::new(Mutex::new(None)),
+            correlator: self.correlator.clone(),
+            test_summary: self.test_summary.clone(),
+            exporter: self.exporter.clone(),
+            filters: self.filters.clone(),
+            formatter: Arc::new(LogFormatter::new(self.config.display.clone(), self.classifier.clone())),
+            console: console.clone(),
+            failed_only: self.config.analysis.failed_only,
+        };
 
-        // Spawn API stdout handler
-        let stdout_handle = if let Some(stdout) = api_stdout {
-            Some(tokio::spawn(async move {
-                process_api_output(
-                    stdout,
-                    correlator_clone,
-                    sql_stats_clone,
-                    &config_clone,
-                    formatter_clone,
-                    filter_clone,
+        let logging = &self.config.logging;
+        let api_handler = ApiOutputHandler {
+            // Context only applies to the streamed terminal output
+            context: (!console.is_dashboard()
+                && (logging.context_before > 0 || logging.context_after > 0))
+                .then(|| {
+                    ContextBuffer::new(
+                        logging.context_before,
+                        logging.context_after,
+                        logging.context_same_request,
+                    )
+                }),
+            dedup: (logging.dedup || logging.rate_limit > 0).then(|| {
+                Deduplicator::new(
+                    logging.dedup,
+                    logging.rate_limit,
+                    Duration::from_secs(logging.rate_limit_window_secs),
                 )
-                .await
-            }))
-        } else {
-            None
+            }),
+            export_dedup: (logging.dedup_export && (logging.dedup || logging.rate_limit > 0)).then(|| {
+                Deduplicator::new(
+                    logging.dedup,
+                    logging.rate_limit,
+                    Duration::from_secs(logging.rate_limit_window_secs),
+                )
+            }),
+            correlator: self.correlator.clone(),
+            sql_stats: self.sql_stats.clone(),
+            config: self.config.clone(),
+            formatter: LogFormatter::new(self.config.display.clone(), self.classifier.clone()),
+            filters: self.filters.clone(),
+            redactor: self.redactor.clone(),
+            schema: self.schema.clone(),
+            exporter: self.exporter.clone(),
+            console: console.clone(),
+            traces: traces.clone(),
         };
 
+        // Spawn API stdout handler
+        let stdout_handle =
+            api_stdout.map(|stdout| tokio::spawn(async move { api_handler.run(stdout).await }));
+
         // Spawn API stderr handler
-        let stderr_handle = if let Some(stderr) = api_stderr {
-            Some(tokio::spawn(async move {
+        let stderr_prefix = format!("{}{}", theme.prefixes.error, theme.prefixes.api);
+        let stderr_style = palette.error;
+        let stderr_redactor = self.redactor.clone();
+        let stderr_console = console.clone();
+        let stderr_traces = traces.clone();
+        let stderr_handle = api_stderr.map(|stderr| {
+            tokio::spawn(async move {
+                let traces = stderr_traces;
                 let mut reader = BufReader::new(stderr).lines();
-                while let Ok(Some(line)) = reader.next_line().await {
-                    eprintln!("{} {}", "❌🔷".red(), line.red());
+                // Panics and stack traces become one event instead of dozens of red lines
+                while let Some(line) = next_line_or_flush(&mut reader, &traces, &traces.stderr).await {
+                    let line = stderr_redactor.redact_line(&line);
+                    let folded = traces.stderr.lock().await.push(&line);
+                    for folded in folded {
+                        match folded {
+                            Folded::Line(line) => stderr_console.error_line(format!(
+                                "{} {}",
+                                stderr_style.paint(&stderr_prefix),
+                                stderr_style.paint(&line)
+                            )),
+                            Folded::Trace(trace) => traces.report(trace).await,
+                        }
+                    }
                 }
-            }))
-        } else {
-            None
+                traces.flush(&traces.stderr).await;
+            })
+        });
+
+        // Run Karate tests; on abort Karate is killed as its future is dropped
+        let exit_code = tokio::select! {
+            exit_code = self.run_karate(test_paths, &traces) => exit_code?,
+            _ = &mut aborted => {
+                console.line(format!("{} Aborted", palette.warn.paint(&theme.prefixes.warn)));
+                130
+            }
         };
 
-        // Run Karate tests
-        let exit_code = self.run_karate(test_paths).await?;
-
         // Clean up API process
-        println!();
-        println!(
-            "{} Stopping API (pid {:?})…",
-            "ℹ️".bright_blue(),
-            api_pid
-        );
+        console.line("");
+        console.line(format!(
+            "{} Stopping API (pid {:?}){}",
+            palette.info.paint(&theme.prefixes.info),
+            api_pid,
+            theme.borders.ellipsis
+        ));
         let _ = api_process.kill().await;
 
         // Wait for log handlers to finish
@@ -147,13 +266,30 @@ impl ProcessManager {
         }
 
         // Finalize export
-        if let Some(exporter) = self.exporter.take() {
+        if let Some(exporter) = self.exporter.lock().await.take() {
             let _ = exporter.finish();
         }
 
         Ok(exit_code)
     }
 
+    /// Show a match failure's expected-vs-actual diff and add the failure, with its
+    /// scenario's calls, to the summary
+    async fn report_failure(
+        &self,
+        failure: &KarateFailure,
+        calls: &[HttpExchange],
+        features: &[gherkin::Feature],
+        formatter: &LogFormatter,
+    ) {
+        if failure.assertion.contains("match failed") {
+            if let Some(diff) = formatter.format_match_diff(failure) {
+                self.console.line(diff);
+            }
+        }
+        self.test_summary.lock().await.track_failure(failure, calls, features);
+    }
+
     /// Start the API server process
     async fn start_api(&self) -> Result<Child, Box<dyn std::error::Error>> {
         let child = Command::new(&self.config.api.command)
@@ -168,17 +304,20 @@ impl ProcessManager {
     async fn wait_for_api(&self) -> bool {
         let timeout = self.config.api.health_timeout_secs;
         let interval = self.config.api.health_interval_secs;
+        let theme = self.formatter.theme();
+        let console = &self.console;
 
         for i in 1..=timeout {
             match reqwest_health_check(&self.config.api.health_url).await {
                 Ok(true) => return true,
                 _ => {
-                    println!(
-                        "{} waiting for API ({}/{})…",
-                        "⏳".bright_yellow(),
+                    console.line(format!(
+                        "{} waiting for API ({}/{}){}",
+                        theme.palette.highlight.paint(&theme.prefixes.wait),
                         i,
-                        timeout
-                    );
+                        timeout,
+                        theme.borders.ellipsis
+                    ));
                     sleep(Duration::from_secs(interval)).await;
                 }
             }
@@ -188,13 +327,23 @@ impl ProcessManager {
     }
 
     /// Run Karate tests
-    async fn run_karate(&mut self, test_paths: &[String]) -> Result<i32, Box<dyn std::error::Error>> {
+    async fn run_karate(
+        &mut self,
+        test_paths: &[String],
+        traces: &TraceReporter,
+    ) -> Result<i32, Box<dyn std::error::Error>> {
         // Build classpath
         let classpath = std::iter::once(self.config.karate.jar_path.clone())
             .chain(self.config.karate.classpath.iter().cloned())
             .collect::<Vec<_>>()
             .join(":");
 
+        let theme = self.formatter.theme().clone();
+        let palette = &theme.palette;
+        let console = self.console.clone();
+        let info = palette.info.paint(&theme.prefixes.info);
+        let warn = palette.warn.paint(&theme.prefixes.warn);
+
         // Build command
         let mut cmd = Command::new("java");
 
@@ -203,28 +352,28 @@ impl ProcessManager {
             // ZGC (Mutually exclusive with Compact Object Headers for now)
             // Strict 25+ requirement for ZGC
             if self.config.karate.use_zgc && version >= 25 {
-                println!("{} Enabling ZGC", "ℹ️".bright_blue());
+                console.line(format!("{} Enabling ZGC", info));
                 cmd.arg("-XX:+UseZGC");
 
                 if self.config.karate.use_compact_object_headers {
-                    println!("{} Compact Object Headers disabled because ZGC is enabled", "⚠️".yellow());
+                    console.line(format!("{} Compact Object Headers disabled because ZGC is enabled", warn));
                 }
             } else {
                 if self.config.karate.use_zgc && version < 25 {
-                    println!("{} ZGC requested but requires Java 25+ (detected: {}). Ignoring.", "⚠️".yellow(), version);
+                    console.line(format!("{} ZGC requested but requires Java 25+ (detected: {}). Ignoring.", warn, version));
                 }
 
                 // Compact Object Headers (Product in 25+) - Optional and Strict 25+
                 // Only enable if compatible (version >= 25)
                 if version >= 25 && self.config.karate.use_compact_object_headers {
-                    println!("{} Java version {} detected, enabling compact object headers", "ℹ️".bright_blue(), version);
+                    console.line(format!("{} Java version {} detected, enabling compact object headers", info, version));
                     cmd.arg("-XX:+UseCompactObjectHeaders");
                 }
             }
 
             // Native Access & Unsafe Suppression (Java 25+)
             if version > 24 {
-                println!("{} Java version {} detected, enabling native access and suppressing Unsafe warnings", "ℹ️".bright_blue(), version);
+                console.line(format!("{} Java version {} detected, enabling native access and suppressing Unsafe warnings", info, version));
                 cmd.arg("--enable-native-access=ALL-UNNAMED");
                 cmd.arg("--sun-misc-unsafe-memory-access=allow");
             }
@@ -246,17 +395,69 @@ impl ProcessManager {
 
         cmd.stdout(std::process::Stdio::piped())
             .stderr(std::process::Stdio::piped())
-            .current_dir("/app");
-
-        println!(
+            .current_dir(KARATE_WORKDIR)
+            .kill_on_drop(true);
+
+        // Karate writes each feature's cucumber report as it finishes, and its DEBUG log
+        // (with the HTTP calls the console may not show) to karate.log
+        let report_dir = Path::new(KARATE_WORKDIR).join(&self.config.karate.report_dir);
+        let mut karate_log = LogTail::new(&report_dir.join("karate.log"));
+
+        // Discover features up front so progress can show totals and an ETA
+        let progress_config = &self.config.progress;
+        let history_path = (!progress_config.history_path.is_empty())
+            .then(|| report_dir.join(&progress_config.history_path));
+        let progress = progress_config.enabled.then(|| {
+            let inventory = discover_features(test_paths, Path::new(KARATE_WORKDIR));
+            if inventory.is_complete() {
+                console.line(format!(
+                    "{} Discovered {} feature files ({} scenarios)",
+                    info,
+                    inventory.features.len(),
+                    inventory.total_scenarios()
+                ));
+            }
+            Arc::new(Mutex::new(ProgressTracker::new(
+                inventory,
+                history_path.as_deref().unwrap_or(Path::new("")),
+                self.config.karate.threads,
+            )))
+        });
+        let interactive = std::io::stdout().is_terminal();
+
+        console.line(format!(
             "{} Running Karate tests: {}",
-            "🥋".bright_cyan(),
-            test_paths.join(", ").bright_yellow()
-        );
-        println!();
+            palette.accent.paint(&theme.prefixes.banner),
+            palette.highlight.paint(&test_paths.join(", "))
+        ));
+        console.line("");
 
+        let started_at = std::time::SystemTime::now();
         let mut child = cmd.spawn()?;
 
+        let cucumber_json = self.config.karate.output_format.contains("cucumber:json");
+        let mut report_watcher = cucumber_json.then(|| ReportWatcher::new(&report_dir, started_at));
+
+        // Without a terminal, report progress as periodic plain status lines
+        let status_task = progress
+            .clone()
+            .filter(|_| !interactive && !console.is_dashboard())
+            .map(|tracker| {
+                let console = console.clone();
+                let interval = Duration::from_secs(progress_config.status_interval_secs.max(1));
+                tokio::spawn(async move {
+                    let mut ticker = tokio::time::interval(interval);
+                    ticker.tick().await;
+                    loop {
+                        ticker.tick().await;
+                        console.line(tracker.lock().await.status().render_plain());
+                    }
+                })
+            });
+
+        // Failures Karate only locates by file and line are named from the feature files
+        let (features, _) = gherkin::load_features(test_paths, Path::new(KARATE_WORKDIR));
+
         // Process Karate output
         let stdout = child.stdout.take();
         let stderr = child.stderr.take();
@@ -264,33 +465,54 @@ impl ProcessManager {
         let correlator_for_karate = self.correlator.clone();
         let test_summary_clone = self.test_summary.clone();
         let failed_only = self.config.analysis.failed_only;
-        let formatter = LogFormatter::new(self.config.display.clone());
+        // The dashboard shows failures and per-feature results, like failed-only mode
+        let compact_output = failed_only || console.is_dashboard();
+        let formatter = LogFormatter::new(self.config.display.clone(), self.classifier.clone());
 
         // Process stdout
         if let Some(stdout) = stdout {
             let mut reader = BufReader::new(stdout).lines();
             let mut pending_failure_url: Option<String> = None;
             let mut current_feature: Option<String> = None;
+            let mut failure_parser = FailureParser::new();
+            let mut http_parser = HttpLogParser::new();
+            let mut log_http_parser = HttpLogParser::new();
+            // Calls come from Karate's output or karate.log, whichever has them first
+            let mut call_source = None;
+            let mut calls = CallLog::default();
             
         // Buffer for batch logs to group them (raw_line, parsed_entry)
-            let mut batch_buffer: Vec<(String, Option<ApiLogEntry>)> = Vec::new();
+            let mut batch_buffer: Vec<(String, Option<ApiLogEntry>, Option<String>)> = Vec::new();
 
             while let Ok(Some(line)) = reader.next_line().await {
-                // Check if this is a batch log line
-                if line.contains("📦") {
+                // Mask secrets before the line is parsed, shown, exported or summarised
+                let line = self.redactor.redact_line(&line);
+
+                if let Some(exporter) = self.exporter.lock().await.as_mut() {
+                    let _ = exporter.write_karate_log(&line);
+                }
+
+                let class = self.classifier.karate(&line);
+
+                // Check if this is a batch log line (the marker is emitted by the API, not themed)
+                if class.log_type == LogType::KarateBatch {
                     // Try to extract and parse JSON part
                     let mut parsed_entry = None;
                     let mut log_content = line.clone();
 
-                    if let Some(start_idx) = line.find('{') {
-                        let json_part = &line[start_idx..];
-                        if let Some(entry) = ApiLogEntry::parse(json_part) {
-                            parsed_entry = Some(entry);
-                            log_content = json_part.to_string();
-                        }
+                    // JSON after the marker, otherwise the rest of the line in a text format
+                    let entry_part = match line.find('{') {
+                        Some(start_idx) => line[start_idx..].trim(),
+                        None => class.rest.trim(),
+                    };
+                    if let Some(entry) = self.schema.parse(entry_part) {
+                        parsed_entry = Some(entry);
+                        log_content = entry_part.to_string();
+                    }
+
+                    if self.filters.should_include_batch(&log_content, parsed_entry.as_ref()) {
+                        batch_buffer.push((log_content, parsed_entry, class.prefix.map(str::to_string)));
                     }
-                    
-                    batch_buffer.push((log_content, parsed_entry));
                     continue; // Don't print yet, wait for group end
                 }
 
@@ -298,23 +520,24 @@ impl ProcessManager {
                 if !batch_buffer.is_empty() {
                     // Find first request_id available in the batch
                     let request_id = batch_buffer.iter()
-                        .find_map(|(_, entry)| entry.as_ref().and_then(|e| e.request_id.clone()))
+                        .find_map(|(_, entry, _)| entry.as_ref().and_then(|e| e.request_id.clone()))
                         .unwrap_or_else(|| "Batch Job".to_string());
 
-                    println!("{}", formatter.format_custom_header("Captured Batch Logs", &request_id));
-                    for (content, entry) in &batch_buffer {
+                    console.line(formatter.format_custom_header("Captured Batch Logs", &request_id));
+                    for (content, entry, prefix) in &batch_buffer {
+                        let prefix = prefix.as_deref().unwrap_or(&theme.prefixes.batch);
                         if let Some(e) = entry {
-                            println!("  {} {}", "📦", formatter.format_api_log(e, content));
+                            console.line(format!("  {}", Theme::prefixed(prefix, &formatter.format_api_log(e, content))));
                         } else {
                             // Print non-JSON batch logs simply
-                            println!("  {} {}", "📦".bright_blue(), content.trim());
+                            console.line(format!("  {}", Theme::prefixed(&palette.info.paint(prefix).to_string(), content.trim())));
                         }
                     }
-                    println!("{}", formatter.format_correlated_footer());
+                    console.line(formatter.format_correlated_footer());
                     batch_buffer.clear();
                 }
 
-                let log_type = parse_karate_line(&line);
+                let log_type = class.log_type.clone();
 
                 // Track current feature file name
                 // Example: "feature: ../tests/fetch_perio_chart.feature"
@@ -322,6 +545,7 @@ impl ProcessManager {
                     if let Some(start) = line.find("feature:") {
                         let feature_part = line[start + 8..].trim();
                         current_feature = Some(feature_part.to_string());
+                        console.event(|| DashboardEvent::FeatureStarted(feature_part.to_string()));
                     }
                 }
 
@@ -330,6 +554,41 @@ impl ProcessManager {
                     pending_failure_url = Some(url);
                 }
 
+                // Karate's own request/response logging, grouped by scenario
+                if call_source != Some(CallSource::Output) {
+                    for logged in karate_log.read_lines() {
+                        let logged = self.redactor.redact_line(&logged);
+                        if let Some(exchange) = log_http_parser.push(&logged) {
+                            call_source = Some(CallSource::KarateLog);
+                            calls.push(exchange);
+                        }
+                    }
+                }
+                if call_source != Some(CallSource::KarateLog) {
+                    if let Some(exchange) = http_parser.push(&line) {
+                        call_source = Some(CallSource::Output);
+                        calls.push(exchange);
+                    }
+                }
+                failure_parser.set_scenario_run(calls.current_run());
+
+                // A panic the failed call caused is reported before the failure is correlated
+                if log_type == LogType::KarateFailure || line.contains("failed features:") {
+                    traces.flush_all().await;
+                }
+
+                // Assemble multi-line failure blocks; match failures get an expected-vs-actual diff
+                if let Some(mut failure) = failure_parser.push(&line) {
+                    traces.flush_all().await;
+                    if failure.url.is_none() {
+                        failure.url = pending_failure_url
+                            .clone()
+                            .or_else(|| calls.last().map(|call| call.url.clone()));
+                    }
+                    self.report_failure(&failure, calls.current_scenario(), &features, &formatter).await;
+                }
+                let last_location = failure_parser.last_location().cloned();
+
                 // Track test summary
                 if log_type == LogType::KarateSummary {
                     let mut summary = test_summary_clone.lock().await;
@@ -339,142 +598,544 @@ impl ProcessManager {
                 // In failed-only mode, we need to correlate and show API logs
                 if log_type == LogType::KarateFailure || line.contains("failed features:") {
                     let correlator = correlator_for_karate.lock().await;
-                    let mut showed_logs = false;
-
-                    // Try to find logs by URL if we have one
-                    if let Some(ref url) = pending_failure_url {
-                        if let Some((request_id, logs)) = correlator.find_matching_logs_by_url(url) {
-                            println!("{}", formatter.format_correlated_header(request_id));
-                            for (raw_json, entry) in logs {
-                                println!("  {}", formatter.format_api_log(entry, raw_json));
-                            }
-                            println!("{}", formatter.format_correlated_footer());
-                            showed_logs = true;
-                        }
-                        pending_failure_url = None;
+                    let mut correlated = None;
+
+                    // Find logs by the failure's URL, or else by the last call Karate logged
+                    let failure_url = pending_failure_url
+                        .take()
+                        .or_else(|| calls.last().map(|call| call.url.clone()));
+                    if let Some(url) = &failure_url {
+                        correlated = correlator
+                            .find_matching_logs_by_url(url)
+                            .map(|(request_id, logs)| (request_id, logs.iter().collect::<Vec<_>>()));
                     }
 
                     // Fallback: if no URL-based logs, show all logs from most recent request
-                    if !showed_logs && failed_only {
-                        if let Some((request_id, logs)) = correlator.get_last_request_logs(100) {
-                            println!("\n{}", formatter.format_correlated_header(request_id));
-                            for (raw_json, entry) in logs {
-                                println!("  {}", formatter.format_api_log(entry, raw_json));
+                    if correlated.is_none() && compact_output {
+                        correlated = correlator.get_last_request_logs(100);
+                    }
+
+                    if let (LogType::KarateFailure, Some((file, line))) = (&log_type, &last_location) {
+                        test_summary_clone.lock().await.model.failure_at(file, *line, None, &features);
+                    }
+
+                    if console.is_dashboard() {
+                        if log_type == LogType::KarateFailure {
+                            console.failure(|| DashboardFailure {
+                                location: last_location
+                                    .as_ref()
+                                    .map(|(file, line)| format!("{}:{}", file, line))
+                                    .unwrap_or_else(|| current_feature.clone().unwrap_or_default()),
+                                message: line.trim().to_string(),
+                                request_id: correlated.as_ref().map(|(id, _)| id.to_string()),
+                                logs: correlated
+                                    .iter()
+                                    .flat_map(|(_, logs)| logs.iter())
+                                    .filter(|(_, entry)| self.filters.should_include_api(entry))
+                                    .map(|&log| log.clone())
+                                    .collect(),
+                            });
+                        }
+                    } else if let Some((request_id, logs)) = correlated {
+                        console.line(formatter.format_correlated_header(request_id));
+                        // Karate's side of the call first, then the API's
+                        let call = calls
+                            .current_scenario()
+                            .iter()
+                            .rev()
+                            .find(|call| Some(&call.url) == failure_url.as_ref());
+                        if let Some(call) = call {
+                            console.line(format!("  {}", formatter.format_http_exchange(call)));
+                        }
+                        for (raw_json, entry) in logs {
+                            if self.filters.should_include_api(entry) {
+                                console.line(format!("  {}", formatter.format_api_log(entry, raw_json)));
                             }
-                            println!("{}", formatter.format_correlated_footer());
                         }
+                        console.line(formatter.format_correlated_footer());
+                    }
+                }
+
+                // Per-feature scenario summary
+                // Example: "scenarios:  4 | passed:  4 | failed:  0 | time: 1.0661"
+                let is_feature_result = line.contains("scenarios:") && line.contains("passed:") && line.contains("time:") && !line.contains("threads");
+                let feature_passed = line.contains("failed:  0") || line.contains("failed: 0");
+                if is_feature_result {
+                    console.event(|| DashboardEvent::FeatureFinished {
+                        feature: current_feature.clone().unwrap_or_default(),
+                        failed: !feature_passed,
+                    });
+                }
+                let feature_result = is_feature_result.then(|| parse_karate_summary(&line)).flatten();
+                if let Some(result) = &feature_result {
+                    let mut summary = test_summary_clone.lock().await;
+                    summary.model.feature_finished(
+                        current_feature.as_deref().unwrap_or_default(),
+                        result,
+                        chrono::Utc::now(),
+                    );
+                    if let Some(Ok(reports)) = report_watcher.as_mut().map(ReportWatcher::poll) {
+                        summary.model.apply_report(&reports);
                     }
                 }
+                let mut progress_bar = None;
+                if let (Some(tracker), Some(result)) = (&progress, &feature_result) {
+                    let mut tracker = tracker.lock().await;
+                    tracker.feature_finished(current_feature.as_deref().unwrap_or_default(), result);
+                    let status = tracker.status();
+                    if interactive && !console.is_dashboard() {
+                        progress_bar = Some(status.render_bar(&theme, 30));
+                    }
+                    console.event(|| DashboardEvent::Progress(status));
+                }
 
                 // Determine what to show based on mode
-                if failed_only {
+                if !self.filters.should_include_karate(&line, &log_type) {
+                    // Hidden by the Karate filter rules
+                } else if compact_output {
                     // Per-feature scenario summary (combine with feature name)
-                    // Example: "scenarios:  4 | passed:  4 | failed:  0 | time: 1.0661"
-                    if line.contains("scenarios:") && line.contains("passed:") && line.contains("time:") && !line.contains("threads") {
-                        let prefix = if line.contains("failed:  0") || line.contains("failed: 0") {
-                            format!("{}", "✅".green())
+                    if is_feature_result {
+                        let prefix = if feature_passed {
+                            palette.success.paint(&theme.prefixes.success).to_string()
                         } else {
-                            format!("{}", "❌".red())
+                            palette.error.paint(&theme.prefixes.error).to_string()
                         };
                         
                         let feature_name = current_feature.as_deref().unwrap_or("unknown");
                         // Extract just the filename from path
                         let short_name = feature_name.rsplit('/').next().unwrap_or(feature_name);
                         
-                        println!("{} {} {}", prefix, short_name.bright_white(), line.trim());
+                        console.line(format!("{} {} {}", prefix, palette.text.paint(short_name), line.trim()));
                     }
                     // Show failures
                     else if log_type == LogType::KarateFailure {
-                        let formatted = formatter.format_karate_log(&line, &log_type);
-                        println!("{}", formatted);
+                        let formatted = formatter.format_karate_log(&line, &class);
+                        console.line(formatted);
                     }
                     // Show failed features details (the >>> block)
                     else if line.contains(">>> failed features:") {
-                        let formatted = formatter.format_karate_log(&line, &log_type);
-                        println!("{}", formatted);
+                        let formatted = formatter.format_karate_log(&line, &class);
+                        console.line(formatted);
                     }
                     // Skip everything else (final summary is handled by TestSummary)
                 } else {
                     // Normal mode: show everything
-                    let formatted = formatter.format_karate_log(&line, &log_type);
-                    println!("{}", formatted);
+                    let formatted = formatter.format_karate_log(&line, &class);
+                    console.line(formatted);
+                }
+
+                if let Some(bar) = progress_bar {
+                    console.status(bar);
                 }
             }
             
+            // Blocks still open when the stream ends
+            if call_source != Some(CallSource::Output) {
+                for logged in karate_log.read_lines() {
+                    if let Some(exchange) = log_http_parser.push(&self.redactor.redact_line(&logged)) {
+                        calls.push(exchange);
+                    }
+                }
+                for exchange in log_http_parser.finish() {
+                    calls.push(exchange);
+                }
+            }
+            if call_source != Some(CallSource::KarateLog) {
+                for exchange in http_parser.finish() {
+                    calls.push(exchange);
+                }
+            }
+            if let Some(mut failure) = failure_parser.finish() {
+                traces.flush_all().await;
+                if failure.url.is_none() {
+                    failure.url = pending_failure_url.or_else(|| calls.last().map(|call| call.url.clone()));
+                }
+                self.report_failure(&failure, calls.current_scenario(), &features, &formatter).await;
+            }
+
             // Flush any remaining batch logs at the end
             if !batch_buffer.is_empty() {
                 let request_id = batch_buffer.iter()
-                    .find_map(|(_, entry)| entry.as_ref().and_then(|e| e.request_id.clone()))
+                    .find_map(|(_, entry, _)| entry.as_ref().and_then(|e| e.request_id.clone()))
                     .unwrap_or_else(|| "Batch Job".to_string());
 
-                println!("{}", formatter.format_custom_header("Captured Batch Logs", &request_id));
-                for (content, entry) in &batch_buffer {
+                console.line(formatter.format_custom_header("Captured Batch Logs", &request_id));
+                for (content, entry, prefix) in &batch_buffer {
+                    let prefix = prefix.as_deref().unwrap_or(&theme.prefixes.batch);
                     if let Some(e) = entry {
-                        println!("  {} {}", "📦", formatter.format_api_log(e, content));
+                        console.line(format!("  {}", Theme::prefixed(prefix, &formatter.format_api_log(e, content))));
                     } else {
-                        println!("  {} {}", "📦".bright_blue(), content.trim());
+                        console.line(format!("  {}", Theme::prefixed(&palette.info.paint(prefix).to_string(), content.trim())));
                     }
                 }
-                println!("{}", formatter.format_correlated_footer());
+                console.line(formatter.format_correlated_footer());
             }
         }
 
         // Process stderr
         if let Some(stderr) = stderr {
             let mut reader = BufReader::new(stderr).lines();
+            let stderr_prefix = format!("{}{}", theme.prefixes.error, theme.prefixes.karate);
             while let Ok(Some(line)) = reader.next_line().await {
-                eprintln!("{} {}", "❌🔶".red(), line.red());
+                let line = self.redactor.redact_line(&line);
+                console.error_line(format!("{} {}", palette.error.paint(&stderr_prefix), palette.error.paint(&line)));
             }
         }
 
         // Wait for process to complete
         let status = child.wait().await?;
         let exit_code = status.code().unwrap_or(1);
+        console.finish_status();
+
+        if let Some(task) = status_task {
+            task.abort();
+        }
+        if let Some(tracker) = &progress {
+            if let Some(history_path) = &history_path {
+                if let Err(e) = tracker.lock().await.save_history(history_path) {
+                    console.line(format!("{} Could not save progress history: {}", warn, e));
+                }
+            }
+        }
+
+        // Karate's own report is authoritative over what was scraped from stdout
+        if cucumber_json {
+            match CucumberReport::load_dir(&report_dir, started_at) {
+                Ok(report) if !report.is_empty() => self.test_summary.lock().await.reconcile(&report, &self.redactor),
+                Ok(_) => console.line(format!(
+                    "{} No cucumber JSON report in {}; the summary is from live output",
+                    warn,
+                    report_dir.display()
+                )),
+                Err(e) => console.line(format!(
+                    "{} Could not read reports in {}: {}",
+                    warn,
+                    report_dir.display(),
+                    e
+                )),
+            }
+        }
+
+        // Name failures the cucumber report added and time single-scenario features
+        self.test_summary.lock().await.annotate(&features);
 
         Ok(exit_code)
     }
 }
 
-/// Process API output stream
-async fn process_api_output(
-    stdout: tokio::process::ChildStdout,
+/// Where Karate's HTTP calls are read from; the other source would repeat them
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum CallSource {
+    Output,
+    KarateLog,
+}
+
+/// State for the API stdout handler task
+struct ApiOutputHandler {
     correlator: Arc<Mutex<RequestCorrelator>>,
     sql_stats: Arc<Mutex<SqlStats>>,
-    config: &Config,
+    config: Config,
     formatter: LogFormatter,
-    filter: LogFilter,
-) {
-    let mut reader = BufReader::new(stdout).lines();
+    filters: Arc<SourceFilters>,
+    redactor: Arc<Redactor>,
+    schema: Arc<ApiSchema>,
+    exporter: Arc<Mutex<Option<LogExporter>>>,
+    console: Console,
+    /// Filtered-out entries kept for grep-style context
+    context: Option<ContextBuffer<(String, ApiLogEntry)>>,
+    /// Collapsing and rate limiting of displayed entries
+    dedup: Option<Deduplicator<(String, ApiLogEntry)>>,
+    /// Collapsing and rate limiting of exported entries
+    export_dedup: Option<Deduplicator<(String, ApiLogEntry)>>,
+    traces: TraceReporter,
+}
+
+impl ApiOutputHandler {
+    /// Process API output stream
+    async fn run(mut self, stdout: tokio::process::ChildStdout) {
+        let mut reader = BufReader::new(stdout).lines();
+
+        let folder = self.traces.stdout.clone();
+        while let Some(line) = next_line_or_flush(&mut reader, &self.traces, &folder).await {
+            // Mask secrets before the line reaches stats, correlation, display or export
+            let line = self.redactor.redact_api_line(&line);
+
+            // Only text lines start a trace; JSON panic entries keep their fields
+            let mut entry = self.schema.parse(&line);
+            let folded = match entry {
+                Some(_) => folder.lock().await.push_entry(&line),
+                None => folder.lock().await.push(&line),
+            };
+            for folded in folded {
+                match folded {
+                    Folded::Line(line) => self.handle_line(line, entry.take()).await,
+                    Folded::Trace(trace) => self.traces.report(trace).await,
+                }
+            }
+        }
+        self.traces.flush(&folder).await;
+
+        if let Some(mut dedup) = self.dedup.take() {
+            for event in dedup.flush() {
+                self.show_dedup_event(event);
+            }
+        }
+        if let Some(mut dedup) = self.export_dedup.take() {
+            if let Some(exporter) = self.exporter.lock().await.as_mut() {
+                for event in dedup.flush() {
+                    Self::export_event(exporter, event);
+                }
+            }
+        }
+    }
+
+    /// Handle an output line that is not part of a stack trace, with its parsed entry
+    async fn handle_line(&mut self, line: String, entry: Option<ApiLogEntry>) {
+        if let Some(entry) = entry {
+            // A logged `debug.Stack()` or recovered panic is shown as a trace
+            if let Some(mut trace) = embedded_stack_trace(&entry) {
+                trace.request_id = entry.request_id.clone();
+                self.traces.report(trace).await;
+                return;
+            }
+            self.traces.track(&entry).await;
+
+            if let Some(exporter) = self.exporter.lock().await.as_mut() {
+                match self.export_dedup.as_mut() {
+                    Some(dedup) => {
+                        for event in dedup.push((line.clone(), entry.clone()), &entry, Instant::now()) {
+                            Self::export_event(exporter, event);
+                        }
+                    }
+                    None => {
+                        let _ = exporter.write_api_log(&line, &entry);
+                    }
+                }
+            }
 
-    while let Ok(Some(line)) = reader.next_line().await {
-        // Try to parse as JSON
-        if let Some(entry) = ApiLogEntry::parse(&line) {
             // Track SQL statistics
-            if config.analysis.track_sql && entry.sql.is_some() {
-                let mut stats = sql_stats.lock().await;
+            if self.config.analysis.track_sql && entry.sql.is_some() {
+                let mut stats = self.sql_stats.lock().await;
                 stats.track_query(&entry);
             }
 
-            // Buffer for correlation (in failed-only mode)
-            if config.analysis.failed_only {
-                let mut corr = correlator.lock().await;
+            // Buffer for correlation (in failed-only and dashboard modes)
+            if self.config.analysis.failed_only || self.console.is_dashboard() {
+                let mut corr = self.correlator.lock().await;
                 corr.buffer_api_log(line.clone(), entry.clone());
             }
 
             // Apply filter and format
-            if !config.analysis.failed_only && filter.should_include_api(&entry) {
-                let formatted = formatter.format_api_log(&entry, &line);
-                println!("{}", formatted);
+            if self.config.analysis.failed_only {
+                return;
             }
+            let matched = self.filters.should_include_api(&entry);
+            if matched {
+                if let Some(dedup) = self.dedup.as_mut() {
+                    // Collapsed and rate-limited entries are not reused as context
+                    for event in dedup.push((line, entry.clone()), &entry, Instant::now()) {
+                        self.show_dedup_event(event);
+                    }
+                    return;
+                }
+            }
+            self.show_api_log(line, entry, matched);
         } else {
             // Non-JSON line, print as-is if not in failed-only mode
-            if !config.analysis.failed_only {
-                println!("{} {}", "🔷".bright_blue(), line);
+            if !self.config.analysis.failed_only {
+                let theme = self.formatter.theme();
+                self.console.line(Theme::prefixed(&theme.palette.info.paint(&theme.prefixes.api).to_string(), &line));
+            }
+        }
+    }
+
+    /// Show an entry directly or through the context buffer
+    fn show_api_log(&mut self, line: String, entry: ApiLogEntry, matched: bool) {
+        match self.context.as_mut() {
+            Some(context) if !self.filters.is_hidden_api(&entry) => {
+                let request_id = entry.request_id.clone();
+                for output in context.push((line, entry), request_id.as_deref(), matched) {
+                    self.show_context_line(output);
+                }
+            }
+            _ if matched => {
+                self.console.api_log(&entry, || self.formatter.format_api_log(&entry, &line));
+            }
+            _ => {}
+        }
+    }
+
+    fn show_dedup_event(&mut self, event: DedupEvent<(String, ApiLogEntry)>) {
+        match event {
+            DedupEvent::Show((line, entry)) => self.show_api_log(line, entry, true),
+            DedupEvent::Repeated { item: (_, entry), count } => {
+                self.console.line(self.formatter.format_repeated(&entry, count));
+            }
+            DedupEvent::RateLimited { msg, count, window } => {
+                self.console.line(self.formatter.format_rate_limited(&msg, count, window));
+            }
+        }
+    }
+
+    /// Write an entry, or a synthetic summary entry for collapsed or suppressed ones
+    fn export_event(exporter: &mut LogExporter, event: DedupEvent<(String, ApiLogEntry)>) {
+        let (line, entry) = match event {
+            DedupEvent::Show(item) => item,
+            DedupEvent::Repeated { item: (line, entry), count } => {
+                // Logfmt and regex lines export the parsed entry, as in `write_api_log`
+                let mut value = serde_json::from_str::<serde_json::Value>(&line)
+                    .ok()
+                    .filter(serde_json::Value::is_object)
+                    .or_else(|| serde_json::to_value(&entry).ok())
+                    .unwrap_or_default();
+                if let Some(object) = value.as_object_mut() {
+                    object.insert("msg".into(), format!("repeated x{}: {}", count, entry.msg).into());
+                    object.insert("repeat_count".into(), count.into());
+                }
+                (value.to_string(), entry)
+            }
+            DedupEvent::RateLimited { msg, count, window } => {
+                let value = serde_json::json!({
+                    "level": "WARN",
+                    "msg": format!("rate limit: suppressed {} \"{}\" entries", count, msg),
+                    "suppressed_count": count,
+                    "window_secs": window.as_secs(),
+                });
+                let line = value.to_string();
+                match ApiLogEntry::parse(&line) {
+                    Some(entry) => (line, entry),
+                    None => return,
+                }
+            }
+        };
+        let _ = exporter.write_api_log(&line, &entry);
+    }
+
+    fn show_context_line(&self, output: ContextLine<(String, ApiLogEntry)>) {
+        let theme = self.formatter.theme();
+        match output {
+            ContextLine::Match((line, entry)) => {
+                self.console.line(self.formatter.format_api_log(&entry, &line));
+            }
+            ContextLine::Context((line, entry)) => {
+                self.console.line(Theme::prefixed(
+                    &theme.palette.muted.paint(&theme.borders.context_marker).to_string(),
+                    &self.formatter.format_api_log(&entry, &line),
+                ));
+            }
+            ContextLine::Separator => {
+                self.console.line(theme.palette.muted.paint(&theme.borders.context_separator).to_string());
             }
         }
     }
 }
 
+/// Reports folded panics and stack traces from the API's stdout and stderr
+#[derive(Clone)]
+struct TraceReporter {
+    /// Trace lines being collected from each stream
+    stdout: Arc<Mutex<StackTraceFolder>>,
+    stderr: Arc<Mutex<StackTraceFolder>>,
+    /// The most recent request that has logged but not finished
+    active_request: Arc<Mutex<Option<String>>>,
+    correlator: Arc<Mutex<RequestCorrelator>>,
+    test_summary: Arc<Mutex<TestSummary>>,
+    exporter: Arc<Mutex<Option<LogExporter>>>,
+    filters: Arc<SourceFilters>,
+    formatter: Arc<LogFormatter>,
+    console: Console,
+    failed_only: bool,
+}
+
+impl TraceReporter {
+    /// Follow which request is being handled, for traces that do not name one
+    async fn track(&self, entry: &ApiLogEntry) {
+        let Some(request_id) = &entry.request_id else {
+            return;
+        };
+        let mut active = self.active_request.lock().await;
+        if entry.is_request_summary() {
+            if active.as_ref() == Some(request_id) {
+                *active = None;
+            }
+        } else {
+            *active = Some(request_id.clone());
+        }
+    }
+
+    /// Report the trace still open in one stream
+    async fn flush(&self, folder: &Mutex<StackTraceFolder>) {
+        let trace = folder.lock().await.finish();
+        if let Some(trace) = trace {
+            self.report(trace).await;
+        }
+    }
+
+    /// Report open traces on both streams, e.g. before a Karate failure is correlated
+    async fn flush_all(&self) {
+        self.flush(&self.stdout).await;
+        self.flush(&self.stderr).await;
+    }
+
+    /// Export, summarise and show a trace as one ERROR entry
+    async fn report(&self, mut trace: StackTrace) {
+        if trace.request_id.is_none() {
+            trace.request_id = self.active_request.lock().await.clone();
+        }
+        let (line, entry) = trace.to_entry();
+
+        if let Some(exporter) = self.exporter.lock().await.as_mut() {
+            let _ = exporter.write_api_log(&line, &entry);
+        }
+        // Traces of a request are shown with its failure; others are always shown
+        let attached = entry.request_id.is_some();
+        if attached && (self.failed_only || self.console.is_dashboard()) {
+            self.correlator.lock().await.buffer_api_log(line.clone(), entry.clone());
+        }
+        self.test_summary.lock().await.api_errors.push(trace);
+
+        let show = if self.failed_only {
+            !attached
+        } else {
+            self.filters.should_include_api(&entry)
+        };
+        if show {
+            self.console.api_log(&entry, || self.formatter.format_api_log(&entry, &line));
+        }
+    }
+}
+
+/// Quiet time after which an open trace is reported without waiting for the next line
+const TRACE_IDLE: Duration = Duration::from_millis(250);
+
+/// The next line of an API stream, reporting the stream's open trace whenever it goes idle
+async fn next_line_or_flush<R: tokio::io::AsyncBufRead + Unpin>(
+    reader: &mut tokio::io::Lines<R>,
+    traces: &TraceReporter,
+    folder: &Mutex<StackTraceFolder>,
+) -> Option<String> {
+    loop {
+        match tokio::time::timeout(TRACE_IDLE, reader.next_line()).await {
+            Ok(line) => return line.ok().flatten(),
+            Err(_) => traces.flush(folder).await,
+        }
+    }
+}
+
+/// A stack trace logged inside one entry (Echo's `[PANIC RECOVER]` message or a `stack` field)
+fn embedded_stack_trace(entry: &ApiLogEntry) -> Option<StackTrace> {
+    let extra = |key| entry.extra.get(key).and_then(|v| v.as_str());
+    let mut trace = [Some(entry.msg.as_str()), extra("message"), extra("stack")]
+        .into_iter()
+        .flatten()
+        .find_map(StackTrace::from_text)?;
+    // A bare goroutine dump takes the entry's error or message
+    if trace.message.is_empty() {
+        trace.message = entry.err.clone().unwrap_or_else(|| entry.msg.clone());
+    }
+    Some(trace)
+}
+
 /// Simple health check using TCP connection (to avoid reqwest dependency)
 async fn reqwest_health_check(url: &str) -> Result<bool, Box<dyn std::error::Error>> {
     // Parse URL to get host and port
@@ -0,0 +1,414 @@
+//! Feature discovery and run progress with an ETA from previous runs
+
+use crate::gherkin;
+use crate::log_parser::KarateTestResult;
+use crate::theme::Theme;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// A feature file found before the run
+#[derive(Debug, Clone)]
+pub struct DiscoveredFeature {
+    pub path: PathBuf,
+    pub scenarios: usize,
+}
+
+/// Feature files found under the test paths
+#[derive(Debug, Clone, Default)]
+pub struct FeatureInventory {
+    pub features: Vec<DiscoveredFeature>,
+    /// Test paths that could not be scanned (classpath entries, missing paths)
+    pub unresolved: Vec<String>,
+}
+
+impl FeatureInventory {
+    pub fn total_scenarios(&self) -> usize {
+        self.features.iter().map(|f| f.scenarios).sum()
+    }
+
+    /// Whether every test path was scanned, so the totals are exact
+    pub fn is_complete(&self) -> bool {
+        self.unresolved.is_empty()
+    }
+}
+
+/// Scan test paths (files or directories) for `.feature` files
+///
+/// Relative paths are resolved against `base_dir`, the directory Karate runs in.
+pub fn discover_features(test_paths: &[String], base_dir: &Path) -> FeatureInventory {
+    let mut inventory = FeatureInventory::default();
+
+    for test_path in test_paths {
+        if test_path.starts_with("classpath:") {
+            inventory.unresolved.push(test_path.clone());
+            continue;
+        }
+
+        // "x.feature:12:30" runs the scenarios (or whole outlines) at those lines
+        let (file, lines) = gherkin::split_test_path(test_path);
+
+        let path = base_dir.join(&file);
+        if path.is_dir() {
+            collect_features(&path, &mut inventory.features);
+        } else if path.is_file() {
+            let content = std::fs::read_to_string(&path).unwrap_or_default();
+            let scenarios = if lines.is_empty() {
+                count_scenarios(&content)
+            } else {
+                gherkin::parse(&path, &content)
+                    .map(|feature| lines.iter().map(|&line| feature.runs_at(line)).sum())
+                    .unwrap_or(lines.len())
+            };
+            inventory.features.push(DiscoveredFeature { path, scenarios });
+        } else {
+            inventory.unresolved.push(test_path.clone());
+        }
+    }
+
+    inventory
+}
+
+fn collect_features(dir: &Path, out: &mut Vec<DiscoveredFeature>) {
+    let mut paths = Vec::new();
+    gherkin::collect_feature_files(dir, &mut paths);
+    for path in paths {
+        let scenarios = std::fs::read_to_string(&path)
+            .map(|content| count_scenarios(&content))
+            .unwrap_or(0);
+        out.push(DiscoveredFeature { path, scenarios });
+    }
+}
+
+/// Count the scenarios Karate will run: one per `Scenario`, one per example row of an outline
+pub fn count_scenarios(content: &str) -> usize {
+    gherkin::parse(Path::new(""), content)
+        .map(|feature| feature.run_count())
+        .unwrap_or(0)
+}
+
+/// Per-feature durations (seconds) from previous runs
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct History {
+    features: HashMap<String, f64>,
+}
+
+/// Point-in-time view of the run progress
+#[derive(Debug, Clone)]
+pub struct ProgressStatus {
+    pub features_done: usize,
+    pub features_total: Option<usize>,
+    pub scenarios_done: u32,
+    pub scenarios_total: Option<usize>,
+    pub failures: u32,
+    pub elapsed: Duration,
+    pub eta: Option<Duration>,
+}
+
+/// Tracks finished features against the discovered inventory
+pub struct ProgressTracker {
+    inventory: FeatureInventory,
+    finished: HashSet<usize>,
+    features_done: usize,
+    scenarios_done: u32,
+    failures: u32,
+    /// Durations measured in this run, keyed like the history
+    durations: HashMap<String, f64>,
+    history: HashMap<String, f64>,
+    threads: u32,
+    started: Instant,
+}
+
+impl ProgressTracker {
+    /// Create a tracker, loading previous durations from `history_path` if it exists
+    pub fn new(inventory: FeatureInventory, history_path: &Path, threads: u32) -> Self {
+        let history = std::fs::read_to_string(history_path)
+            .ok()
+            .and_then(|content| serde_json::from_str::<History>(&content).ok())
+            .unwrap_or_default();
+
+        Self {
+            inventory,
+            finished: HashSet::new(),
+            features_done: 0,
+            scenarios_done: 0,
+            failures: 0,
+            durations: HashMap::new(),
+            history: history.features,
+            threads: threads.max(1),
+            started: Instant::now(),
+        }
+    }
+
+    /// Record a per-feature result line for the feature Karate reported
+    pub fn feature_finished(&mut self, reported: &str, result: &KarateTestResult) {
+        self.features_done += 1;
+        self.scenarios_done += result.total_scenarios;
+        self.failures += result.failed;
+
+        let key = match self.find_feature(reported) {
+            Some(index) => {
+                self.finished.insert(index);
+                history_key(&self.inventory.features[index].path)
+            }
+            None => reported.to_string(),
+        };
+        if let Some(secs) = result.time_secs {
+            self.durations.insert(key, secs);
+        }
+    }
+
+    /// Match a reported feature name ("classpath:tests/a.feature", "../tests/a.feature")
+    /// to a discovered feature that has not finished yet
+    fn find_feature(&self, reported: &str) -> Option<usize> {
+        let mut name = reported.trim().trim_start_matches("classpath:");
+        while let Some(rest) = name.strip_prefix("../").or_else(|| name.strip_prefix("./")) {
+            name = rest;
+        }
+        if name.is_empty() {
+            return None;
+        }
+
+        let name = Path::new(name);
+        self.inventory
+            .features
+            .iter()
+            .enumerate()
+            .find(|(i, f)| !self.finished.contains(i) && f.path.ends_with(name))
+            .map(|(i, _)| i)
+    }
+
+    /// Estimated time left, from previous-run durations of the remaining features
+    ///
+    /// Features without history use the average duration seen so far. Returns `None`
+    /// when the total is unknown or there is nothing to estimate from.
+    fn eta(&self) -> Option<Duration> {
+        if !self.inventory.is_complete() {
+            return None;
+        }
+
+        let average = |values: Vec<f64>| {
+            (!values.is_empty()).then(|| values.iter().sum::<f64>() / values.len() as f64)
+        };
+        let fallback = average(self.durations.values().copied().collect())
+            .or_else(|| average(self.history.values().copied().collect()));
+
+        let mut remaining = 0.0;
+        for (i, feature) in self.inventory.features.iter().enumerate() {
+            if self.finished.contains(&i) {
+                continue;
+            }
+            remaining += self
+                .history
+                .get(&history_key(&feature.path))
+                .copied()
+                .or(fallback)?;
+        }
+
+        Some(Duration::from_secs_f64(remaining / self.threads as f64))
+    }
+
+    pub fn status(&self) -> ProgressStatus {
+        let complete = self.inventory.is_complete();
+        ProgressStatus {
+            features_done: self.features_done,
+            features_total: complete.then_some(self.inventory.features.len()),
+            scenarios_done: self.scenarios_done,
+            scenarios_total: complete.then(|| self.inventory.total_scenarios()),
+            failures: self.failures,
+            elapsed: self.started.elapsed(),
+            eta: self.eta(),
+        }
+    }
+
+    /// Merge this run's durations into the history file
+    pub fn save_history(&self, history_path: &Path) -> std::io::Result<()> {
+        if self.durations.is_empty() {
+            return Ok(());
+        }
+
+        let mut features = self.history.clone();
+        features.extend(self.durations.iter().map(|(k, v)| (k.clone(), *v)));
+
+        if let Some(parent) = history_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let json = serde_json::to_string_pretty(&History { features })?;
+        std::fs::write(history_path, json)
+    }
+}
+
+fn history_key(path: &Path) -> String {
+    path.to_string_lossy().to_string()
+}
+
+impl ProgressStatus {
+    /// Fraction of features done, when the total is known
+    pub fn ratio(&self) -> Option<f64> {
+        self.features_total
+            .filter(|&total| total > 0)
+            .map(|total| (self.features_done as f64 / total as f64).min(1.0))
+    }
+
+    /// One-line progress bar for interactive terminals
+    pub fn render_bar(&self, theme: &Theme, width: usize) -> String {
+        let palette = &theme.palette;
+        let filled = self
+            .ratio()
+            .map(|r| (r * width as f64).round() as usize)
+            .unwrap_or(0);
+        let bar_style = if self.failures > 0 { palette.error } else { palette.success };
+        let bar = format!(
+            "[{}{}]",
+            bar_style.paint(&theme.borders.bar_filled.repeat(filled)),
+            palette.muted.paint(&theme.borders.bar_empty.repeat(width - filled))
+        );
+
+        let mut parts = vec![
+            format!("{} features", count_of(self.features_done, self.features_total)),
+            format!(
+                "{} scenarios",
+                count_of(self.scenarios_done as usize, self.scenarios_total)
+            ),
+        ];
+        if self.failures > 0 {
+            parts.push(palette.error.paint(&format!("{} failed", self.failures)).to_string());
+        }
+        parts.push(format!("{} elapsed", format_duration(self.elapsed)));
+        if let Some(eta) = self.eta {
+            parts.push(format!("ETA {}", format_duration(eta)));
+        }
+
+        format!("{} {}", bar, palette.text.paint(&parts.join(&theme.borders.separator)))
+    }
+
+    /// Plain status line for logs and CI output
+    pub fn render_plain(&self) -> String {
+        let mut line = format!(
+            "Progress: {} features, {} scenarios, {} failed, elapsed {}",
+            count_of(self.features_done, self.features_total),
+            count_of(self.scenarios_done as usize, self.scenarios_total),
+            self.failures,
+            format_duration(self.elapsed)
+        );
+        if let Some(eta) = self.eta {
+            line.push_str(&format!(", ETA {}", format_duration(eta)));
+        }
+        line
+    }
+}
+
+fn count_of(done: usize, total: Option<usize>) -> String {
+    match total {
+        Some(total) => format!("{}/{}", done, total),
+        None => done.to_string(),
+    }
+}
+
+/// Format a duration as mm:ss, or h:mm:ss past an hour
+pub fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    if secs >= 3600 {
+        format!("{}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+    } else {
+        format!("{:02}:{:02}", secs / 60, secs % 60)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(scenarios: u32, failed: u32, time: f64) -> KarateTestResult {
+        KarateTestResult {
+            total_scenarios: scenarios,
+            passed: scenarios - failed,
+            failed,
+            time_secs: Some(time),
+        }
+    }
+
+    fn inventory(paths: &[&str]) -> FeatureInventory {
+        FeatureInventory {
+            features: paths
+                .iter()
+                .map(|p| DiscoveredFeature {
+                    path: PathBuf::from(p),
+                    scenarios: 2,
+                })
+                .collect(),
+            unresolved: vec![],
+        }
+    }
+
+    #[test]
+    fn test_count_scenarios_with_outline() {
+        let content = r#"
+Feature: patients
+
+  Scenario: list
+    * print 'a'
+
+  Scenario Outline: get <id>
+    * print id
+
+    Examples:
+      | id |
+      | 1  |
+      | 2  |
+
+  @ignore
+  Scenario: helper
+    * print 'b'
+"#;
+        assert_eq!(count_scenarios(content), 3);
+
+        // A whole outline selected by its line runs every examples row
+        let dir = std::env::temp_dir().join(format!("karate-monitor-progress-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("patients.feature"), content).unwrap();
+        let inventory = discover_features(&["patients.feature:4:7".to_string()], &dir);
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(inventory.total_scenarios(), 3);
+    }
+
+    #[test]
+    fn test_eta_uses_history_and_run_average() {
+        let mut tracker = ProgressTracker::new(
+            inventory(&["/tests/a.feature", "/tests/b.feature", "/tests/c.feature"]),
+            Path::new(""),
+            1,
+        );
+        tracker.history.insert("/tests/b.feature".to_string(), 10.0);
+
+        tracker.feature_finished("classpath:tests/a.feature", &result(2, 1, 4.0));
+        let status = tracker.status();
+
+        assert_eq!(status.features_done, 1);
+        assert_eq!(status.features_total, Some(3));
+        assert_eq!(status.scenarios_done, 2);
+        assert_eq!(status.failures, 1);
+        // b from history, c from this run's average
+        assert_eq!(status.eta, Some(Duration::from_secs(14)));
+    }
+
+    #[test]
+    fn test_unknown_total_has_no_eta() {
+        let mut inv = inventory(&["/tests/a.feature"]);
+        inv.unresolved.push("classpath:other".to_string());
+        let mut tracker = ProgressTracker::new(inv, Path::new(""), 1);
+        tracker.feature_finished("../tests/a.feature", &result(2, 0, 1.0));
+
+        let status = tracker.status();
+        assert_eq!(status.features_total, None);
+        assert_eq!(status.eta, None);
+        assert_eq!(
+            status.render_plain().split(", elapsed").next(),
+            Some("Progress: 1 features, 2 scenarios, 0 failed")
+        );
+        let bar = status.render_bar(&Theme::ascii(), 10);
+        assert!(bar.is_ascii(), "{}", bar);
+        assert!(bar.contains("1 features | 2 scenarios | "), "{}", bar);
+    }
+}
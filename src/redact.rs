@@ -0,0 +1,327 @@
+//! Redaction of secrets and PII before logs are displayed, exported or reported
+
+use crate::config::RedactionConfig;
+use regex::Regex;
+use serde_json::Value;
+
+/// Field names masked by the built-in "default" preset
+const PRESET_FIELDS: &[&str] = &[
+    "authorization",
+    "proxy-authorization",
+    "cookie",
+    "set-cookie",
+    "password",
+    "passwd",
+    "secret",
+    "client_secret",
+    "token",
+    "access_token",
+    "refresh_token",
+    "id_token",
+    "api_key",
+    "apikey",
+    "x-api-key",
+];
+
+/// Text patterns masked by the built-in "default" preset (group 1 is masked when present)
+const PRESET_PATTERNS: &[&str] = &[
+    r"(?i)\bbearer\s+([A-Za-z0-9\-._~+/]+=*)",
+    r"(?i)\bbasic\s+([A-Za-z0-9+/]+=*)",
+    r"\beyJ[A-Za-z0-9_-]+\.[A-Za-z0-9_-]+\.[A-Za-z0-9_-]+",
+];
+
+/// One segment of a configured JSON path
+#[derive(Debug, Clone, PartialEq)]
+enum PathSegment {
+    Key(String),
+    Any,
+}
+
+/// Masks configured fields, paths and patterns in log lines
+pub struct Redactor {
+    enabled: bool,
+    mask: String,
+    fields: Vec<String>,
+    paths: Vec<Vec<PathSegment>>,
+    patterns: Vec<Regex>,
+    /// `field: value` / `field=value` occurrences in free text
+    field_text_pattern: Option<Regex>,
+    sql_literals: bool,
+    /// A quoted SQL string literal
+    sql_literal: Regex,
+}
+
+impl Redactor {
+    /// Create a redactor from configuration, failing on an invalid `patterns` entry
+    pub fn new(config: &RedactionConfig) -> Result<Self, regex::Error> {
+        let use_preset = config.preset.eq_ignore_ascii_case("default");
+
+        let mut fields: Vec<String> = config.fields.iter().map(|f| f.to_lowercase()).collect();
+        let mut pattern_sources: Vec<&str> = config.patterns.iter().map(String::as_str).collect();
+        if use_preset {
+            fields.extend(PRESET_FIELDS.iter().map(|f| f.to_string()));
+            pattern_sources.extend(PRESET_PATTERNS);
+        }
+        fields.sort();
+        fields.dedup();
+
+        let patterns = pattern_sources
+            .iter()
+            .map(|p| Regex::new(p))
+            .collect::<Result<_, _>>()?;
+
+        let field_text_pattern = (!fields.is_empty())
+            .then(|| {
+                let names = fields
+                    .iter()
+                    .map(|f| regex::escape(f))
+                    .collect::<Vec<_>>()
+                    .join("|");
+                Regex::new(&format!(
+                    r#"(?i)(["']?\b(?:{})\b["']?\s*[:=]\s*["']?)((?:bearer|basic)\s+[^"'\s,&}}]+|[^"'\s,&}}]+)"#,
+                    names
+                ))
+                .ok()
+            })
+            .flatten();
+
+        Ok(Self {
+            enabled: config.enabled,
+            mask: config.mask.clone(),
+            fields,
+            paths: config.paths.iter().map(|p| parse_path(p)).collect(),
+            patterns,
+            field_text_pattern,
+            sql_literals: config.sql_literals,
+            sql_literal: Regex::new(r"'(?:[^'\\]|\\.|'')*'").expect("valid literal regex"),
+        })
+    }
+
+    /// Redact an API log line; JSON lines are masked structurally, others as text
+    pub fn redact_api_line(&self, line: &str) -> String {
+        if !self.enabled {
+            return line.to_string();
+        }
+
+        match serde_json::from_str::<Value>(line) {
+            Ok(mut value) if value.is_object() => {
+                let mut path = Vec::new();
+                if self.redact_value(&mut value, &mut path) {
+                    serde_json::to_string(&value).unwrap_or_else(|_| line.to_string())
+                } else {
+                    line.to_string()
+                }
+            }
+            _ => self.redact_text(line),
+        }
+    }
+
+    /// Redact a Karate (or any free text) line, including embedded JSON objects
+    pub fn redact_line(&self, line: &str) -> String {
+        if !self.enabled {
+            return line.to_string();
+        }
+
+        if let Some(start) = line.find('{') {
+            if let Ok(mut value) = serde_json::from_str::<Value>(&line[start..]) {
+                let mut path = Vec::new();
+                let head = self.redact_text(&line[..start]);
+                if self.redact_value(&mut value, &mut path) {
+                    return format!("{}{}", head, serde_json::to_string(&value).unwrap_or_default());
+                }
+                return format!("{}{}", head, &line[start..]);
+            }
+        }
+
+        self.redact_text(line)
+    }
+
+    /// Apply text patterns and `field=value` masking to a string
+    pub fn redact_text(&self, text: &str) -> String {
+        if !self.enabled {
+            return text.to_string();
+        }
+
+        let mut result = text.to_string();
+
+        if let Some(re) = &self.field_text_pattern {
+            result = re
+                .replace_all(&result, |caps: &regex::Captures| format!("{}{}", &caps[1], self.mask))
+                .into_owned();
+        }
+
+        for re in &self.patterns {
+            result = re
+                .replace_all(&result, |caps: &regex::Captures| match caps.get(1) {
+                    Some(group) => {
+                        let whole = caps.get(0).unwrap();
+                        let text = whole.as_str();
+                        let start = group.start() - whole.start();
+                        let end = group.end() - whole.start();
+                        format!("{}{}{}", &text[..start], self.mask, &text[end..])
+                    }
+                    None => self.mask.clone(),
+                })
+                .into_owned();
+        }
+
+        result
+    }
+
+    /// Mask string literals in an SQL statement
+    fn redact_sql(&self, sql: &str) -> String {
+        let masked = self.sql_literal.replace_all(sql, format!("'{}'", self.mask).as_str());
+        self.redact_text(&masked)
+    }
+
+    /// Redact a JSON value in place, returning whether anything changed
+    fn redact_value(&self, value: &mut Value, path: &mut Vec<String>) -> bool {
+        if self.matches_path(path) {
+            *value = Value::String(self.mask.clone());
+            return true;
+        }
+
+        match value {
+            Value::Object(map) => {
+                let mut changed = false;
+                for (key, child) in map.iter_mut() {
+                    if self.fields.contains(&key.to_lowercase()) {
+                        *child = Value::String(self.mask.clone());
+                        changed = true;
+                        continue;
+                    }
+                    path.push(key.clone());
+                    changed |= self.redact_value(child, path);
+                    path.pop();
+                }
+                changed
+            }
+            Value::Array(items) => {
+                let mut changed = false;
+                for (i, child) in items.iter_mut().enumerate() {
+                    path.push(i.to_string());
+                    changed |= self.redact_value(child, path);
+                    path.pop();
+                }
+                changed
+            }
+            Value::String(s) => {
+                // Bodies are often logged as JSON encoded in a string
+                if let Ok(mut inner @ (Value::Object(_) | Value::Array(_))) =
+                    serde_json::from_str::<Value>(s)
+                {
+                    if self.redact_value(&mut inner, path) {
+                        *s = serde_json::to_string(&inner).unwrap_or_default();
+                        return true;
+                    }
+                    return false;
+                }
+
+                let redacted = if self.sql_literals && path.last().is_some_and(|k| k == "sql") {
+                    self.redact_sql(s)
+                } else {
+                    self.redact_text(s)
+                };
+                if redacted != *s {
+                    *s = redacted;
+                    return true;
+                }
+                false
+            }
+            _ => false,
+        }
+    }
+
+    fn matches_path(&self, path: &[String]) -> bool {
+        self.paths.iter().any(|pattern| {
+            pattern.len() == path.len()
+                && pattern.iter().zip(path).all(|(segment, key)| match segment {
+                    PathSegment::Any => true,
+                    PathSegment::Key(k) => k == key,
+                })
+        })
+    }
+}
+
+/// Parse a path such as `$.request_body.items[*].birthday`
+fn parse_path(path: &str) -> Vec<PathSegment> {
+    let path = path.trim().trim_start_matches('$').trim_start_matches('.');
+    path.replace('[', ".[")
+        .split('.')
+        .filter(|s| !s.is_empty())
+        .map(|segment| {
+            let segment = segment.trim_start_matches('[').trim_end_matches(']');
+            if segment == "*" {
+                PathSegment::Any
+            } else {
+                PathSegment::Key(segment.to_string())
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn redactor(fields: &[&str], paths: &[&str], patterns: &[&str]) -> Redactor {
+        Redactor::new(&RedactionConfig {
+            fields: fields.iter().map(|s| s.to_string()).collect(),
+            paths: paths.iter().map(|s| s.to_string()).collect(),
+            patterns: patterns.iter().map(|s| s.to_string()).collect(),
+            sql_literals: true,
+            ..Default::default()
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_fields_and_paths() {
+        let r = redactor(&["birthday"], &["$.request_body.patients[*].name"], &[]);
+        let line = r#"{"msg":"dump","request_body":{"patients":[{"name":"Taro","birthday":"1990-01-01","id":1}]},"authorization":"Bearer abc"}"#;
+        let out = r.redact_api_line(line);
+        assert!(!out.contains("Taro"));
+        assert!(!out.contains("1990-01-01"));
+        assert!(!out.contains("abc"));
+        assert!(out.contains(r#""id":1"#));
+    }
+
+    #[test]
+    fn test_string_encoded_body_and_sql() {
+        let r = redactor(&["name"], &[], &[]);
+        let line = r#"{"response_body":"{\"name\":\"Hanako\"}","sql":"SELECT * FROM p WHERE name = 'Hanako'"}"#;
+        let out = r.redact_api_line(line);
+        assert!(!out.contains("Hanako"), "{}", out);
+    }
+
+    #[test]
+    fn test_text_patterns() {
+        let r = redactor(&[], &[], &[r"patient_id=(\d+)"]);
+        assert_eq!(
+            r.redact_line("1 > Authorization: Bearer eyJhbGciOi.x.y patient_id=42"),
+            "1 > Authorization: *** patient_id=***"
+        );
+        let unchanged = r#"{"msg":"ok"}"#;
+        assert_eq!(r.redact_api_line(unchanged), unchanged);
+    }
+
+    #[test]
+    fn test_disabled_is_passthrough() {
+        let r = Redactor::new(&RedactionConfig {
+            enabled: false,
+            ..Default::default()
+        })
+        .unwrap();
+        let line = r#"{"password":"hunter2"}"#;
+        assert_eq!(r.redact_api_line(line), line);
+    }
+
+    #[test]
+    fn test_invalid_pattern_is_an_error() {
+        let config = RedactionConfig {
+            patterns: vec!["(unclosed".to_string()],
+            ..Default::default()
+        };
+        assert!(Redactor::new(&config).is_err());
+    }
+}
@@ -0,0 +1,454 @@
+//! Per-feature and per-scenario results, built while Karate runs
+
+use crate::cucumber::{FeatureReport, Status};
+use crate::gherkin::{Feature, Scenario};
+use crate::log_parser::KarateTestResult;
+use crate::progress::format_duration;
+use crate::theme::Theme;
+use chrono::{DateTime, Local, Utc};
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+use std::time::Duration;
+
+#[derive(Debug, Clone)]
+pub struct ScenarioRecord {
+    pub name: String,
+    pub line: u32,
+    pub tags: Vec<String>,
+    /// 1-based examples row of a Scenario Outline
+    pub example_row: Option<usize>,
+    pub started: Option<DateTime<Utc>>,
+    pub finished: Option<DateTime<Utc>>,
+    pub duration: Option<Duration>,
+    pub status: Status,
+    /// e.g. `* match response.id == 1`
+    pub failing_step: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct FeatureRecord {
+    /// As Karate reports it, e.g. `classpath:features/users.feature`
+    pub path: String,
+    pub started: Option<DateTime<Utc>>,
+    pub finished: DateTime<Utc>,
+    pub duration: Option<Duration>,
+    pub passed: u32,
+    pub failed: u32,
+    /// From the cucumber report when available, otherwise only the failure locations
+    pub scenarios: Vec<ScenarioRecord>,
+}
+
+impl FeatureRecord {
+    fn short_name(&self) -> &str {
+        self.path.rsplit('/').next().unwrap_or(&self.path)
+    }
+}
+
+/// Everything known about the run, feature by feature
+#[derive(Debug, Default)]
+pub struct RunModel {
+    pub features: Vec<FeatureRecord>,
+    /// Failures seen before their feature's summary line, by feature file
+    pending_failures: Vec<(String, ScenarioRecord)>,
+}
+
+impl RunModel {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a failure at `file:line`, attributed when its feature finishes
+    ///
+    /// The scenario's name and tags come from the parsed `features`; a location reported
+    /// again fills in what the earlier report lacked.
+    pub fn failure_at(&mut self, file: &str, line: u32, step: Option<&str>, features: &[Feature]) {
+        let index = match self
+            .pending_failures
+            .iter()
+            .position(|(pending, record)| pending == file && record.line == line)
+        {
+            Some(index) => index,
+            None => {
+                let record = ScenarioRecord {
+                    name: String::new(),
+                    line,
+                    tags: Vec::new(),
+                    example_row: None,
+                    started: None,
+                    finished: None,
+                    duration: None,
+                    status: Status::Failed,
+                    failing_step: None,
+                };
+                self.pending_failures.push((file.to_string(), record));
+                self.pending_failures.len() - 1
+            }
+        };
+
+        let record = &mut self.pending_failures[index].1;
+        if record.name.is_empty() {
+            if let Some((feature, scenario)) = find_scenario(features, file, line) {
+                record.name = scenario.name.clone();
+                record.tags = feature.tags.iter().chain(&scenario.tags).cloned().collect();
+            }
+        }
+        if record.failing_step.is_none() {
+            record.failing_step = step.map(str::to_string);
+        }
+    }
+
+    /// Record a feature's summary line; Karate prints it when the feature is done
+    ///
+    /// Summaries of an unknown feature are skipped; a cucumber report may add it later.
+    pub fn feature_finished(&mut self, path: &str, result: &KarateTestResult, now: DateTime<Utc>) {
+        if path.trim().is_empty() {
+            return;
+        }
+        let duration = result.time_secs.map(Duration::from_secs_f64);
+        let (failures, pending): (Vec<_>, Vec<_>) = std::mem::take(&mut self.pending_failures)
+            .into_iter()
+            .partition(|(file, _)| same_feature(file, path));
+        self.pending_failures = pending;
+
+        self.features.push(FeatureRecord {
+            path: path.to_string(),
+            started: duration.and_then(|d| chrono::Duration::from_std(d).ok()).map(|d| now - d),
+            finished: now,
+            duration,
+            passed: result.passed,
+            failed: result.failed,
+            scenarios: failures.into_iter().map(|(_, record)| record).collect(),
+        });
+    }
+
+    /// Fill in scenario details from cucumber reports
+    ///
+    /// Features not seen live (e.g. the summary line was filtered) are added.
+    pub fn apply_report(&mut self, reports: &[FeatureReport]) {
+        for report in reports {
+            let scenarios = scenario_records(report);
+            match self
+                .features
+                .iter_mut()
+                .find(|f| same_feature(&f.path, &report.uri))
+            {
+                Some(feature) => feature.scenarios = scenarios,
+                None => {
+                    let duration = scenarios.iter().filter_map(|s| s.duration).sum();
+                    let count = |status| scenarios.iter().filter(|s| s.status == status).count() as u32;
+                    self.features.push(FeatureRecord {
+                        path: report.uri.clone(),
+                        started: scenarios.iter().filter_map(|s| s.started).min(),
+                        finished: scenarios
+                            .iter()
+                            .filter_map(|s| s.finished)
+                            .max()
+                            .unwrap_or_else(Utc::now),
+                        duration: Some(duration),
+                        passed: count(Status::Passed),
+                        failed: count(Status::Failed),
+                        scenarios,
+                    });
+                }
+            }
+        }
+    }
+
+    /// Time the scenario of live-only features that ran just one, using the feature's summary
+    ///
+    /// Without a cucumber report Karate prints no per-scenario times; the feature's time
+    /// (which includes its background) is the closest there is.
+    pub fn time_single_scenarios(&mut self, parsed: &[Feature]) {
+        let live_only = |f: &FeatureRecord| {
+            f.passed + f.failed == 1 && f.scenarios.iter().all(|s| s.duration.is_none())
+        };
+        for record in self.features.iter_mut().filter(|f| live_only(f)) {
+            let Some(feature) = parsed
+                .iter()
+                .find(|f| same_feature(&f.path.to_string_lossy(), &record.path))
+                .filter(|f| f.run_count() == 1)
+            else {
+                continue;
+            };
+            let Some(scenario) = feature.runnable().next() else {
+                continue;
+            };
+            let failing_step = record.scenarios.iter().find_map(|s| s.failing_step.clone());
+            record.scenarios = vec![ScenarioRecord {
+                name: scenario.name.clone(),
+                line: scenario.line,
+                tags: feature.tags.iter().chain(&scenario.tags).cloned().collect(),
+                example_row: scenario.outline.then_some(1),
+                started: record.started,
+                finished: Some(record.finished),
+                duration: record.duration,
+                status: if record.failed > 0 { Status::Failed } else { Status::Passed },
+                failing_step,
+            }];
+        }
+    }
+
+    /// Scenarios with a known duration, slowest first
+    pub fn slowest(&self, n: usize) -> Vec<(&FeatureRecord, &ScenarioRecord)> {
+        let mut timed: Vec<_> = self
+            .features
+            .iter()
+            .flat_map(|f| f.scenarios.iter().map(move |s| (f, s)))
+            .filter(|(_, s)| s.duration.is_some())
+            .collect();
+        timed.sort_by_key(|(_, s)| std::cmp::Reverse(s.duration));
+        timed.truncate(n);
+        timed
+    }
+
+    /// Per-feature breakdown and the slowest scenarios
+    pub fn print_report(&self, theme: &Theme, breakdown: bool, slowest: usize) {
+        if breakdown {
+            self.print_breakdown(theme);
+        }
+
+        let palette = &theme.palette;
+        let slowest = self.slowest(slowest);
+        if slowest.is_empty() {
+            return;
+        }
+        println!();
+        println!(
+            "{}",
+            palette.heading.paint(&Theme::prefixed(&theme.prefixes.banner, "Slowest Scenarios"))
+        );
+        println!("{}", palette.muted.paint(&theme.rule(40)));
+        for (feature, scenario) in slowest {
+            let row = scenario
+                .example_row
+                .map(|row| format!(" [{}]", row))
+                .unwrap_or_default();
+            println!(
+                "  {:>7}  {}{} {}{} {}",
+                palette
+                    .warn
+                    .paint(&scenario.duration.map(format_duration).unwrap_or_default()),
+                palette.text.paint(feature.short_name()),
+                palette.muted.paint(&format!(":{}", scenario.line)),
+                scenario.name,
+                row,
+                palette.muted.paint(&scenario.tags.join(" "))
+            );
+        }
+    }
+
+    fn print_breakdown(&self, theme: &Theme) {
+        if self.features.is_empty() {
+            return;
+        }
+        let palette = &theme.palette;
+        let name_width = self.features.iter().map(|f| f.short_name().len()).max().unwrap_or(0);
+
+        println!();
+        println!(
+            "{}",
+            palette.heading.paint(&Theme::prefixed(&theme.prefixes.banner, "Features"))
+        );
+        println!("{}", palette.muted.paint(&theme.rule(40)));
+        for feature in &self.features {
+            let marker = if feature.failed > 0 {
+                palette.error.paint(&theme.borders.fail_marker)
+            } else {
+                palette.success.paint(&theme.borders.pass_marker)
+            };
+            let failed = if feature.failed > 0 {
+                palette.error_strong.paint(&format!(", {} failed", feature.failed)).to_string()
+            } else {
+                String::new()
+            };
+            let clock = |t: DateTime<Utc>| t.with_timezone(&Local).format("%H:%M:%S").to_string();
+            let span = match feature.started {
+                Some(started) => format!("{}-{}", clock(started), clock(feature.finished)),
+                None => clock(feature.finished),
+            };
+            println!(
+                "  {} {:<width$}  {} passed{}  {} {}",
+                marker,
+                feature.short_name(),
+                feature.passed,
+                failed,
+                palette
+                    .muted
+                    .paint(&feature.duration.map(format_duration).unwrap_or_default()),
+                palette.muted.paint(&format!("({})", span)),
+                width = name_width
+            );
+            for scenario in feature.scenarios.iter().filter(|s| s.status == Status::Failed) {
+                println!(
+                    "      {} {} {}",
+                    palette.muted.paint(&format!(":{}", scenario.line)),
+                    scenario.name,
+                    palette.error.paint(scenario.failing_step.as_deref().unwrap_or_default())
+                );
+            }
+        }
+    }
+}
+
+fn scenario_records(report: &FeatureReport) -> Vec<ScenarioRecord> {
+    let mut rows: HashMap<&str, usize> = HashMap::new();
+    report
+        .scenarios
+        .iter()
+        .map(|scenario| {
+            let duration = scenario.duration();
+            ScenarioRecord {
+                name: scenario.name.clone(),
+                line: scenario.line,
+                tags: report.tags.iter().chain(&scenario.tags).cloned().collect(),
+                example_row: scenario.outline.then(|| {
+                    let row = rows.entry(&scenario.name).or_default();
+                    *row += 1;
+                    *row
+                }),
+                started: scenario.started,
+                finished: scenario
+                    .started
+                    .zip(chrono::Duration::from_std(duration).ok())
+                    .map(|(start, d)| start + d),
+                duration: Some(duration),
+                status: scenario.status(),
+                failing_step: scenario
+                    .failing_step()
+                    .map(|s| format!("{} {}", s.keyword, s.name)),
+            }
+        })
+        .collect()
+}
+
+/// The parsed scenario a reported `file:line` falls in, with its feature
+pub fn find_scenario<'a>(features: &'a [Feature], file: &str, line: u32) -> Option<(&'a Feature, &'a Scenario)> {
+    features
+        .iter()
+        .filter(|f| same_feature(&f.path.to_string_lossy(), file))
+        .find_map(|f| f.scenario_at(line).map(|s| (f, s)))
+}
+
+/// Whether two feature references (`classpath:a/b.feature`, `/tests/a/b.feature`) name the same file
+///
+/// Whole path components are compared, so `users.feature` does not match `admin_users.feature`.
+pub fn same_feature(a: &str, b: &str) -> bool {
+    // `/`, `./` and `../` say where the path starts, not which file it is
+    let components = |path: &str| -> PathBuf {
+        Path::new(path.trim().trim_start_matches("classpath:"))
+            .components()
+            .filter(|c| matches!(c, Component::Normal(_)))
+            .collect()
+    };
+    let (a, b) = (components(a), components(b));
+    !a.as_os_str().is_empty() && !b.as_os_str().is_empty() && (a.ends_with(&b) || b.ends_with(&a))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cucumber::CucumberReport;
+
+    fn result(passed: u32, failed: u32, time: f64) -> KarateTestResult {
+        KarateTestResult {
+            total_scenarios: passed + failed,
+            passed,
+            failed,
+            time_secs: Some(time),
+        }
+    }
+
+    #[test]
+    fn test_live_features_with_failure_locations() {
+        let mut model = RunModel::new();
+        let now = Utc::now();
+        let features = [crate::gherkin::parse(
+            Path::new("/tests/features/orders.feature"),
+            "Feature: orders\n@smoke\nScenario: place order\n* url base\n* method post\n",
+        )
+        .unwrap()];
+        model.failure_at("classpath:features/orders.feature", 5, None, &features);
+        model.failure_at("classpath:features/orders.feature", 5, Some("* method post"), &features);
+        model.feature_finished("classpath:features/users.feature", &result(2, 0, 0.5), now);
+        model.feature_finished("classpath:features/orders.feature", &result(1, 1, 1.5), now);
+
+        assert_eq!(model.features.len(), 2);
+        assert!(model.features[0].scenarios.is_empty());
+        let failed = &model.features[1].scenarios;
+        assert_eq!(failed.len(), 1);
+        assert_eq!((failed[0].line, failed[0].name.as_str()), (5, "place order"));
+        assert_eq!(failed[0].tags, vec!["@smoke"]);
+        assert_eq!(failed[0].failing_step.as_deref(), Some("* method post"));
+        assert_eq!(
+            model.features[1].started,
+            Some(now - chrono::Duration::milliseconds(1500))
+        );
+
+        // A summary before any feature line names no feature
+        model.feature_finished("", &result(1, 0, 0.2), now);
+        assert_eq!(model.features.len(), 2);
+    }
+
+    #[test]
+    fn test_single_scenario_features_are_timed_live() {
+        let mut model = RunModel::new();
+        let now = Utc::now();
+        model.feature_finished("classpath:features/health.feature", &result(1, 0, 2.5), now);
+        model.feature_finished("classpath:features/users.feature", &result(2, 0, 0.5), now);
+
+        let parse = |path: &str, content: &str| crate::gherkin::parse(Path::new(path), content).unwrap();
+        let parsed = vec![
+            parse(
+                "/app/features/health.feature",
+                "@ops\nFeature: health\n\n  Scenario: ping\n    * print 'a'\n\n  @ignore\n  Scenario: helper\n    * print 'b'\n",
+            ),
+            parse(
+                "/app/features/users.feature",
+                "Feature: users\n  Scenario: a\n    * print 'a'\n  Scenario: b\n    * print 'b'\n",
+            ),
+        ];
+        model.time_single_scenarios(&parsed);
+
+        let slowest: Vec<(&str, u32, Vec<String>)> = model
+            .slowest(5)
+            .iter()
+            .map(|(_, s)| (s.name.as_str(), s.line, s.tags.clone()))
+            .collect();
+        assert_eq!(slowest, vec![("ping", 4, vec!["@ops".to_string()])]);
+        assert_eq!(model.features[0].scenarios[0].duration, Some(Duration::from_millis(2500)));
+    }
+
+    #[test]
+    fn test_report_fills_scenarios_and_example_rows() {
+        let mut model = RunModel::new();
+        model.feature_finished("features/users.feature", &result(3, 0, 0.9), Utc::now());
+        let report = CucumberReport::parse(
+            r#"[{"uri": "classpath:features/users.feature", "tags": [{"name": "@users"}], "elements": [
+                {"type": "scenario", "keyword": "Scenario Outline", "name": "create", "line": 12, "steps": [
+                    {"line": 5, "result": {"status": "passed", "duration": 300000000}}]},
+                {"type": "scenario", "keyword": "Scenario Outline", "name": "create", "line": 13, "steps": [
+                    {"line": 5, "result": {"status": "passed", "duration": 500000000}}]},
+                {"type": "scenario", "keyword": "Scenario", "name": "list", "line": 20, "steps": [
+                    {"line": 21, "result": {"status": "passed", "duration": 100000000}}]}
+            ]}]"#,
+        )
+        .unwrap();
+        model.apply_report(&report);
+
+        let slowest: Vec<(u32, Option<usize>)> = model
+            .slowest(2)
+            .iter()
+            .map(|(_, s)| (s.line, s.example_row))
+            .collect();
+        assert_eq!(slowest, vec![(13, Some(2)), (12, Some(1))]);
+        assert_eq!(model.features[0].scenarios[2].tags, vec!["@users"]);
+    }
+
+    #[test]
+    fn test_same_feature_compares_components() {
+        assert!(same_feature("../tests/users.feature", "/app/tests/users.feature"));
+        assert!(same_feature("classpath:features/users.feature", "features/users.feature"));
+        assert!(!same_feature("users.feature", "features/admin_users.feature"));
+        assert!(!same_feature("", "users.feature"));
+    }
+}
@@ -0,0 +1,327 @@
+//! API log line formats (JSON, logfmt, regex) and their mapping onto `ApiLogEntry`
+
+use crate::config::SchemaConfig;
+use crate::log_parser::{duration_unit_ms, ApiLogEntry};
+use regex::Regex;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+
+/// Values accepted for `schema.format`
+pub const LINE_FORMATS: &[&str] = &["auto", "json", "logfmt", "regex"];
+
+/// `ApiLogEntry` fields a schema can map to
+pub const ENTRY_FIELDS: &[&str] = &[
+    "time",
+    "level",
+    "msg",
+    "request_id",
+    "uri",
+    "method",
+    "status",
+    "latency_human",
+    "sql",
+    "elapsed",
+    "rows_affected",
+    "err",
+    "func",
+    "office_id",
+    "user_id",
+    "request_body",
+    "response_body",
+];
+
+/// Units accepted in `duration_units`
+pub const DURATION_UNITS: &[&str] = &["ns", "us", "µs", "μs", "ms", "s"];
+
+/// Text fields; numbers and booleans in them are converted to strings
+const TEXT_FIELDS: &[&str] = &[
+    "time",
+    "level",
+    "msg",
+    "request_id",
+    "uri",
+    "method",
+    "latency_human",
+    "sql",
+    "elapsed",
+    "err",
+    "func",
+];
+
+/// Integer fields; numeric strings in them are converted to numbers
+const INTEGER_FIELDS: &[&str] = &["status", "rows_affected", "office_id", "user_id"];
+
+/// How API log lines are encoded
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum LineFormat {
+    /// Per line: JSON objects, then the pattern, then logfmt
+    #[default]
+    Auto,
+    Json,
+    Logfmt,
+    Regex,
+}
+
+impl LineFormat {
+    pub fn from_str(s: &str) -> Self {
+        match s.to_lowercase().as_str() {
+            "json" => Self::Json,
+            "logfmt" => Self::Logfmt,
+            "regex" => Self::Regex,
+            _ => Self::Auto,
+        }
+    }
+}
+
+/// Reads API log lines in the configured format, then renames fields, maps level values
+/// and converts numeric durations before an entry is built
+#[derive(Debug, Clone, Default)]
+pub struct ApiSchema {
+    format: LineFormat,
+    /// Named groups are entry fields (or names mapped in `fields`)
+    pattern: Option<Regex>,
+    fields: Vec<(String, Vec<String>)>,
+    /// Lowercased source value -> level
+    levels: HashMap<String, String>,
+    /// Entry field -> milliseconds per unit
+    duration_units: Vec<(String, f64)>,
+}
+
+impl ApiSchema {
+    /// Build from config; invalid patterns and unknown units are reported by `config check`
+    /// and ignored here
+    pub fn new(config: &SchemaConfig) -> Self {
+        Self {
+            format: LineFormat::from_str(&config.format),
+            pattern: config.pattern.as_deref().and_then(|p| Regex::new(p).ok()),
+            fields: config
+                .fields
+                .iter()
+                .map(|(field, sources)| (field.clone(), sources.clone()))
+                .collect(),
+            levels: config
+                .levels
+                .iter()
+                .map(|(from, to)| (from.to_lowercase(), to.to_uppercase()))
+                .collect(),
+            duration_units: config
+                .duration_units
+                .iter()
+                .filter_map(|(field, unit)| Some((field.clone(), duration_unit_ms(unit)?)))
+                .collect(),
+        }
+    }
+
+    /// Whether fields are read as they are (our slog field names)
+    pub fn is_identity(&self) -> bool {
+        self.fields.is_empty() && self.levels.is_empty() && self.duration_units.is_empty()
+    }
+
+    /// Parse a log line in the configured format, applying the mapping
+    pub fn parse(&self, line: &str) -> Option<ApiLogEntry> {
+        let trimmed = line.trim();
+        let json = trimmed.starts_with('{');
+        if json && self.is_identity() && matches!(self.format, LineFormat::Auto | LineFormat::Json) {
+            return ApiLogEntry::parse(line);
+        }
+
+        let mut object = match self.format {
+            LineFormat::Json => json_object(trimmed)?,
+            LineFormat::Logfmt => parse_logfmt(trimmed)?,
+            LineFormat::Regex => self.pattern_object(trimmed)?,
+            LineFormat::Auto if json => json_object(trimmed)?,
+            LineFormat::Auto => self
+                .pattern_object(trimmed)
+                .or_else(|| parse_logfmt(trimmed))?,
+        };
+        self.apply(&mut object);
+        ApiLogEntry::from_value(Value::Object(object))
+    }
+
+    /// Named groups of the pattern that took part in the match
+    fn pattern_object(&self, line: &str) -> Option<Map<String, Value>> {
+        let pattern = self.pattern.as_ref()?;
+        let caps = pattern.captures(line)?;
+        Some(
+            pattern
+                .capture_names()
+                .flatten()
+                .filter_map(|name| Some((name.to_string(), Value::String(caps.name(name)?.as_str().to_string()))))
+                .collect(),
+        )
+    }
+
+    fn apply(&self, object: &mut Map<String, Value>) {
+        // Aliases fill entry fields the line does not have under their own name
+        for (field, sources) in &self.fields {
+            if object.get(field).is_some_and(|v| !v.is_null()) {
+                continue;
+            }
+            if let Some(value) = sources.iter().find_map(|source| object.remove(source)) {
+                object.insert(field.clone(), value);
+            }
+        }
+
+        // Unmapped levels stay as logged; `LogLevel` reads them case-insensitively
+        if let Some(level) = object.get_mut("level").filter(|_| !self.levels.is_empty()) {
+            let raw = match &*level {
+                Value::String(s) => s.clone(),
+                other => other.to_string(),
+            };
+            if let Some(mapped) = self.levels.get(&raw.to_lowercase()) {
+                *level = Value::String(mapped.clone());
+            }
+        }
+
+        for (field, ms_per_unit) in &self.duration_units {
+            let number = match object.get(field) {
+                Some(Value::Number(n)) => n.as_f64(),
+                Some(Value::String(s)) => s.trim().parse::<f64>().ok(),
+                _ => None,
+            };
+            if let Some(number) = number {
+                object.insert(field.clone(), Value::String(format!("{}ms", number * ms_per_unit)));
+            }
+        }
+
+        // Other loggers disagree on types; coerce to what the entry expects
+        for field in TEXT_FIELDS {
+            if let Some(value @ (Value::Number(_) | Value::Bool(_))) = object.get_mut(*field) {
+                *value = Value::String(value.to_string());
+            }
+        }
+        for field in INTEGER_FIELDS {
+            if let Some(value) = object.get_mut(*field) {
+                if let Some(number) = value.as_str().and_then(|s| s.trim().parse::<i64>().ok()) {
+                    *value = Value::from(number);
+                }
+            }
+        }
+    }
+}
+
+fn json_object(line: &str) -> Option<Map<String, Value>> {
+    match serde_json::from_str(line).ok()? {
+        Value::Object(object) => Some(object),
+        _ => None,
+    }
+}
+
+/// Parse `level=info msg="user created" request_id=abc`
+///
+/// Every token must be a `key=value` pair, and there must be at least two, so
+/// free text such as startup banners is not mistaken for logfmt.
+pub fn parse_logfmt(line: &str) -> Option<Map<String, Value>> {
+    let mut object = Map::new();
+    let mut chars = line.chars().peekable();
+    loop {
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+        if chars.peek().is_none() {
+            break;
+        }
+
+        let mut key = String::new();
+        while let Some(c) = chars.next_if(|c| *c != '=' && !c.is_whitespace()) {
+            key.push(c);
+        }
+        let valid_key = !key.is_empty()
+            && key.chars().all(|c| c.is_alphanumeric() || matches!(c, '_' | '.' | '-'));
+        if !valid_key || chars.next() != Some('=') {
+            return None;
+        }
+
+        let mut value = String::new();
+        if chars.next_if_eq(&'"').is_some() {
+            loop {
+                match chars.next()? {
+                    '"' => break,
+                    '\\' => value.push(chars.next()?),
+                    c => value.push(c),
+                }
+            }
+        } else {
+            while let Some(c) = chars.next_if(|c| !c.is_whitespace()) {
+                value.push(c);
+            }
+        }
+        object.insert(key, Value::String(value));
+    }
+    (object.len() >= 2).then_some(object)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::log_parser::LogLevel;
+
+    fn schema(toml: &str) -> ApiSchema {
+        ApiSchema::new(&toml::from_str(toml).unwrap())
+    }
+
+    #[test]
+    fn test_zap_style_entry() {
+        let zap = schema(
+            r#"
+fields = { time = ["ts"], msg = ["message"], request_id = ["req_id"], latency_human = ["duration"] }
+duration_units = { latency_human = "ns" }
+"#,
+        );
+        let entry = zap
+            .parse(r#"{"level":"error","ts":1718000000.5,"message":"REQUEST","req_id":42,"uri":"/x","status":"500","duration":1500000}"#)
+            .unwrap();
+        assert_eq!(entry.level, "error");
+        assert_eq!(entry.log_level(), LogLevel::Error);
+        assert_eq!(entry.msg, "REQUEST");
+        assert_eq!(entry.request_id.as_deref(), Some("42"));
+        assert_eq!(entry.time.as_deref(), Some("1718000000.5"));
+        assert_eq!(entry.status, Some(500));
+        assert_eq!(entry.latency_human.as_deref(), Some("1.5ms"));
+        assert!(entry.is_request_summary());
+    }
+
+    #[test]
+    fn test_logfmt_and_regex_lines() {
+        let auto = ApiSchema::default();
+        let entry = auto
+            .parse(r#"time=2025-01-01T10:00:00Z level=info msg="SQL" sql="SELECT \"id\" FROM users" elapsed=1.2ms rows_affected=3 request_id=r1"#)
+            .unwrap();
+        assert_eq!((entry.level.as_str(), entry.msg.as_str()), ("info", "SQL"));
+        assert_eq!(entry.sql.as_deref(), Some(r#"SELECT "id" FROM users"#));
+        assert_eq!(entry.rows_affected, Some(3));
+        assert!(auto.parse("⇨ http server started on [::]:1323").is_none());
+        assert!(auto.parse("level=info unterminated=\"x").is_none());
+
+        let plain = schema(
+            r#"
+format = "regex"
+pattern = '^(?P<time>\S+) \[(?P<level>\w+)\] (?P<req_id>\S+) (?P<method>[A-Z]+) (?P<uri>\S+) (?P<status>\d+)$'
+fields = { request_id = ["req_id"] }
+"#,
+        );
+        let entry = plain
+            .parse("2025-01-01T10:00:00Z [warn] r2 GET /api/v1/users 404")
+            .unwrap();
+        assert_eq!(entry.log_level(), LogLevel::Warn);
+        assert_eq!(entry.request_id.as_deref(), Some("r2"));
+        assert_eq!((entry.uri.as_deref(), entry.status), (Some("/api/v1/users"), Some(404)));
+        assert!(plain.parse(r#"{"level":"INFO","msg":"json"}"#).is_none());
+    }
+
+    #[test]
+    fn test_level_values_and_own_names_win() {
+        let bunyan = schema(
+            r#"
+fields = { msg = ["message"] }
+levels = { "30" = "info", "50" = "error" }
+"#,
+        );
+        let entry = bunyan
+            .parse(r#"{"level":50,"msg":"kept","message":"ignored"}"#)
+            .unwrap();
+        assert_eq!((entry.level.as_str(), entry.msg.as_str()), ("ERROR", "kept"));
+        let unmapped = bunyan.parse(r#"{"level":"fatal","msg":"x"}"#).unwrap();
+        assert_eq!(unmapped.level, "fatal");
+        assert!(bunyan.parse("not json").is_none());
+        assert!(ApiSchema::default().is_identity());
+    }
+}
@@ -0,0 +1,361 @@
+//! SQL statement formatting and syntax highlighting for the terminal
+
+use crate::theme::{Palette, Theme};
+
+/// Keywords that start a new line at the statement's base indentation
+const CLAUSE_KEYWORDS: &[&str] = &[
+    "SELECT", "FROM", "WHERE", "GROUP BY", "ORDER BY", "HAVING", "LIMIT", "OFFSET", "VALUES",
+    "SET", "INSERT INTO", "UPDATE", "DELETE FROM", "UNION ALL", "UNION", "RETURNING",
+    "ON DUPLICATE KEY UPDATE", "LEFT OUTER JOIN", "RIGHT OUTER JOIN", "LEFT JOIN", "RIGHT JOIN",
+    "INNER JOIN", "CROSS JOIN", "JOIN", "FOR UPDATE",
+];
+
+/// Keywords that start a new, further indented line (conditions)
+const CONDITION_KEYWORDS: &[&str] = &["AND", "OR"];
+
+/// Other keywords that are only highlighted
+const KEYWORDS: &[&str] = &[
+    "AS", "ON", "IN", "IS", "NOT", "NULL", "LIKE", "BETWEEN", "EXISTS", "CASE", "WHEN", "THEN",
+    "ELSE", "END", "DISTINCT", "ASC", "DESC", "BY", "INTO", "COUNT", "SUM", "MIN", "MAX", "AVG",
+    "COALESCE", "IFNULL", "TRUE", "FALSE", "DEFAULT", "BEGIN", "COMMIT", "ROLLBACK", "OUTER",
+    "LEFT", "RIGHT", "INNER", "CROSS", "GROUP", "ORDER", "UNION", "ALL", "DUPLICATE", "KEY",
+    "FOR", "INSERT", "DELETE", "REPLACE", "WITH", "NOW",
+];
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token<'a> {
+    Whitespace,
+    Word(&'a str),
+    QuotedIdent(&'a str),
+    StringLit(&'a str),
+    Number(&'a str),
+    Placeholder(&'a str),
+    Comment(&'a str),
+    Punct(&'a str),
+}
+
+/// Split a statement into tokens
+fn tokenize(sql: &str) -> Vec<Token<'_>> {
+    let bytes = sql.as_bytes();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let start = i;
+        let c = bytes[i];
+
+        if c.is_ascii_whitespace() {
+            while i < bytes.len() && bytes[i].is_ascii_whitespace() {
+                i += 1;
+            }
+            tokens.push(Token::Whitespace);
+        } else if c == b'\'' {
+            i += 1;
+            while i < bytes.len() {
+                if bytes[i] == b'\\' {
+                    i += 2;
+                    continue;
+                }
+                if bytes[i] == b'\'' {
+                    // '' is an escaped quote
+                    if i + 1 < bytes.len() && bytes[i + 1] == b'\'' {
+                        i += 2;
+                        continue;
+                    }
+                    i += 1;
+                    break;
+                }
+                i += 1;
+            }
+            i = i.min(bytes.len());
+            tokens.push(Token::StringLit(&sql[start..i]));
+        } else if c == b'`' || c == b'"' {
+            i += 1;
+            while i < bytes.len() && bytes[i] != c {
+                i += 1;
+            }
+            i = (i + 1).min(bytes.len());
+            tokens.push(Token::QuotedIdent(&sql[start..i]));
+        } else if c == b'-' && bytes.get(i + 1) == Some(&b'-') {
+            while i < bytes.len() && bytes[i] != b'\n' {
+                i += 1;
+            }
+            tokens.push(Token::Comment(&sql[start..i]));
+        } else if c == b'/' && bytes.get(i + 1) == Some(&b'*') {
+            i += 2;
+            while i + 1 < bytes.len() && !(bytes[i] == b'*' && bytes[i + 1] == b'/') {
+                i += 1;
+            }
+            i = (i + 2).min(bytes.len());
+            tokens.push(Token::Comment(&sql[start..i]));
+        } else if c == b'?' {
+            i += 1;
+            tokens.push(Token::Placeholder(&sql[start..i]));
+        } else if (c == b'$' || c == b':') && bytes.get(i + 1).is_some_and(|b| b.is_ascii_alphanumeric()) {
+            i += 1;
+            while i < bytes.len() && (bytes[i].is_ascii_alphanumeric() || bytes[i] == b'_') {
+                i += 1;
+            }
+            tokens.push(Token::Placeholder(&sql[start..i]));
+        } else if c.is_ascii_digit() {
+            while i < bytes.len() && (bytes[i].is_ascii_digit() || bytes[i] == b'.') {
+                i += 1;
+            }
+            tokens.push(Token::Number(&sql[start..i]));
+        } else if c.is_ascii_alphabetic() || c == b'_' || c >= 0x80 {
+            while i < bytes.len()
+                && (bytes[i].is_ascii_alphanumeric() || bytes[i] == b'_' || bytes[i] == b'.' || bytes[i] >= 0x80)
+            {
+                i += 1;
+            }
+            tokens.push(Token::Word(&sql[start..i]));
+        } else {
+            // Multi-character operators
+            let two = sql.get(i..i + 2).unwrap_or("");
+            i += if matches!(two, "<=" | ">=" | "<>" | "!=" | "||" | "::") { 2 } else { 1 };
+            tokens.push(Token::Punct(&sql[start..i]));
+        }
+    }
+
+    tokens
+}
+
+/// Rendering options for SQL statements
+#[derive(Debug, Clone)]
+pub struct SqlRenderOptions {
+    /// Spaces per indentation level
+    pub indent: usize,
+    /// In compact mode, statements longer than this are cut
+    pub compact_max_len: usize,
+}
+
+/// Formats and highlights SQL statements
+pub struct SqlRenderer<'a> {
+    palette: &'a Palette,
+    ellipsis: &'a str,
+    options: &'a SqlRenderOptions,
+}
+
+impl<'a> SqlRenderer<'a> {
+    pub fn new(theme: &'a Theme, options: &'a SqlRenderOptions) -> Self {
+        Self {
+            palette: &theme.palette,
+            ellipsis: &theme.borders.ellipsis,
+            options,
+        }
+    }
+
+    /// Render a statement across multiple lines, each prefixed with `base_indent`
+    pub fn render_pretty(&self, sql: &str, base_indent: &str) -> String {
+        let tokens = tokenize(sql.trim());
+        let mut lines: Vec<(usize, String)> = vec![(0, String::new())];
+        let mut depth = 0usize;
+        let mut i = 0;
+
+        while i < tokens.len() {
+            if let Some((keyword, consumed)) = match_keyword(&tokens[i..], CLAUSE_KEYWORDS) {
+                if !lines.last().is_some_and(|(_, l)| l.is_empty()) {
+                    lines.push((depth, String::new()));
+                } else if let Some(last) = lines.last_mut() {
+                    last.0 = depth;
+                }
+                self.push_word(lines.last_mut(), &self.paint_keyword(&keyword));
+                i += consumed;
+                continue;
+            }
+            if let Some((keyword, consumed)) = match_keyword(&tokens[i..], CONDITION_KEYWORDS) {
+                lines.push((depth + 1, String::new()));
+                self.push_word(lines.last_mut(), &self.paint_keyword(&keyword));
+                i += consumed;
+                continue;
+            }
+
+            match &tokens[i] {
+                Token::Whitespace => {}
+                Token::Punct("(") => {
+                    self.push_word(lines.last_mut(), &self.palette.muted.paint("(").to_string());
+                    depth += 1;
+                }
+                Token::Punct(")") => {
+                    depth = depth.saturating_sub(1);
+                    if let Some((_, line)) = lines.last_mut() {
+                        line.push_str(&self.palette.muted.paint(")").to_string());
+                    }
+                }
+                Token::Punct(",") => {
+                    if let Some((_, line)) = lines.last_mut() {
+                        line.push_str(&self.palette.muted.paint(",").to_string());
+                    }
+                }
+                token => {
+                    let painted = self.paint_token(token);
+                    self.push_word(lines.last_mut(), &painted);
+                }
+            }
+            i += 1;
+        }
+
+        lines
+            .into_iter()
+            .filter(|(_, line)| !line.is_empty())
+            .map(|(depth, line)| {
+                format!("{}{}{}", base_indent, " ".repeat(depth * self.options.indent), line)
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Render a statement on a single line, truncated to the compact length
+    pub fn render_compact(&self, sql: &str) -> String {
+        let collapsed = sql.split_whitespace().collect::<Vec<_>>().join(" ");
+        let (text, truncated) = if collapsed.chars().count() > self.options.compact_max_len {
+            let cut: String = collapsed.chars().take(self.options.compact_max_len).collect();
+            (cut, true)
+        } else {
+            (collapsed, false)
+        };
+
+        let mut out = String::new();
+        for token in tokenize(&text) {
+            match token {
+                Token::Whitespace => out.push(' '),
+                token => out.push_str(&self.paint_token(&token)),
+            }
+        }
+        if truncated {
+            out.push_str(&self.palette.muted.paint(self.ellipsis).to_string());
+        }
+        out
+    }
+
+    fn push_word(&self, line: Option<&mut (usize, String)>, word: &str) {
+        if let Some((_, line)) = line {
+            if !line.is_empty() && !line.ends_with('(') && !ends_with_painted(line, "(") {
+                line.push(' ');
+            }
+            line.push_str(word);
+        }
+    }
+
+    fn paint_keyword(&self, keyword: &str) -> String {
+        self.palette.sql.bold().paint(keyword).to_string()
+    }
+
+    fn paint_token(&self, token: &Token) -> String {
+        let palette = self.palette;
+        match token {
+            Token::Whitespace => " ".to_string(),
+            Token::Word(w) => {
+                if is_keyword(w) {
+                    self.paint_keyword(w)
+                } else {
+                    palette.text.paint(w).to_string()
+                }
+            }
+            Token::QuotedIdent(w) => palette.text.paint(w).to_string(),
+            Token::StringLit(w) => palette.body.paint(w).to_string(),
+            Token::Number(w) => palette.highlight.paint(w).to_string(),
+            Token::Placeholder(w) => palette.accent.paint(w).to_string(),
+            Token::Comment(w) => palette.muted.paint(w).to_string(),
+            Token::Punct(w) => palette.muted.paint(w).to_string(),
+        }
+    }
+}
+
+/// Whether the line ends with `text`, ignoring a trailing ANSI reset sequence
+fn ends_with_painted(line: &str, text: &str) -> bool {
+    line.trim_end_matches("\u{1b}[0m").ends_with(text)
+}
+
+fn is_keyword(word: &str) -> bool {
+    let upper = word.to_uppercase();
+    KEYWORDS.contains(&upper.as_str())
+        || CLAUSE_KEYWORDS.contains(&upper.as_str())
+        || CONDITION_KEYWORDS.contains(&upper.as_str())
+}
+
+/// Match a (possibly multi-word) keyword at the start of the token stream.
+/// Returns the keyword as written and the number of tokens consumed.
+fn match_keyword(tokens: &[Token], keywords: &[&str]) -> Option<(String, usize)> {
+    for keyword in keywords {
+        let mut consumed = 0;
+        let mut words = Vec::new();
+        let mut matched = true;
+
+        for part in keyword.split(' ') {
+            while let Some(Token::Whitespace) = tokens.get(consumed) {
+                if words.is_empty() {
+                    break;
+                }
+                consumed += 1;
+            }
+            match tokens.get(consumed) {
+                Some(Token::Word(w)) if w.eq_ignore_ascii_case(part) => {
+                    words.push(*w);
+                    consumed += 1;
+                }
+                _ => {
+                    matched = false;
+                    break;
+                }
+            }
+        }
+
+        if matched {
+            return Some((words.join(" "), consumed));
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options() -> SqlRenderOptions {
+        SqlRenderOptions {
+            indent: 2,
+            compact_max_len: 30,
+        }
+    }
+
+    /// Rendered text without colors, whatever the terminal supports
+    fn plain(out: &str) -> String {
+        regex::Regex::new(r"\x1b\[[0-9;]*m").unwrap().replace_all(out, "").into_owned()
+    }
+
+    #[test]
+    fn test_pretty_breaks_clauses() {
+        let theme = Theme::default();
+        let options = options();
+        let renderer = SqlRenderer::new(&theme, &options);
+        let out = plain(&renderer.render_pretty(
+            "select id, name from patients where office_id = ? and name like 'a%' order by id",
+            "",
+        ));
+        assert_eq!(
+            out,
+            "select id, name\nfrom patients\nwhere office_id = ?\n  and name like 'a%'\norder by id"
+        );
+    }
+
+    #[test]
+    fn test_compact_truncates() {
+        let theme = Theme::default();
+        let options = options();
+        let renderer = SqlRenderer::new(&theme, &options);
+        let out = plain(&renderer.render_compact("SELECT *\n  FROM karte WHERE patient_id = 1 AND deleted_at IS NULL"));
+        assert_eq!(out, "SELECT * FROM karte WHERE pati…");
+
+        let ascii = Theme::ascii();
+        let renderer = SqlRenderer::new(&ascii, &options);
+        assert!(plain(&renderer.render_compact("SELECT * FROM karte WHERE patient_id = 1")).ends_with("pati..."));
+    }
+
+    #[test]
+    fn test_tokenize_literals() {
+        let tokens = tokenize("x = 'it''s' AND y = $1");
+        assert!(tokens.contains(&Token::StringLit("'it''s'")));
+        assert!(tokens.contains(&Token::Placeholder("$1")));
+    }
+}
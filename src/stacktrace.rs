@@ -0,0 +1,358 @@
+//! Go panics and goroutine stack traces, folded from many output lines into one event
+
+use crate::log_parser::ApiLogEntry;
+use serde_json::json;
+
+/// One function in a goroutine trace
+#[derive(Debug, Clone, PartialEq)]
+pub struct StackFrame {
+    /// e.g. `main.(*Handler).GetUser`
+    pub function: String,
+    /// e.g. `/app/handler.go:42`
+    pub location: Option<String>,
+}
+
+/// A panic, fatal error or bare goroutine dump
+#[derive(Debug, Clone, Default)]
+pub struct StackTrace {
+    /// `panic`, `fatal error` or `stack trace`
+    pub kind: String,
+    pub message: String,
+    /// e.g. `goroutine 42 [running]`
+    pub goroutine: Option<String>,
+    pub frames: Vec<StackFrame>,
+    /// The lines as printed
+    pub lines: Vec<String>,
+    /// The request being handled when it happened, when known
+    pub request_id: Option<String>,
+}
+
+impl StackTrace {
+    /// Where it happened: the first frame after the `panic()` call outside the Go runtime
+    pub fn origin(&self) -> Option<&StackFrame> {
+        let after_panic = self
+            .frames
+            .iter()
+            .rposition(|f| f.function == "panic")
+            .map_or(0, |i| i + 1);
+        self.frames[after_panic..]
+            .iter()
+            .find(|f| !f.function.starts_with("runtime.") && !f.function.starts_with("runtime/"))
+            .or_else(|| self.frames.first())
+    }
+
+    /// `panic: runtime error: index out of range [3] with length 3`
+    pub fn headline(&self) -> String {
+        if self.message.is_empty() {
+            self.kind.clone()
+        } else {
+            format!("{}: {}", self.kind, self.message)
+        }
+    }
+
+    /// The trace as one ERROR entry, so filtering, correlation and export treat it like any log
+    pub fn to_entry(&self) -> (String, ApiLogEntry) {
+        let origin = self.origin();
+        let value = json!({
+            "time": chrono::Utc::now().to_rfc3339(),
+            "level": "ERROR",
+            "msg": self.headline(),
+            "err": self.message,
+            "func": origin.map(|f| &f.function),
+            "source": origin.and_then(|f| f.location.as_ref()),
+            "request_id": self.request_id,
+            "goroutine": self.goroutine,
+            "stack": self.lines,
+        });
+        let line = value.to_string();
+        let entry = ApiLogEntry::from_value(value).unwrap_or_default();
+        (line, entry)
+    }
+
+    /// Fold a trace embedded in a single message, e.g. a logged `debug.Stack()`
+    pub fn from_text(text: &str) -> Option<StackTrace> {
+        if !text.contains('\n') {
+            return None;
+        }
+        let mut folder = StackTraceFolder::new();
+        let mut traces = Vec::new();
+        for line in text.lines() {
+            traces.extend(folder.push(line).into_iter().filter_map(|f| match f {
+                Folded::Trace(trace) => Some(trace),
+                Folded::Line(_) => None,
+            }));
+        }
+        traces.extend(folder.finish());
+        traces.into_iter().find(|t| !t.frames.is_empty())
+    }
+}
+
+/// Output of the folder: a line to handle as before, or a completed trace
+#[derive(Debug)]
+pub enum Folded {
+    Line(String),
+    Trace(StackTrace),
+}
+
+/// Collects the lines of a panic or goroutine dump into one `StackTrace`
+///
+/// ```text
+/// panic: runtime error: invalid memory address or nil pointer dereference
+/// [signal SIGSEGV: segmentation violation code=0x1 addr=0x0 pc=0x6f2a1c]
+///
+/// goroutine 42 [running]:
+/// main.(*Handler).GetUser(0xc000123450, {0x7f2c, 0xc0001})
+///         /app/handler.go:42 +0x1c
+/// ```
+///
+/// Net/http's `http: panic serving` and Echo's `[PANIC RECOVER]` lines start a trace
+/// too. A trace ends at the first line that is not part of it, or when `finish` is called.
+#[derive(Debug, Default)]
+pub struct StackTraceFolder {
+    trace: Option<StackTrace>,
+}
+
+impl StackTraceFolder {
+    /// Lines kept per trace; deeper dumps are cut
+    const MAX_LINES: usize = 400;
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feed an output line
+    pub fn push(&mut self, line: &str) -> Vec<Folded> {
+        self.feed(line, true)
+    }
+
+    /// Feed a line that parsed as an API log entry: it never starts a trace, so a
+    /// JSON panic entry stays one entry with its own fields
+    pub fn push_entry(&mut self, line: &str) -> Vec<Folded> {
+        self.feed(line, false)
+    }
+
+    fn feed(&mut self, line: &str, can_start: bool) -> Vec<Folded> {
+        let mut out = Vec::new();
+        if let Some(trace) = self.trace.as_mut() {
+            if continues(trace, line) {
+                if trace.lines.len() < Self::MAX_LINES {
+                    trace.lines.push(line.trim_end().to_string());
+                }
+                return out;
+            }
+            out.extend(self.finish().map(Folded::Trace));
+        }
+
+        match start(line).filter(|_| can_start) {
+            Some(trace) => self.trace = Some(trace),
+            None => out.push(Folded::Line(line.to_string())),
+        }
+        out
+    }
+
+    /// Complete the open trace (call when the stream ends or goes idle)
+    pub fn finish(&mut self) -> Option<StackTrace> {
+        let mut trace = self.trace.take()?;
+        while trace.lines.last().is_some_and(|l| l.trim().is_empty()) {
+            trace.lines.pop();
+        }
+        Some(trace)
+    }
+}
+
+/// A line that begins a trace
+fn start(line: &str) -> Option<StackTrace> {
+    let trimmed = line.trim_end();
+    let (kind, message) = if let Some(message) = trimmed.strip_prefix("panic: ") {
+        ("panic", message)
+    } else if let Some(message) = trimmed.strip_prefix("fatal error: ") {
+        ("fatal error", message)
+    } else if let Some((_, rest)) = trimmed.split_once("[PANIC RECOVER] ") {
+        ("panic", rest)
+    } else if let Some((_, rest)) = trimmed.split_once("http: panic serving ") {
+        // `http: panic serving 127.0.0.1:50312: boom`
+        ("panic", rest.split_once(": ").map_or(rest, |(_, message)| message))
+    } else if goroutine_header(trimmed).is_some() {
+        ("stack trace", "")
+    } else {
+        return None;
+    };
+
+    // Echo prints the first goroutine header on the message line
+    let (message, goroutine) = match message.rfind("goroutine ") {
+        Some(i) if goroutine_header(&message[i..]).is_some() => {
+            (message[..i].trim_end(), goroutine_header(&message[i..]))
+        }
+        _ => (message, None),
+    };
+
+    Some(StackTrace {
+        kind: kind.to_string(),
+        message: message.to_string(),
+        goroutine: goroutine.or_else(|| goroutine_header(trimmed)),
+        lines: vec![trimmed.to_string()],
+        ..StackTrace::default()
+    })
+}
+
+/// Whether a line belongs to the open trace, recording frames as they appear
+fn continues(trace: &mut StackTrace, line: &str) -> bool {
+    let trimmed = line.trim();
+    if trimmed.is_empty() {
+        return true;
+    }
+
+    if let Some(goroutine) = goroutine_header(trimmed) {
+        trace.goroutine.get_or_insert(goroutine);
+        return true;
+    }
+
+    if line.starts_with(char::is_whitespace) {
+        if let Some(location) = frame_location(trimmed) {
+            if let Some(frame) = trace.frames.last_mut() {
+                frame.location.get_or_insert(location);
+            }
+            return true;
+        }
+        // `panic: a [recovered]` followed by the indented panic it caused
+        return trimmed.starts_with("panic: ");
+    }
+
+    if let Some(function) = frame_function(trimmed) {
+        trace.frames.push(StackFrame {
+            function,
+            location: None,
+        });
+        return true;
+    }
+
+    trimmed.starts_with("[signal ")
+        || trimmed.starts_with("exit status ")
+        || trimmed == "...additional frames elided..."
+        // A second panic before any frame is part of the same report
+        || (trimmed.starts_with("panic: ") && trace.frames.is_empty())
+}
+
+/// `goroutine 42 [running]` from `goroutine 42 [running]:`
+fn goroutine_header(line: &str) -> Option<String> {
+    let header = line.strip_suffix(':')?;
+    let rest = header.strip_prefix("goroutine ")?;
+    let (id, status) = rest.split_once(' ')?;
+    let valid = id.chars().all(|c| c.is_ascii_digit()) && status.starts_with('[') && status.ends_with(']');
+    valid.then(|| header.to_string())
+}
+
+/// The function of `main.(*Handler).GetUser(0xc000123450, {0x7f2c, 0xc0001})`
+/// or `created by net/http.(*Server).Serve in goroutine 1`
+fn frame_function(line: &str) -> Option<String> {
+    let name = match line.strip_prefix("created by ") {
+        Some(rest) => rest.split(" in goroutine ").next()?.trim(),
+        None => {
+            // The argument list is the last balanced parenthesis group
+            if !line.ends_with(')') {
+                return None;
+            }
+            let mut depth = 0;
+            let mut open = None;
+            for (i, c) in line.char_indices().rev() {
+                match c {
+                    ')' => depth += 1,
+                    '(' => depth -= 1,
+                    _ => {}
+                }
+                if depth == 0 {
+                    open = Some(i);
+                    break;
+                }
+            }
+            &line[..open?]
+        }
+    };
+    let valid = !name.is_empty()
+        && !name.contains(char::is_whitespace)
+        && (name.contains('.') || name == "panic");
+    valid.then(|| name.to_string())
+}
+
+/// `/app/handler.go:42` from `/app/handler.go:42 +0x1c`
+fn frame_location(line: &str) -> Option<String> {
+    let location = line.split(" +0x").next()?;
+    let (file, number) = location.rsplit_once(':')?;
+    let valid = !file.is_empty()
+        && !file.contains(char::is_whitespace)
+        && !number.is_empty()
+        && number.chars().all(|c| c.is_ascii_digit());
+    valid.then(|| location.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PANIC: &str = "panic: runtime error: invalid memory address or nil pointer dereference [recovered]
+\tpanic: runtime error: invalid memory address or nil pointer dereference
+[signal SIGSEGV: segmentation violation code=0x1 addr=0x0 pc=0x6f2a1c]
+
+goroutine 42 [running]:
+github.com/labstack/echo/v4/middleware.RecoverWithConfig.func1.1()
+\t/go/pkg/mod/github.com/labstack/echo/v4@v4.11.4/middleware/recover.go:98 +0x1c
+panic({0x7a1e20?, 0xc3c1f0?})
+\t/usr/local/go/src/runtime/panic.go:770 +0x132
+runtime.panicmem(...)
+\t/usr/local/go/src/runtime/panic.go:261
+main.(*Handler).GetUser(0xc000123450, {0x7f2c, 0xc0001})
+\t/app/handler.go:42 +0x1c
+created by net/http.(*Server).Serve in goroutine 1
+\t/usr/local/go/src/net/http/server.go:3086 +0x5cb
+exit status 2
+{\"level\":\"INFO\",\"msg\":\"after\"}";
+
+    #[test]
+    fn test_panic_is_one_event() {
+        let mut folder = StackTraceFolder::new();
+        let mut folded: Vec<Folded> = PANIC.lines().flat_map(|l| folder.push(l)).collect();
+        folded.extend(folder.finish().map(Folded::Trace));
+        assert_eq!(folded.len(), 2);
+
+        let Folded::Trace(trace) = &folded[0] else {
+            panic!("expected a trace first: {:?}", folded[0]);
+        };
+        assert_eq!(trace.kind, "panic");
+        assert!(trace.message.ends_with("dereference [recovered]"));
+        assert_eq!(trace.goroutine.as_deref(), Some("goroutine 42 [running]"));
+        assert_eq!(trace.frames.len(), 5);
+        assert_eq!(trace.lines.len(), 16);
+        let origin = trace.origin().unwrap();
+        assert_eq!(origin.function, "main.(*Handler).GetUser");
+        assert_eq!(origin.location.as_deref(), Some("/app/handler.go:42"));
+        assert!(matches!(&folded[1], Folded::Line(l) if l.contains("after")));
+
+        let (line, entry) = trace.to_entry();
+        assert_eq!(entry.level, "ERROR");
+        assert_eq!(entry.func.as_deref(), Some("main.(*Handler).GetUser"));
+        assert!(line.contains("\"stack\":["));
+    }
+
+    #[test]
+    fn test_recovered_panics_and_ordinary_lines() {
+        let mut folder = StackTraceFolder::new();
+        assert!(matches!(&folder.push("⇨ http server started on [::]:1323")[..], [Folded::Line(_)]));
+        assert!(folder.push("2025/01/01 10:00:00 http: panic serving 127.0.0.1:50312: boom").is_empty());
+        assert!(folder.push("goroutine 7 [running]:").is_empty());
+        assert!(folder.push("main.handler(...)").is_empty());
+        let trace = folder.finish().unwrap();
+        assert_eq!((trace.kind.as_str(), trace.message.as_str()), ("panic", "boom"));
+        assert_eq!(trace.origin().map(|f| f.function.as_str()), Some("main.handler"));
+
+        let echo = "[PANIC RECOVER] index out of range goroutine 9 [running]:\nmain.list(0x1)\n\t/app/list.go:7 +0x2";
+        let trace = StackTrace::from_text(echo).unwrap();
+        assert_eq!(trace.message, "index out of range");
+        assert_eq!(trace.goroutine.as_deref(), Some("goroutine 9 [running]"));
+        assert_eq!(trace.origin().and_then(|f| f.location.as_deref()), Some("/app/list.go:7"));
+        assert!(StackTrace::from_text("panic: single line").is_none());
+
+        // A JSON panic entry is left whole for `embedded_stack_trace`
+        let entry = r#"{"level":"ERROR","msg":"[PANIC RECOVER] boom goroutine 9 [running]:\nmain.list(0x1)"}"#;
+        assert!(matches!(&folder.push_entry(entry)[..], [Folded::Line(_)]));
+    }
+}
@@ -0,0 +1,470 @@
+//! Display themes: prefixes, colours and borders used by all terminal output
+
+use crate::config::DisplayConfig;
+use colored::{Color, ColoredString, Colorize};
+
+/// Names of the built-in themes
+pub const THEME_NAMES: &[&str] = &["default", "ascii", "minimal", "high-contrast"];
+
+/// Colour names accepted in config
+pub const COLOR_NAMES: &[&str] = &[
+    "black",
+    "red",
+    "green",
+    "yellow",
+    "blue",
+    "magenta",
+    "cyan",
+    "white",
+    "bright_black",
+    "bright_red",
+    "bright_green",
+    "bright_yellow",
+    "bright_blue",
+    "bright_magenta",
+    "bright_cyan",
+    "bright_white",
+];
+
+/// A foreground colour plus emphasis
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Style {
+    pub fg: Option<Color>,
+    pub bold: bool,
+    pub dimmed: bool,
+}
+
+impl Style {
+    const fn fg(color: Color) -> Self {
+        Self {
+            fg: Some(color),
+            bold: false,
+            dimmed: false,
+        }
+    }
+
+    const fn plain() -> Self {
+        Self {
+            fg: None,
+            bold: false,
+            dimmed: false,
+        }
+    }
+
+    /// Foreground style for a name in `COLOR_NAMES`
+    pub fn named(name: &str) -> Option<Self> {
+        let color = match name.to_lowercase().as_str() {
+            "black" => Color::Black,
+            "red" => Color::Red,
+            "green" => Color::Green,
+            "yellow" => Color::Yellow,
+            "blue" => Color::Blue,
+            "magenta" => Color::Magenta,
+            "cyan" => Color::Cyan,
+            "white" => Color::White,
+            "bright_black" => Color::BrightBlack,
+            "bright_red" => Color::BrightRed,
+            "bright_green" => Color::BrightGreen,
+            "bright_yellow" => Color::BrightYellow,
+            "bright_blue" => Color::BrightBlue,
+            "bright_magenta" => Color::BrightMagenta,
+            "bright_cyan" => Color::BrightCyan,
+            "bright_white" => Color::BrightWhite,
+            _ => return None,
+        };
+        Some(Self::fg(color))
+    }
+
+    pub const fn bold(mut self) -> Self {
+        self.bold = true;
+        self
+    }
+
+    const fn dimmed(mut self) -> Self {
+        self.dimmed = true;
+        self
+    }
+
+    /// Apply this style to a piece of text
+    pub fn paint(&self, text: &str) -> ColoredString {
+        let mut out = ColoredString::from(text);
+        if let Some(color) = self.fg {
+            out = out.color(color);
+        }
+        if self.bold {
+            out = out.bold();
+        }
+        if self.dimmed {
+            out = out.dimmed();
+        }
+        out
+    }
+}
+
+/// Colours for every role the formatter and summaries use
+#[derive(Debug, Clone)]
+pub struct Palette {
+    pub error: Style,
+    pub error_strong: Style,
+    pub success: Style,
+    pub success_strong: Style,
+    pub warn: Style,
+    pub info: Style,
+    pub highlight: Style,
+    pub heading: Style,
+    pub section: Style,
+    pub accent: Style,
+    pub text: Style,
+    pub text_strong: Style,
+    pub dim: Style,
+    pub muted: Style,
+    pub sql: Style,
+    pub body: Style,
+    pub frame: Style,
+    pub border: Style,
+}
+
+/// Line and box characters
+#[derive(Debug, Clone)]
+pub struct Borders {
+    /// Thin horizontal rule (separators, summary underlines)
+    pub rule: String,
+    /// Heavy horizontal rule (banner)
+    pub heavy_rule: String,
+    pub box_top_left: String,
+    pub box_top_right: String,
+    pub box_bottom: String,
+    pub failure_top_left: String,
+    pub failure_top_right: String,
+    pub failure_bottom_left: String,
+    /// Marker in front of each failed test in the summary
+    pub fail_marker: String,
+    /// Marker in front of each passed feature in the breakdown
+    pub pass_marker: String,
+    /// Filled and empty cells of the progress bar
+    pub bar_filled: String,
+    pub bar_empty: String,
+    /// Marker in front of context lines, and the separator between context groups
+    pub context_marker: String,
+    pub context_separator: String,
+    /// Marks truncated text
+    pub ellipsis: String,
+    /// Points from an expected value to the actual one
+    pub arrow: String,
+    /// Between the parts of a status line, spaces included
+    pub separator: String,
+}
+
+/// Line prefixes
+#[derive(Debug, Clone)]
+pub struct Prefixes {
+    pub api: String,
+    pub karate: String,
+    pub sql: String,
+    pub error: String,
+    pub success: String,
+    pub body: String,
+    pub batch: String,
+    pub stats: String,
+    pub banner: String,
+    pub start: String,
+    pub wait: String,
+    pub info: String,
+    pub warn: String,
+    /// Counter in front of collapsed repeats, followed by the count
+    pub repeat: String,
+}
+
+/// A complete display theme
+#[derive(Debug, Clone)]
+pub struct Theme {
+    pub prefixes: Prefixes,
+    pub palette: Palette,
+    pub borders: Borders,
+}
+
+impl Theme {
+    /// Look up a built-in theme by one of `THEME_NAMES`
+    pub fn by_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "default" => Some(Self::default_theme()),
+            "ascii" => Some(Self::ascii()),
+            "minimal" => Some(Self::minimal()),
+            "high-contrast" => Some(Self::high_contrast()),
+            _ => None,
+        }
+    }
+
+    /// Build the theme selected in the display config, applying prefix overrides
+    pub fn from_config(config: &DisplayConfig) -> Self {
+        let mut theme = Self::by_name(&config.theme).unwrap_or_else(Self::default_theme);
+
+        let overrides = [
+            (&config.api_prefix, &mut theme.prefixes.api),
+            (&config.karate_prefix, &mut theme.prefixes.karate),
+            (&config.sql_prefix, &mut theme.prefixes.sql),
+            (&config.error_prefix, &mut theme.prefixes.error),
+            (&config.success_prefix, &mut theme.prefixes.success),
+        ];
+        for (value, slot) in overrides {
+            if let Some(value) = value {
+                *slot = value.clone();
+            }
+        }
+
+        theme
+    }
+
+    /// Emoji prefixes and box-drawing borders (the original look)
+    pub fn default_theme() -> Self {
+        Self {
+            prefixes: Prefixes {
+                api: "🔷".to_string(),
+                karate: "🔶".to_string(),
+                sql: "🗃️".to_string(),
+                error: "❌".to_string(),
+                success: "✅".to_string(),
+                body: "📄".to_string(),
+                batch: "📦".to_string(),
+                stats: "📊".to_string(),
+                banner: "🥋".to_string(),
+                start: "🚀".to_string(),
+                wait: "⏳".to_string(),
+                info: "ℹ️".to_string(),
+                warn: "⚠️".to_string(),
+                repeat: "🔁 ×".to_string(),
+            },
+            palette: Palette {
+                error: Style::fg(Color::Red),
+                error_strong: Style::fg(Color::Red).bold(),
+                success: Style::fg(Color::Green),
+                success_strong: Style::fg(Color::BrightGreen).bold(),
+                warn: Style::fg(Color::Yellow),
+                info: Style::fg(Color::BrightBlue),
+                highlight: Style::fg(Color::BrightYellow),
+                heading: Style::fg(Color::BrightCyan).bold(),
+                section: Style::fg(Color::BrightYellow),
+                accent: Style::fg(Color::BrightCyan),
+                text: Style::fg(Color::BrightWhite),
+                text_strong: Style::fg(Color::BrightWhite).bold(),
+                dim: Style::fg(Color::BrightWhite).dimmed(),
+                muted: Style::fg(Color::BrightBlack),
+                sql: Style::fg(Color::BrightBlue),
+                body: Style::fg(Color::Green),
+                frame: Style::fg(Color::BrightBlue),
+                border: Style::fg(Color::BrightYellow),
+            },
+            borders: Borders {
+                rule: "─".to_string(),
+                heavy_rule: "═".to_string(),
+                box_top_left: "┌─".to_string(),
+                box_top_right: "─┐".to_string(),
+                box_bottom: format!("└{}┘", "─".repeat(39)),
+                failure_top_left: "╔".to_string(),
+                failure_top_right: "╗".to_string(),
+                failure_bottom_left: "╚".to_string(),
+                fail_marker: "✗".to_string(),
+                pass_marker: "✓".to_string(),
+                bar_filled: "█".to_string(),
+                bar_empty: "░".to_string(),
+                context_marker: "┊".to_string(),
+                context_separator: "┄┄".to_string(),
+                ellipsis: "…".to_string(),
+                arrow: "→".to_string(),
+                separator: " · ".to_string(),
+            },
+        }
+    }
+
+    /// Plain ASCII prefixes and borders, same colours as the default theme
+    pub fn ascii() -> Self {
+        Self {
+            prefixes: Self::ascii_prefixes(),
+            borders: Self::ascii_borders("-", "="),
+            ..Self::default_theme()
+        }
+    }
+
+    /// Short prefixes, almost no colour, ASCII borders
+    pub fn minimal() -> Self {
+        let plain = Style::plain();
+        Self {
+            prefixes: Prefixes {
+                api: "api".to_string(),
+                karate: "krt".to_string(),
+                sql: "sql".to_string(),
+                error: "!".to_string(),
+                success: "".to_string(),
+                body: "body".to_string(),
+                batch: "batch".to_string(),
+                stats: "".to_string(),
+                banner: "".to_string(),
+                start: ">".to_string(),
+                wait: "..".to_string(),
+                info: "-".to_string(),
+                warn: "!".to_string(),
+                repeat: "x".to_string(),
+            },
+            palette: Palette {
+                error: Style::fg(Color::Red),
+                error_strong: Style::fg(Color::Red).bold(),
+                success: plain,
+                success_strong: plain.bold(),
+                warn: Style::fg(Color::Yellow),
+                info: plain,
+                highlight: plain,
+                heading: plain.bold(),
+                section: plain.bold(),
+                accent: plain,
+                text: plain,
+                text_strong: plain.bold(),
+                dim: plain,
+                muted: plain,
+                sql: plain,
+                body: plain,
+                frame: plain,
+                border: plain,
+            },
+            borders: Self::ascii_borders("-", "-"),
+        }
+    }
+
+    /// ASCII glyphs with bold, bright colours and no dimmed text
+    pub fn high_contrast() -> Self {
+        Self {
+            prefixes: Self::ascii_prefixes(),
+            palette: Palette {
+                error: Style::fg(Color::BrightRed).bold(),
+                error_strong: Style::fg(Color::BrightRed).bold(),
+                success: Style::fg(Color::BrightGreen).bold(),
+                success_strong: Style::fg(Color::BrightGreen).bold(),
+                warn: Style::fg(Color::BrightYellow).bold(),
+                info: Style::fg(Color::BrightCyan).bold(),
+                highlight: Style::fg(Color::BrightYellow).bold(),
+                heading: Style::fg(Color::BrightWhite).bold(),
+                section: Style::fg(Color::BrightYellow).bold(),
+                accent: Style::fg(Color::BrightCyan).bold(),
+                text: Style::fg(Color::BrightWhite),
+                text_strong: Style::fg(Color::BrightWhite).bold(),
+                dim: Style::fg(Color::White),
+                muted: Style::fg(Color::White),
+                sql: Style::fg(Color::BrightCyan),
+                body: Style::fg(Color::BrightGreen),
+                frame: Style::fg(Color::BrightWhite).bold(),
+                border: Style::fg(Color::BrightYellow).bold(),
+            },
+            borders: Self::ascii_borders("-", "="),
+        }
+    }
+
+    fn ascii_prefixes() -> Prefixes {
+        Prefixes {
+            api: "[API]".to_string(),
+            karate: "[KRT]".to_string(),
+            sql: "[SQL]".to_string(),
+            error: "[ERR]".to_string(),
+            success: "[OK]".to_string(),
+            body: "[BODY]".to_string(),
+            batch: "[BATCH]".to_string(),
+            stats: "[STATS]".to_string(),
+            banner: "[KARATE]".to_string(),
+            start: "[START]".to_string(),
+            wait: "[WAIT]".to_string(),
+            info: "[INFO]".to_string(),
+            warn: "[WARN]".to_string(),
+            repeat: "[REPEAT] x".to_string(),
+        }
+    }
+
+    fn ascii_borders(rule: &str, heavy_rule: &str) -> Borders {
+        Borders {
+            rule: rule.to_string(),
+            heavy_rule: heavy_rule.to_string(),
+            box_top_left: "+-".to_string(),
+            box_top_right: "-+".to_string(),
+            box_bottom: format!("+{}+", "-".repeat(39)),
+            failure_top_left: "#".to_string(),
+            failure_top_right: "#".to_string(),
+            failure_bottom_left: "#".to_string(),
+            fail_marker: "x".to_string(),
+            pass_marker: "+".to_string(),
+            bar_filled: "#".to_string(),
+            bar_empty: "-".to_string(),
+            context_marker: ":".to_string(),
+            context_separator: "--".to_string(),
+            ellipsis: "...".to_string(),
+            arrow: "->".to_string(),
+            separator: " | ".to_string(),
+        }
+    }
+
+    /// A thin rule of the given width
+    pub fn rule(&self, width: usize) -> String {
+        self.borders.rule.repeat(width)
+    }
+
+    /// A heavy (banner) rule of the given width
+    pub fn heavy_rule(&self, width: usize) -> String {
+        self.borders.heavy_rule.repeat(width)
+    }
+
+    /// Join prefix and text with a space, dropping the space for empty prefixes
+    pub fn prefixed(prefix: &str, text: &str) -> String {
+        if prefix.is_empty() {
+            text.to_string()
+        } else {
+            format!("{} {}", prefix, text)
+        }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::default_theme()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_themes_resolve() {
+        for name in THEME_NAMES {
+            assert!(Theme::by_name(name).is_some(), "{}", name);
+        }
+        assert!(Theme::by_name("neon").is_none());
+        assert!(Theme::by_name("high_contrast").is_none());
+    }
+
+    #[test]
+    fn test_ascii_theme_has_no_non_ascii_glyphs() {
+        for theme in [Theme::ascii(), Theme::minimal(), Theme::high_contrast()] {
+            let p = &theme.prefixes;
+            let b = &theme.borders;
+            let glyphs = [
+                &p.api, &p.karate, &p.sql, &p.error, &p.success, &p.body, &p.batch, &p.stats,
+                &p.banner, &p.start, &p.wait, &p.info, &p.warn, &p.repeat, &b.rule, &b.heavy_rule,
+                &b.box_top_left, &b.box_top_right, &b.box_bottom, &b.failure_top_left,
+                &b.failure_top_right, &b.failure_bottom_left, &b.fail_marker, &b.pass_marker,
+                &b.bar_filled, &b.bar_empty, &b.context_marker, &b.context_separator, &b.ellipsis,
+                &b.arrow, &b.separator,
+            ];
+            for glyph in glyphs {
+                assert!(glyph.is_ascii(), "{:?}", glyph);
+            }
+        }
+    }
+
+    #[test]
+    fn test_prefix_overrides_apply() {
+        let config = DisplayConfig {
+            theme: "ascii".to_string(),
+            api_prefix: Some("API>".to_string()),
+            ..Default::default()
+        };
+        let theme = Theme::from_config(&config);
+        assert_eq!(theme.prefixes.api, "API>");
+        assert_eq!(theme.prefixes.sql, "[SQL]");
+    }
+}
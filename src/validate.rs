@@ -0,0 +1,569 @@
+//! Configuration validation with key paths and source line numbers
+
+use crate::classify::{API_TYPES, KARATE_TYPES};
+use crate::config::{ClassifyRule, Config, SourceFilterConfig, OPTIONAL_KEYS};
+use crate::filter_expr::FilterExpr;
+use crate::schema::{DURATION_UNITS, ENTRY_FIELDS, LINE_FORMATS};
+use crate::theme::{Theme, COLOR_NAMES, THEME_NAMES};
+use regex::Regex;
+use serde_json::Value;
+use std::fmt;
+
+const LEVELS: &[&str] = &["ALL", "DEBUG", "INFO", "WARN", "ERROR"];
+const EXPORT_FORMATS: &[&str] = &["json", "text", "txt", "both"];
+const SQL_STYLES: &[&str] = &["pretty", "compact", "raw"];
+const REDACTION_PRESETS: &[&str] = &["default", "none"];
+const RULE_KEYS: &[&str] = &["type", "when", "patterns", "prefix", "color"];
+
+/// A problem found in the configuration
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigIssue {
+    /// Key path such as `filters.sql.level` or `logging.include_patterns[1]`
+    pub path: String,
+    pub message: String,
+    /// 1-based line in the config file, when it could be located
+    pub line: Option<usize>,
+}
+
+impl fmt::Display for ConfigIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "{} (line {}): {}", self.path, line, self.message),
+            None => write!(f, "{}: {}", self.path, self.message),
+        }
+    }
+}
+
+/// Check every setting, returning all problems found
+///
+/// `source` is the config file text, used to report line numbers; keys set by
+/// `profile` are located in its `[profiles.<name>]` tables first.
+pub fn validate_config(config: &Config, source: Option<&str>, profile: Option<&str>) -> Vec<ConfigIssue> {
+    let mut v = Validator {
+        issues: Vec::new(),
+        source,
+        profile,
+    };
+
+    if config.api.health_timeout_secs == 0 {
+        v.error("api.health_timeout_secs", "must be greater than 0");
+    }
+    if config.api.health_interval_secs == 0 {
+        v.error("api.health_interval_secs", "must be greater than 0");
+    }
+    if let Err(e) = url::Url::parse(&config.api.health_url) {
+        v.error("api.health_url", format!("invalid URL: {}", e));
+    }
+    if config.karate.threads == 0 {
+        v.error("karate.threads", "must be at least 1");
+    }
+
+    let logging = &config.logging;
+    v.one_of("logging.level", &logging.level, LEVELS);
+    v.regexes("logging.include_patterns", &logging.include_patterns);
+    v.regexes("logging.exclude_patterns", &logging.exclude_patterns);
+    if let Some(filter) = &logging.filter {
+        v.filter_expr("logging.filter", filter);
+    }
+    v.one_of("logging.export_format", &logging.export_format, EXPORT_FORMATS);
+    if logging.rate_limit > 0 && logging.rate_limit_window_secs == 0 {
+        v.error("logging.rate_limit_window_secs", "must be greater than 0 when rate_limit is set");
+    }
+
+    let display = &config.display;
+    v.one_of("display.theme", &display.theme, THEME_NAMES);
+    v.one_of("display.sql_style", &display.sql_style, SQL_STYLES);
+    if display.body_max_items == 0 {
+        v.error("display.body_max_items", "must be greater than 0");
+    }
+    if display.body_max_string_len == 0 {
+        v.error("display.body_max_string_len", "must be greater than 0");
+    }
+    if display.slow_query_ms.is_nan() || display.slow_query_ms < 0.0 {
+        v.error("display.slow_query_ms", "must be 0 or more");
+    }
+
+    let redaction = &config.redaction;
+    v.one_of("redaction.preset", &redaction.preset, REDACTION_PRESETS);
+    v.regexes("redaction.patterns", &redaction.patterns);
+    for (i, path) in redaction.paths.iter().enumerate() {
+        if path.trim().trim_start_matches('$').trim_start_matches('.').is_empty() {
+            v.error(format!("redaction.paths[{}]", i), "path selects nothing");
+        }
+    }
+
+    if config.progress.status_interval_secs == 0 {
+        v.error("progress.status_interval_secs", "must be greater than 0");
+    }
+
+    let schema = &config.schema;
+    v.one_of("schema.format", &schema.format, LINE_FORMATS);
+    match &schema.pattern {
+        Some(pattern) => {
+            if let Err(e) = Regex::new(pattern) {
+                v.error("schema.pattern", format!("invalid regex\n{}", e));
+            }
+        }
+        None if schema.format.eq_ignore_ascii_case("regex") => {
+            v.error("schema.format", "\"regex\" requires schema.pattern");
+        }
+        None => {}
+    }
+    for field in schema.fields.keys() {
+        v.entry_field(&format!("schema.fields.{}", field), field);
+    }
+    for (from, level) in &schema.levels {
+        v.one_of(&format!("schema.levels.{}", from), level, &LEVELS[1..]);
+    }
+    for (field, unit) in &schema.duration_units {
+        let path = format!("schema.duration_units.{}", field);
+        v.entry_field(&path, field);
+        v.one_of(&path, unit, DURATION_UNITS);
+    }
+
+    for (i, rule) in config.classify.api.iter().enumerate() {
+        v.classify_rule(&format!("classify.api[{}]", i), rule, API_TYPES);
+    }
+    for (i, rule) in config.classify.karate.iter().enumerate() {
+        v.classify_rule(&format!("classify.karate[{}]", i), rule, KARATE_TYPES);
+    }
+
+    let filters = &config.filters;
+    for (name, source) in [
+        ("api", &filters.api),
+        ("sql", &filters.sql),
+        ("body", &filters.body),
+        ("batch", &filters.batch),
+        ("karate", &filters.karate),
+    ] {
+        v.source_filter(name, source);
+    }
+
+    v.issues
+}
+
+/// Report keys in a config file (and its profiles) that no setting reads, e.g. misspellings
+///
+/// `raw` is the file merged with its includes; `source` locates the keys.
+pub fn unknown_keys(raw: &Value, source: Option<&str>) -> Vec<ConfigIssue> {
+    let mut v = Validator {
+        issues: Vec::new(),
+        source,
+        profile: None,
+    };
+    let known = serde_json::to_value(Config::default()).unwrap_or_default();
+    let mut raw = raw.clone();
+    let profiles = raw.as_object_mut().and_then(|root| root.remove("profiles"));
+    v.unknown_keys(&known, &raw, "", "");
+    for (name, overlay) in profiles.iter().filter_map(Value::as_object).flatten() {
+        v.unknown_keys(&known, overlay, &format!("profiles.{}", name), "");
+    }
+    v.issues
+}
+
+/// Point issues for keys set through environment variables at the variable, not the file
+pub fn mark_env_overrides(issues: &mut [ConfigIssue], keys: &[String]) {
+    for issue in issues {
+        let key = issue.path.split('[').next().unwrap_or(&issue.path);
+        if keys.iter().any(|k| k == key) {
+            issue.line = None;
+            let var = format!(
+                "{}{}",
+                crate::config::ENV_PREFIX,
+                key.to_ascii_uppercase().replace('.', "__")
+            );
+            // Keep the note on the first line; regex errors continue below it
+            issue.message = match issue.message.split_once('\n') {
+                Some((first, rest)) => format!("{} (set by {})\n{}", first, var, rest),
+                None => format!("{} (set by {})", issue.message, var),
+            };
+        }
+    }
+}
+
+/// Print issues to stderr, one per key path, with continuation lines indented
+pub fn print_report(issues: &[ConfigIssue], origin: &str, theme: &Theme) {
+    let palette = &theme.palette;
+    eprintln!(
+        "{} {}",
+        palette.error.paint(&theme.prefixes.error),
+        palette.error_strong.paint(&format!(
+            "Configuration has {} problem{} ({})",
+            issues.len(),
+            if issues.len() == 1 { "" } else { "s" },
+            origin
+        ))
+    );
+    for issue in issues {
+        let mut lines = issue.to_string().lines().map(str::to_string).collect::<Vec<_>>().into_iter();
+        if let Some(first) = lines.next() {
+            eprintln!("  {} {}", palette.error.paint(&theme.borders.fail_marker), first);
+        }
+        for line in lines {
+            eprintln!("      {}", palette.muted.paint(&line));
+        }
+    }
+}
+
+struct Validator<'a> {
+    issues: Vec<ConfigIssue>,
+    source: Option<&'a str>,
+    profile: Option<&'a str>,
+}
+
+impl Validator<'_> {
+    fn error(&mut self, path: impl Into<String>, message: impl Into<String>) {
+        let path = path.into();
+        let line = self.source.and_then(|source| {
+            self.profile
+                .and_then(|profile| locate(source, &format!("profiles.{}.{}", profile, path)))
+                .or_else(|| locate(source, &path))
+        });
+        self.issues.push(ConfigIssue {
+            path,
+            message: message.into(),
+            line,
+        });
+    }
+
+    fn one_of(&mut self, path: &str, value: &str, allowed: &[&str]) {
+        if !allowed.iter().any(|a| a.eq_ignore_ascii_case(value)) {
+            self.error(
+                path,
+                format!("unknown value \"{}\" (expected one of: {})", value, allowed.join(", ")),
+            );
+        }
+    }
+
+    /// Entry field names are matched exactly, as the schema uses them
+    fn entry_field(&mut self, path: &str, field: &str) {
+        if !ENTRY_FIELDS.contains(&field) {
+            self.error(
+                path,
+                format!("unknown field \"{}\" (expected one of: {})", field, ENTRY_FIELDS.join(", ")),
+            );
+        }
+    }
+
+    fn regexes(&mut self, path: &str, patterns: &[String]) {
+        for (i, pattern) in patterns.iter().enumerate() {
+            if let Err(e) = Regex::new(pattern) {
+                // The regex error already quotes the pattern with a caret
+                self.error(format!("{}[{}]", path, i), format!("invalid regex\n{}", e));
+            }
+        }
+    }
+
+    /// Walk `raw` alongside the defaults; `path` is reported, `setting` is the key inside `Config`
+    fn unknown_keys(&mut self, known: &Value, raw: &Value, path: &str, setting: &str) {
+        let join = |base: &str, key: &str| {
+            if base.is_empty() {
+                key.to_string()
+            } else {
+                format!("{}.{}", base, key)
+            }
+        };
+        for (key, value) in raw.as_object().into_iter().flatten() {
+            let (path, setting) = (join(path, key), join(setting, key));
+            if let Some(known) = known.get(key) {
+                self.unknown_keys(known, value, &path, &setting);
+            } else if setting == "classify.api" || setting == "classify.karate" {
+                for (i, rule) in value.as_array().into_iter().flatten().enumerate() {
+                    for key in rule.as_object().into_iter().flatten().map(|(k, _)| k) {
+                        if !RULE_KEYS.contains(&key.as_str()) {
+                            self.unknown_key(format!("{}[{}].{}", path, i, key), key, RULE_KEYS);
+                        }
+                    }
+                }
+            } else if !OPTIONAL_KEYS.iter().any(|(k, _)| *k == setting) {
+                let parent = &setting[..setting.len() - key.len()];
+                let siblings: Vec<&str> = known
+                    .as_object()
+                    .into_iter()
+                    .flatten()
+                    .map(|(k, _)| k.as_str())
+                    .chain(OPTIONAL_KEYS.iter().filter_map(|(k, _)| k.strip_prefix(parent)))
+                    .filter(|k| !k.contains('.'))
+                    .collect();
+                self.unknown_key(path, key, &siblings);
+            }
+        }
+    }
+
+    fn unknown_key(&mut self, path: String, key: &str, known: &[&str]) {
+        match known.iter().find(|k| edit_distance(k, key) <= 2) {
+            Some(similar) => self.error(path, format!("unknown key (did you mean \"{}\"?)", similar)),
+            None => self.error(path, "unknown key"),
+        }
+    }
+
+    fn filter_expr(&mut self, path: &str, expression: &str) {
+        if let Err(e) = FilterExpr::parse(expression) {
+            self.error(path, format!("invalid filter expression: {}", e.render(expression)));
+        }
+    }
+
+    /// API rules match entries with `when`; Karate rules match lines with `patterns`
+    fn classify_rule(&mut self, path: &str, rule: &ClassifyRule, types: &[&str]) {
+        self.one_of(&format!("{}.type", path), &rule.log_type, types);
+        let karate = types == KARATE_TYPES;
+        match (&rule.when, karate) {
+            (Some(when), false) => self.filter_expr(&format!("{}.when", path), when),
+            (None, false) => self.error(path, "API rules require a `when` filter expression"),
+            (Some(_), true) => self.error(
+                format!("{}.when", path),
+                "Karate rules match lines; use patterns instead of a filter expression",
+            ),
+            (None, true) => {}
+        }
+        match (rule.patterns.is_empty(), karate) {
+            (false, true) => self.regexes(&format!("{}.patterns", path), &rule.patterns),
+            (true, true) => self.error(path, "Karate rules require at least one pattern"),
+            (false, false) => self.error(
+                format!("{}.patterns", path),
+                "API rules match entries; use a `when` filter expression instead of patterns",
+            ),
+            (true, false) => {}
+        }
+        if let Some(color) = &rule.color {
+            self.one_of(&format!("{}.color", path), color, COLOR_NAMES);
+        }
+    }
+
+    fn source_filter(&mut self, name: &str, source: &SourceFilterConfig) {
+        let path = format!("filters.{}", name);
+        if let Some(level) = &source.level {
+            self.one_of(&format!("{}.level", path), level, LEVELS);
+        }
+        if let Some(patterns) = &source.include_patterns {
+            self.regexes(&format!("{}.include_patterns", path), patterns);
+        }
+        if let Some(patterns) = &source.exclude_patterns {
+            self.regexes(&format!("{}.exclude_patterns", path), patterns);
+        }
+        if let Some(filter) = &source.filter {
+            if name == "karate" {
+                self.error(
+                    format!("{}.filter", path),
+                    "filter expressions apply to API fields; use level and patterns for Karate",
+                );
+            } else {
+                self.filter_expr(&format!("{}.filter", path), filter);
+            }
+        }
+    }
+}
+
+/// Levenshtein distance between two keys
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitute = previous[j] + usize::from(ca != *cb);
+            current.push(substitute.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+/// Find the line defining a key path in TOML, YAML or JSON source
+fn locate(source: &str, path: &str) -> Option<usize> {
+    let path = path.split('[').next().unwrap_or(path);
+    let (section, key) = path.rsplit_once('.').unwrap_or(("", path));
+
+    if source.trim_start().starts_with('{') {
+        // JSON: the key after its parent object's key
+        let parent = section.rsplit('.').next().unwrap_or("");
+        let quoted_key = format!("\"{}\"", key);
+        let quoted_parent = format!("\"{}\"", parent);
+        let mut in_parent = parent.is_empty();
+        for (i, line) in source.lines().enumerate() {
+            if !in_parent && line.contains(&quoted_parent) {
+                in_parent = true;
+                if !line.contains(&quoted_key) {
+                    continue;
+                }
+            }
+            if in_parent && line.contains(&quoted_key) {
+                return Some(i + 1);
+            }
+        }
+        return None;
+    }
+
+    // TOML: `key = ...` inside the `[section]` table
+    let mut current = String::new();
+    for (i, line) in source.lines().enumerate() {
+        let trimmed = line.trim();
+        if let Some(header) = trimmed.strip_prefix('[').and_then(|h| h.split(']').next()) {
+            current = header.trim().to_string();
+            continue;
+        }
+        if current == section {
+            if let Some(rest) = trimmed.strip_prefix(key) {
+                if rest.trim_start().starts_with('=') {
+                    return Some(i + 1);
+                }
+            }
+        }
+    }
+
+    // YAML: `key:` nested under its parents by indentation
+    let mut parents: Vec<(usize, &str)> = Vec::new();
+    for (i, line) in source.lines().enumerate() {
+        let trimmed = line.trim_start();
+        let Some((yaml_key, _)) = trimmed.split_once(':') else {
+            continue;
+        };
+        let yaml_key = yaml_key.trim_matches(|c| c == '"' || c == '\'');
+        if trimmed.starts_with(['#', '-']) || yaml_key.is_empty() || yaml_key.contains(' ') {
+            continue;
+        }
+        let indent = line.len() - trimmed.len();
+        while parents.last().is_some_and(|(level, _)| *level >= indent) {
+            parents.pop();
+        }
+        parents.push((indent, yaml_key));
+        if parents.iter().map(|(_, k)| *k).collect::<Vec<_>>().join(".") == path {
+            return Some(i + 1);
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reports_every_issue_with_lines() {
+        let source = r#"
+[logging]
+level = "VERBOSE"
+include_patterns = ["ok", "(unclosed"]
+export_format = "xml"
+
+[filters.sql]
+level = "ERROR"
+filter = "status >="
+"#;
+        let config: Config = toml::from_str(source).unwrap();
+        let issues = validate_config(&config, Some(source), None);
+
+        let paths: Vec<(&str, Option<usize>)> =
+            issues.iter().map(|i| (i.path.as_str(), i.line)).collect();
+        assert_eq!(
+            paths,
+            vec![
+                ("logging.level", Some(3)),
+                ("logging.include_patterns[1]", Some(4)),
+                ("logging.export_format", Some(5)),
+                ("filters.sql.filter", Some(9)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unknown_keys() {
+        let source = r#"
+[logging]
+levl = "INFO"
+
+[schema.fields]
+msg = ["message"]
+
+[[classify.api]]
+type = "sql"
+wen = "msg ~ 'DB'"
+
+[profiles.ci.display]
+theme = "plain"
+colour = true
+"#;
+        let raw = serde_json::to_value(toml::from_str::<toml::Value>(source).unwrap()).unwrap();
+        let issues = unknown_keys(&raw, Some(source));
+        let found: Vec<(&str, &str, Option<usize>)> = issues
+            .iter()
+            .map(|i| (i.path.as_str(), i.message.as_str(), i.line))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                ("classify.api[0].wen", "unknown key (did you mean \"when\"?)", None),
+                ("logging.levl", "unknown key (did you mean \"level\"?)", Some(3)),
+                ("profiles.ci.display.colour", "unknown key", Some(14)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_default_config_is_valid() {
+        assert!(validate_config(&Config::default(), None, None).is_empty());
+    }
+
+    #[test]
+    fn test_yaml_locations() {
+        let source = "logging:\n  level: INFO\nfilters:\n  sql:\n    level: LOUD\n";
+        let config: Config = serde_yaml_ng::from_str(source).unwrap();
+        let issues = validate_config(&config, Some(source), None);
+        assert_eq!(issues.len(), 1);
+        assert_eq!((issues[0].path.as_str(), issues[0].line), ("filters.sql.level", Some(5)));
+    }
+
+    #[test]
+    fn test_schema_mapping() {
+        let source = "[schema.fields]\nmessage = [\"msg\"]\nMSG = [\"m\"]\n\n[schema.duration_units]\nelapsed = \"ticks\"\n";
+        let config: Config = toml::from_str(source).unwrap();
+        let issues = validate_config(&config, Some(source), None);
+        let paths: Vec<(&str, Option<usize>)> = issues.iter().map(|i| (i.path.as_str(), i.line)).collect();
+        assert_eq!(
+            paths,
+            vec![
+                ("schema.fields.MSG", Some(3)),
+                ("schema.fields.message", Some(2)),
+                ("schema.duration_units.elapsed", Some(6)),
+            ]
+        );
+
+        let source = "[schema]\nformat = \"regex\"\n";
+        let config: Config = toml::from_str(source).unwrap();
+        let issues = validate_config(&config, Some(source), None);
+        assert_eq!(issues.len(), 1);
+        assert_eq!((issues[0].path.as_str(), issues[0].line), ("schema.format", Some(2)));
+    }
+
+    #[test]
+    fn test_classify_rules() {
+        let source = r#"[classify]
+api = [{ type = "sql", when = 'msg ~ "DB"' }, { type = "query", patterns = ["^DB"], color = "teal" }]
+karate = [{ type = "batch", patterns = ["("] }, { type = "info", when = "level == INFO" }]
+"#;
+        let config: Config = toml::from_str(source).unwrap();
+        let issues = validate_config(&config, Some(source), None);
+        let paths: Vec<&str> = issues.iter().map(|i| i.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            vec![
+                "classify.api[1].type",
+                "classify.api[1]",
+                "classify.api[1].patterns",
+                "classify.api[1].color",
+                "classify.karate[0].patterns[0]",
+                "classify.karate[1].when",
+                "classify.karate[1]",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_json_locations() {
+        let source = "{\n  \"display\": {\n    \"theme\": \"neon\"\n  }\n}";
+        let config: Config = serde_json::from_str(source).unwrap();
+        let issues = validate_config(&config, Some(source), None);
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].to_string(), "display.theme (line 3): unknown value \"neon\" (expected one of: default, ascii, minimal, high-contrast)");
+    }
+}